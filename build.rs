@@ -0,0 +1,63 @@
+//! Build script capturing version information for `TrayIcon.get_version()`.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let ksni_version = find_cargo_lock()
+        .and_then(|lock| locked_version(&lock, "ksni"))
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GODOT_KSNI_KSNI_VERSION={}", ksni_version);
+
+    // Only read the hash of godot-ksni's own checkout, not of an enclosing project.
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let git_dir = Path::new(&manifest_dir).join(".git");
+    let mut git_hash = String::new();
+    if git_dir.exists() {
+        println!("cargo:rerun-if-changed={}", git_dir.join("HEAD").display());
+        git_hash = Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .current_dir(&manifest_dir)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .unwrap_or_default();
+    }
+    println!("cargo:rustc-env=GODOT_KSNI_GIT_HASH={}", git_hash);
+}
+
+/// Finds the `Cargo.lock` of the workspace being built.
+///
+/// The lock file lives in the workspace root, which is an ancestor of `OUT_DIR` when the
+/// default target directory is used, or of the manifest directory otherwise.
+fn find_cargo_lock() -> Option<PathBuf> {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").ok()?);
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").ok()?);
+
+    out_dir
+        .ancestors()
+        .chain(manifest_dir.ancestors())
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|lock| lock.is_file())
+}
+
+/// Returns the locked version of a package from a `Cargo.lock` file.
+fn locked_version(lock: &Path, package: &str) -> Option<String> {
+    println!("cargo:rerun-if-changed={}", lock.display());
+
+    let contents = fs::read_to_string(lock).ok()?;
+    let name_line = format!("name = \"{}\"", package);
+    let mut lines = contents.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() == name_line {
+            let version = lines.next()?.trim().strip_prefix("version = ")?;
+            return Some(version.trim_matches('"').to_string());
+        }
+    }
+    None
+}
@@ -0,0 +1,155 @@
+//! Error type of the Rust API.
+//!
+//! Fallible functions in `tray/` and `menu/` return [`Error`]. The GDScript API reports
+//! the same errors as integer codes (the `ERR_*` constants on `TrayIcon`), which are
+//! defined here so the two layers cannot diverge.
+
+/// Errors returned by the Rust API of godot-ksni.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The ksni tray service could not be spawned, usually because of a D-Bus error.
+    #[error("failed to spawn tray: {0}")]
+    SpawnFailed(#[from] ksni::Error),
+    /// The operation requires a spawned tray.
+    #[error("tray is not spawned")]
+    NotSpawned,
+    /// The tray is already spawned.
+    #[error("tray is already spawned")]
+    AlreadySpawned,
+    /// Tray icons are not supported on this platform.
+    #[error("tray icons are not supported on this platform")]
+    Unsupported,
+    /// No menu item with the given ID exists.
+    #[error("menu item not found: {id}")]
+    ItemNotFound {
+        /// The ID that was looked up.
+        id: String,
+    },
+    /// Pixel data does not match the given dimensions.
+    #[error("invalid icon data: expected {expected} bytes, got {got}")]
+    InvalidIconData {
+        /// Number of bytes the dimensions require.
+        expected: usize,
+        /// Number of bytes given.
+        got: usize,
+    },
+    /// The row stride of pixel data is shorter than a row of pixels.
+    #[error("invalid icon stride: {stride} bytes is less than a row of {row_bytes} bytes")]
+    InvalidIconStride {
+        /// The given stride, in bytes.
+        stride: usize,
+        /// Number of bytes a row of pixels takes.
+        row_bytes: usize,
+    },
+    /// Icon dimensions are not positive, or exceed [`MAX_ICON_PIXELS`] pixels.
+    ///
+    /// [`MAX_ICON_PIXELS`]: crate::tray::icon::MAX_ICON_PIXELS
+    #[error("invalid icon size: {width}x{height}")]
+    InvalidIconSize {
+        /// The given width.
+        width: i32,
+        /// The given height.
+        height: i32,
+    },
+    /// No StatusNotifierWatcher runs on the session bus, so the tray cannot be shown.
+    #[error("no StatusNotifierWatcher found")]
+    NoWatcher,
+    /// A menu item with the given ID already exists.
+    #[error("duplicate menu item id: {id}")]
+    DuplicateId {
+        /// The duplicated ID.
+        id: String,
+    },
+    /// No kept-alive tray with the given ID exists.
+    #[error("no kept-alive tray with id: {tray_id}")]
+    KeptAliveNotFound {
+        /// The tray ID that was looked up.
+        tray_id: String,
+    },
+    /// The kept-alive tray is owned by another node.
+    #[error("tray is already owned by another node: {tray_id}")]
+    AlreadyAdopted {
+        /// The tray ID that was looked up.
+        tray_id: String,
+    },
+    /// The thread spawning the tray service panicked, e.g. in a `ksni::Tray` method.
+    #[error("tray spawn thread panicked")]
+    SpawnPanicked,
+    /// The tray did not finish registering with the StatusNotifierWatcher in time.
+    #[error("timed out waiting for the tray to register")]
+    Timeout,
+    /// The tray ID has no characters usable in a D-Bus name.
+    #[error("invalid tray id: {tray_id:?}")]
+    InvalidTrayId {
+        /// The rejected tray ID.
+        tray_id: String,
+    },
+    /// A TOML tray configuration could not be parsed.
+    #[cfg(feature = "toml")]
+    #[error("invalid TOML configuration: {0}")]
+    Toml(#[from] toml::de::Error),
+}
+
+impl Error {
+    /// Code reported to GDScript when no error occurred.
+    pub const OK: i64 = 0;
+    /// Code of [`Error::SpawnFailed`].
+    pub const SPAWN_FAILED: i64 = 1;
+    /// Code of [`Error::NotSpawned`].
+    pub const NOT_SPAWNED: i64 = 2;
+    /// Code of [`Error::AlreadySpawned`].
+    pub const ALREADY_SPAWNED: i64 = 3;
+    /// Code of [`Error::Unsupported`].
+    pub const UNSUPPORTED: i64 = 4;
+    /// Code of [`Error::ItemNotFound`].
+    pub const ITEM_NOT_FOUND: i64 = 5;
+    /// Code of [`Error::InvalidIconData`].
+    pub const INVALID_ICON_DATA: i64 = 6;
+    /// Code of [`Error::DuplicateId`].
+    pub const DUPLICATE_ID: i64 = 7;
+    /// Code of [`Error::KeptAliveNotFound`].
+    pub const KEPT_ALIVE_NOT_FOUND: i64 = 8;
+    /// Code of [`Error::AlreadyAdopted`].
+    pub const ALREADY_ADOPTED: i64 = 9;
+    /// Code of [`Error::Toml`].
+    pub const TOML: i64 = 10;
+    /// Code of [`Error::Timeout`].
+    pub const TIMEOUT: i64 = 11;
+    /// Code of [`Error::InvalidTrayId`].
+    pub const INVALID_TRAY_ID: i64 = 12;
+    /// Code of [`Error::InvalidIconStride`].
+    pub const INVALID_ICON_STRIDE: i64 = 13;
+    /// Code of [`Error::NoWatcher`].
+    pub const NO_WATCHER: i64 = 14;
+    /// Code of [`Error::InvalidIconSize`].
+    pub const INVALID_ICON_SIZE: i64 = 15;
+    /// Code of [`Error::SpawnPanicked`].
+    pub const SPAWN_PANICKED: i64 = 16;
+
+    /// Returns the integer code of this error, as reported to GDScript.
+    pub fn code(&self) -> i64 {
+        match self {
+            Error::SpawnFailed(_) => Self::SPAWN_FAILED,
+            Error::NotSpawned => Self::NOT_SPAWNED,
+            Error::AlreadySpawned => Self::ALREADY_SPAWNED,
+            Error::Unsupported => Self::UNSUPPORTED,
+            Error::ItemNotFound { .. } => Self::ITEM_NOT_FOUND,
+            Error::InvalidIconData { .. } => Self::INVALID_ICON_DATA,
+            Error::DuplicateId { .. } => Self::DUPLICATE_ID,
+            Error::KeptAliveNotFound { .. } => Self::KEPT_ALIVE_NOT_FOUND,
+            Error::AlreadyAdopted { .. } => Self::ALREADY_ADOPTED,
+            Error::Timeout => Self::TIMEOUT,
+            Error::InvalidTrayId { .. } => Self::INVALID_TRAY_ID,
+            Error::InvalidIconStride { .. } => Self::INVALID_ICON_STRIDE,
+            Error::NoWatcher => Self::NO_WATCHER,
+            Error::InvalidIconSize { .. } => Self::INVALID_ICON_SIZE,
+            Error::SpawnPanicked => Self::SPAWN_PANICKED,
+            #[cfg(feature = "toml")]
+            Error::Toml(_) => Self::TOML,
+        }
+    }
+}
+
+/// Result type of the Rust API of godot-ksni.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
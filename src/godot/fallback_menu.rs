@@ -0,0 +1,143 @@
+//! Godot `PopupMenu` fallback for hosts without dbusmenu support.
+//!
+//! This module builds a `PopupMenu` from the same `MenuItemData` tree that is exported
+//! over dbusmenu. Each clickable entry gets a unique PopupMenu ID that maps to a
+//! [`FallbackAction`], which `TrayIcon` routes through the same `TrayState` click
+//! handlers as the ksni menu, so the resulting signals are identical.
+
+use crate::menu::item::MenuItemData;
+use crate::tray::state::TrayState;
+use godot::classes::PopupMenu;
+use godot::prelude::*;
+
+/// What clicking an entry of the fallback menu does.
+pub enum FallbackAction {
+    /// Activates a standard item, emitting the given ID.
+    Standard { id: String, emitted_id: String },
+    /// Toggles a checkmark item.
+    Checkmark { id: String },
+    /// Selects an option of a radio group.
+    Radio { group_id: String, index: usize },
+}
+
+impl FallbackAction {
+    /// Performs the click on the tray state, sending the resulting event.
+    pub fn run(&self, state: &mut TrayState) {
+        match self {
+            FallbackAction::Standard { id, emitted_id } => state.click_standard(id, emitted_id),
+            FallbackAction::Checkmark { id } => state.click_checkmark(id),
+            FallbackAction::Radio { group_id, index } => state.click_radio(group_id, *index),
+        }
+    }
+}
+
+/// Fills `popup` with the given items, appending one action per clickable entry.
+///
+/// The PopupMenu ID of an entry is its index in `actions`. Submenus are added as
+/// child PopupMenus, and `on_id_pressed` is connected to every menu created here.
+pub fn build(
+    state: &TrayState,
+    items: &[&MenuItemData],
+    popup: &mut Gd<PopupMenu>,
+    on_id_pressed: &Callable,
+    actions: &mut Vec<FallbackAction>,
+    path_prefix: &str,
+) {
+    // Checkable items stay open in Godot by default, unlike dbusmenu hosts.
+    popup.set_hide_on_checkable_item_selection(true);
+    popup.connect("id_pressed", on_id_pressed);
+
+    for item in items {
+        match item {
+            MenuItemData::Standard {
+                id,
+                label,
+                enabled,
+                visible: true,
+                ..
+            } => {
+                popup
+                    .add_item_ex(label.as_str())
+                    .id(actions.len() as i32)
+                    .done();
+                disable_last(popup, !(*enabled && state.tray_enabled));
+                actions.push(FallbackAction::Standard {
+                    id: id.clone(),
+                    emitted_id: state.emitted_id(id, path_prefix),
+                });
+            }
+            MenuItemData::Checkmark {
+                id,
+                label,
+                enabled,
+                visible: true,
+                checked,
+                ..
+            } => {
+                popup
+                    .add_check_item_ex(label.as_str())
+                    .id(actions.len() as i32)
+                    .done();
+                let index = popup.get_item_count() - 1;
+                popup.set_item_checked(index, *checked);
+                disable_last(popup, !(*enabled && state.tray_enabled));
+                actions.push(FallbackAction::Checkmark { id: id.clone() });
+            }
+            MenuItemData::RadioGroup {
+                id,
+                selected,
+                options,
+                ..
+            } => {
+                for (option_index, option) in options.iter().enumerate() {
+                    if !option.visible {
+                        continue;
+                    }
+                    popup
+                        .add_radio_check_item_ex(option.label.as_str())
+                        .id(actions.len() as i32)
+                        .done();
+                    let index = popup.get_item_count() - 1;
+                    popup.set_item_checked(index, option_index == *selected);
+                    disable_last(popup, !(option.enabled && state.tray_enabled));
+                    actions.push(FallbackAction::Radio {
+                        group_id: id.clone(),
+                        index: option_index,
+                    });
+                }
+            }
+            MenuItemData::SubMenu {
+                label,
+                enabled,
+                visible: true,
+                submenu,
+                ..
+            } => {
+                let mut child = PopupMenu::new_alloc();
+                let children: Vec<&MenuItemData> = submenu.iter().collect();
+                build(
+                    state,
+                    &children,
+                    &mut child,
+                    on_id_pressed,
+                    actions,
+                    &format!("{}{}/", path_prefix, label),
+                );
+                popup.add_child(&child);
+                popup.add_submenu_node_item(label.as_str(), &child);
+                disable_last(popup, !(*enabled && state.tray_enabled));
+            }
+            MenuItemData::Header { label } => {
+                popup.add_separator_ex().label(label.as_str()).done();
+            }
+            MenuItemData::Separator => popup.add_separator(),
+            _ => {}
+        }
+    }
+}
+
+/// Sets the disabled state of the most recently added entry.
+fn disable_last(popup: &mut Gd<PopupMenu>, disabled: bool) {
+    let index = popup.get_item_count() - 1;
+    popup.set_item_disabled(index, disabled);
+}
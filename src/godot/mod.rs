@@ -3,6 +3,10 @@
 //! This module contains the Godot node implementation that exposes the tray icon
 //! functionality to GDScript through the GDExtension API.
 
+pub mod fallback_menu;
+pub mod popup_source;
+pub mod project_settings;
+pub mod singleton;
 pub mod tray_icon;
 
 pub use tray_icon::TrayIcon;
@@ -0,0 +1,164 @@
+//! Tray menus mirrored from a Godot `PopupMenu`.
+//!
+//! This module converts an existing `PopupMenu` tree into `MenuItemData`, the reverse
+//! of [`fallback_menu`](super::fallback_menu). Each converted entry remembers the
+//! PopupMenu it came from, so `TrayIcon` can forward tray clicks to that PopupMenu's
+//! `id_pressed` and `index_pressed` signals.
+//!
+//! Item IDs are taken from String (or StringName) metadata when set. Otherwise the
+//! PopupMenu item ID is used, prefixed by the labels of the enclosing submenus (e.g.
+//! `"File/3"`), since every PopupMenu numbers its items independently.
+
+use crate::menu::item::{MenuItemData, RadioItemData};
+use godot::classes::PopupMenu;
+use godot::prelude::*;
+use std::collections::HashMap;
+
+/// The PopupMenu entry a converted menu item came from.
+#[derive(Clone)]
+pub struct PopupItem {
+    /// The PopupMenu holding the entry.
+    pub popup: Gd<PopupMenu>,
+    /// ID of the entry in the PopupMenu.
+    pub id: i32,
+    /// Index of the entry in the PopupMenu.
+    pub index: i32,
+}
+
+impl PopupItem {
+    /// Emits `id_pressed` and `index_pressed` on the PopupMenu, as a click in it would.
+    ///
+    /// Does nothing if the PopupMenu was freed.
+    pub fn press(&self) {
+        if !self.popup.is_instance_valid() {
+            return;
+        }
+        let mut popup = self.popup.clone();
+        popup.emit_signal("id_pressed", &[Variant::from(self.id as i64)]);
+        popup.emit_signal("index_pressed", &[Variant::from(self.index as i64)]);
+    }
+}
+
+/// Converts the entries of `popup` into menu items, recording the source of every
+/// clickable item in `sources`, keyed by the item ID.
+///
+/// Consecutive radio-checkable entries form one radio group, whose ID is the ID of its
+/// first option with a `"_group"` suffix. Separators with text become headers.
+pub fn convert(
+    popup: &Gd<PopupMenu>,
+    sources: &mut HashMap<String, PopupItem>,
+    path_prefix: &str,
+) -> Vec<MenuItemData> {
+    let mut items = Vec::new();
+
+    for index in 0..popup.get_item_count() {
+        let label = popup.get_item_text(index).to_string();
+        let enabled = !popup.is_item_disabled(index);
+
+        if popup.is_item_separator(index) {
+            items.push(if label.is_empty() {
+                MenuItemData::Separator
+            } else {
+                MenuItemData::Header { label }
+            });
+            continue;
+        }
+
+        if let Some(submenu) = popup.get_item_submenu_node(index) {
+            let submenu_items = convert(&submenu, sources, &format!("{}{}/", path_prefix, label));
+            items.push(MenuItemData::SubMenu {
+                label,
+                icon_name: String::new(),
+                enabled,
+                visible: true,
+                submenu: submenu_items,
+                priority: 0,
+            });
+            continue;
+        }
+
+        let id = item_id(popup, index, path_prefix);
+        if sources.contains_key(&id) {
+            godot_warn!(
+                "Duplicate menu item ID '{}' in PopupMenu, set unique String metadata",
+                id
+            );
+        }
+        sources.insert(
+            id.clone(),
+            PopupItem {
+                popup: popup.clone(),
+                id: popup.get_item_id(index),
+                index,
+            },
+        );
+
+        if popup.is_item_radio_checkable(index) {
+            let option = RadioItemData {
+                id: id.clone(),
+                label,
+                icon_name: String::new(),
+                enabled,
+                visible: true,
+            };
+            let checked = popup.is_item_checked(index);
+            // Extend the radio group started by the previous entry, if any.
+            if let Some(MenuItemData::RadioGroup {
+                selected, options, ..
+            }) = items.last_mut()
+                && index > 0
+                && popup.is_item_radio_checkable(index - 1)
+            {
+                if checked {
+                    *selected = options.len();
+                }
+                options.push(option);
+            } else {
+                items.push(MenuItemData::RadioGroup {
+                    id: format!("{}_group", id),
+                    selected: 0,
+                    options: vec![option],
+                    emit_on_reselect: true,
+                    priority: 0,
+                });
+            }
+        } else if popup.is_item_checkable(index) {
+            items.push(MenuItemData::Checkmark {
+                id,
+                label,
+                icon_name: String::new(),
+                enabled,
+                visible: true,
+                checked: popup.is_item_checked(index),
+                priority: 0,
+            });
+        } else {
+            items.push(MenuItemData::Standard {
+                id,
+                label,
+                icon_name: String::new(),
+                enabled,
+                visible: true,
+                priority: 0,
+                suppress_global: false,
+            });
+        }
+    }
+
+    items
+}
+
+/// Returns the menu item ID for an entry: its String metadata, or its prefixed item ID.
+fn item_id(popup: &Gd<PopupMenu>, index: i32, path_prefix: &str) -> String {
+    let metadata = popup.get_item_metadata(index);
+    let metadata = match metadata.get_type() {
+        VariantType::STRING | VariantType::STRING_NAME => metadata.to_string(),
+        _ => String::new(),
+    };
+
+    if metadata.is_empty() {
+        format!("{}{}", path_prefix, popup.get_item_id(index))
+    } else {
+        metadata
+    }
+}
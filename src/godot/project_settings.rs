@@ -0,0 +1,115 @@
+//! Project-wide tray defaults in the `godot_ksni/` section of the Project Settings.
+//!
+//! Every `TrayIcon` starts from these defaults, so a project-wide policy (e.g. its own
+//! icon, or no tray spawning on its own) applies without per-node code. Exported
+//! properties and setters called on a node still override them.
+//!
+//! The settings are added with their defaults and editor hints when the extension is
+//! loaded, unless the project already set them. Settings left at their defaults are
+//! not written to `project.godot`. As for the [`singleton`](super::singleton),
+//! extensions that embed this crate as a dependency must call [`register`] from their
+//! `ExtensionLibrary` init at `InitLevel::Scene` to get them.
+
+use crate::tray::logging;
+use godot::classes::ProjectSettings;
+use godot::global::PropertyHint;
+use godot::prelude::*;
+
+/// Icon resource path applied when the tray is spawned without an icon set.
+pub const DEFAULT_ICON_PATH: &str = "godot_ksni/tray/default_icon_path";
+/// Title of new trays, instead of the project name.
+pub const DEFAULT_TITLE: &str = "godot_ksni/tray/default_title";
+/// Category of new trays, as accepted by `set_tray_category()`.
+pub const DEFAULT_CATEGORY: &str = "godot_ksni/tray/default_category";
+/// Initial value of the `auto_spawn` property of new nodes.
+pub const AUTO_SPAWN: &str = "godot_ksni/tray/auto_spawn";
+/// Whether the standalone extension registers the `Tray` engine singleton.
+pub const REGISTER_SINGLETON: &str = "godot_ksni/tray/register_singleton";
+/// Level of internal log messages, as accepted by `set_log_level()`.
+pub const LOG_LEVEL: &str = "godot_ksni/debug/log_level";
+
+/// Category names in the order shown by the editor, the first being the default.
+const CATEGORIES: &str = "ApplicationStatus,Communications,SystemServices,Hardware";
+
+/// Level used when the setting is missing, printing warnings and errors.
+const DEFAULT_LOG_LEVEL: i64 = 2;
+
+/// Adds the tray settings to the Project Settings and applies the log level.
+pub fn register() {
+    add_setting(
+        DEFAULT_ICON_PATH,
+        GString::new().to_variant(),
+        VariantType::STRING,
+        PropertyHint::FILE,
+        "*.png,*.svg,*.webp,*.jpg,*.jpeg,*.bmp,*.tres,*.res",
+    );
+    add_setting(
+        DEFAULT_TITLE,
+        GString::new().to_variant(),
+        VariantType::STRING,
+        PropertyHint::PLACEHOLDER_TEXT,
+        "Project name",
+    );
+    add_setting(
+        DEFAULT_CATEGORY,
+        "ApplicationStatus".to_variant(),
+        VariantType::STRING,
+        PropertyHint::ENUM,
+        CATEGORIES,
+    );
+    add_setting(
+        AUTO_SPAWN,
+        false.to_variant(),
+        VariantType::BOOL,
+        PropertyHint::NONE,
+        "",
+    );
+    add_setting(
+        REGISTER_SINGLETON,
+        false.to_variant(),
+        VariantType::BOOL,
+        PropertyHint::NONE,
+        "",
+    );
+    add_setting(
+        LOG_LEVEL,
+        DEFAULT_LOG_LEVEL.to_variant(),
+        VariantType::INT,
+        PropertyHint::ENUM,
+        "Off,Error,Warn,Info,Debug,Trace",
+    );
+
+    logging::init();
+    logging::set_level(logging::level_filter(get(LOG_LEVEL, DEFAULT_LOG_LEVEL)));
+}
+
+/// Returns the value of a setting, or `default` if it is missing or of another type.
+pub(crate) fn get<T: FromGodot>(name: &str, default: T) -> T {
+    let settings = ProjectSettings::singleton();
+    if !settings.has_setting(name) {
+        return default;
+    }
+    settings.get_setting(name).try_to().unwrap_or(default)
+}
+
+/// Adds a setting with its default, keeping a value the project already set, and
+/// describes it to the editor.
+fn add_setting(
+    name: &str,
+    default: Variant,
+    variant_type: VariantType,
+    hint: PropertyHint,
+    hint_string: &str,
+) {
+    let mut settings = ProjectSettings::singleton();
+    if !settings.has_setting(name) {
+        settings.set_setting(name, &default);
+    }
+    settings.set_initial_value(name, &default);
+    settings.add_property_info(&vdict! {
+        "name": name,
+        "type": variant_type.ord(),
+        "hint": hint.ord(),
+        "hint_string": hint_string,
+    });
+}
@@ -0,0 +1,84 @@
+//! The optional `Tray` engine singleton.
+//!
+//! Small projects can use the default `TrayIcon` registered as the `Tray` engine
+//! singleton instead of adding a node to their scenes. Since it is a `TrayIcon`, the
+//! whole API and all signals are available on it (e.g. `Tray.set_title("My Game")`,
+//! `Tray.menu_activated.connect(...)`). Its node enters the scene tree, as a child of
+//! the root, when the tray is spawned. Nodes created by the project are independent of
+//! it.
+//!
+//! The standalone extension registers the singleton when the
+//! `godot_ksni/tray/register_singleton` project setting is enabled, which takes effect
+//! the next time the project is started. Extensions that embed this crate as a
+//! dependency do not get it unless they call [`register`] and [`unregister`] from
+//! their `ExtensionLibrary` init and deinit at `InitLevel::Scene`.
+
+use crate::godot::tray_icon::TrayIcon;
+use godot::classes::{Engine, SceneTree};
+use godot::prelude::*;
+
+/// Name of the engine singleton, as used from GDScript.
+pub const SINGLETON_NAME: &str = "Tray";
+
+/// Creates the default `TrayIcon` and registers it as the `Tray` engine singleton.
+///
+/// Does nothing if a singleton with that name already exists.
+pub fn register() {
+    let mut engine = Engine::singleton();
+    if engine.has_singleton(SINGLETON_NAME) {
+        godot_warn!(
+            "An engine singleton named '{}' already exists, the default TrayIcon is not registered",
+            SINGLETON_NAME
+        );
+        return;
+    }
+
+    let mut tray = TrayIcon::new_alloc();
+    tray.set_name(SINGLETON_NAME);
+    tray.bind_mut().engine_singleton = true;
+    engine.register_singleton(SINGLETON_NAME, &tray);
+}
+
+/// Unregisters the `Tray` engine singleton and frees its `TrayIcon`, unless the scene
+/// tree already did.
+pub fn unregister() {
+    let mut engine = Engine::singleton();
+    let Some(singleton) = engine.get_singleton(SINGLETON_NAME) else {
+        return;
+    };
+    engine.unregister_singleton(SINGLETON_NAME);
+
+    if let Ok(tray) = singleton.try_cast::<TrayIcon>()
+        && tray.is_instance_valid()
+    {
+        tray.free();
+    }
+}
+
+/// Called by the `Tray` singleton when it is freed, so the engine does not keep a
+/// dangling singleton.
+pub(crate) fn forget() {
+    let mut engine = Engine::singleton();
+    if engine.has_singleton(SINGLETON_NAME) {
+        engine.unregister_singleton(SINGLETON_NAME);
+    }
+}
+
+/// Adds the `Tray` singleton to the scene tree as a child of the root, so it receives
+/// `process()` calls.
+///
+/// The root may be busy setting up its children, so the node is added at the end of
+/// the frame. Does nothing if there is no scene tree.
+pub(crate) fn enter_tree(tray: Gd<TrayIcon>) {
+    let Some(tree) = Engine::singleton()
+        .get_main_loop()
+        .and_then(|main_loop| main_loop.try_cast::<SceneTree>().ok())
+    else {
+        godot_warn!("No SceneTree, the Tray singleton cannot process events");
+        return;
+    };
+
+    if let Some(mut root) = tree.get_root() {
+        root.call_deferred("add_child", &[tray.to_variant()]);
+    }
+}
@@ -3,15 +3,64 @@
 //! This module contains the `TrayIcon` Godot node that provides system tray icon
 //! functionality for Godot 4 projects on Linux using the StatusNotifierItem specification.
 
+use crate::error::{self, Error};
+use crate::godot::fallback_menu::{self, FallbackAction};
+use crate::godot::popup_source::{self, PopupItem};
+use crate::godot::project_settings;
+use crate::godot::singleton;
 use crate::menu::item::{MenuItemData, RadioItemData};
+use crate::menu::media;
 use crate::tray::event::TrayEvent;
-use crate::tray::ksni_impl::KsniTray;
+use crate::tray::icon;
+use crate::tray::ksni_impl::{Handle, KsniTray};
+use crate::tray::logging;
+use crate::tray::registry::{self, KeptAliveTray};
+use crate::tray::screen::{self, ScreenRect};
+use crate::tray::scroll::{ScrollAccumulator, ScrollOrientation};
 use crate::tray::state::TrayState;
-use godot::classes::{Image, ResourceLoader, Texture2D};
+use crate::tray::stats::TrayStats;
+use crate::tray::watcher;
+use godot::classes::image::Format;
+use godot::classes::notify::NodeNotification;
+use godot::classes::{
+    CompressedTexture2D, DisplayServer, FileAccess, Image, PopupMenu, ProjectSettings,
+    ResourceLoader, TextServerManager, Texture2D, TranslationServer,
+};
+use godot::global::PropertyUsageFlags;
+use godot::meta::PropertyInfo;
 use godot::prelude::*;
-use ksni::blocking::TrayMethods;
-use std::sync::mpsc::channel;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Project setting holding the application icon path.
+const PROJECT_ICON_SETTING: &str = "application/config/icon";
+
+/// Project setting holding the application name.
+const PROJECT_NAME_SETTING: &str = "application/config/name";
+
+/// Value of Godot's global `HORIZONTAL` orientation constant.
+const GAUGE_HORIZONTAL: i64 = 0;
+
+/// Value of Godot's global `VERTICAL` orientation constant.
+const GAUGE_VERTICAL: i64 = 1;
+
+/// Interval in seconds between checks of the project icon while window icon syncing is enabled.
+const WINDOW_ICON_POLL_INTERVAL: f64 = 2.0;
+
+/// Time `spawn_tray()` waits for the tray to register by default.
+const DEFAULT_SPAWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Title, subtitle, icon name, and icon texture parsed by `set_tooltip_from_dict()`.
+type TooltipFields = (GString, GString, GString, Option<Gd<Texture2D>>);
+
+/// A resource loaded by `set_icon_from_path()`.
+enum IconResource {
+    Texture(Gd<Texture2D>),
+    Image(Gd<Image>),
+}
 
 #[derive(GodotClass)]
 #[class(base=Node)]
@@ -25,6 +74,39 @@ use std::sync::{Arc, Mutex};
 /// - `menu_activated(id: String)` - Emitted when a standard menu item is clicked
 /// - `checkmark_toggled(id: String, checked: bool)` - Emitted when a checkmark item is toggled
 /// - `radio_selected(group_id: String, index: int, option_id: String)` - Emitted when a radio option is selected
+/// - `activated(x: int, y: int)` - Emitted when the tray icon itself is activated (usually a left click)
+/// - `activated_at_screen(pos: Vector2i)` - Same as `activated`, with the position as a `Vector2i`
+/// - `tray_respawned()` - Emitted when the tray was re-registered under a new tray ID
+/// - `menu_opened()` - Emitted when the host is about to show the tray menu
+/// - `menu_closed()` - Emitted, on a best-effort basis, when the tray menu was closed
+/// - `menu_events_batch(events: Array)` - Emitted once per frame instead of the event signals
+///   above when `set_batch_signal(true)` was called
+/// - `checkmark_state_changed(id: String, checked: bool, source: int)` - Emitted when a
+///   checkmark changes, by a click or from code
+/// - `radio_state_changed(group_id: String, index: int, option_id: String, source: int)` -
+///   Emitted when a radio selection changes, by a click or from code
+/// - `play_pause_pressed()`, `previous_pressed()`, `next_pressed()`, `stop_pressed()` -
+///   Emitted when an item added by `add_media_controls()` is clicked
+///
+/// # Properties
+///
+/// - `auto_spawn: bool` - Spawns the tray when the node is ready (default `false`, or
+///   the `godot_ksni/tray/auto_spawn` project setting)
+/// - `radio_default_handler: bool` - Connects `radio_selected` to
+///   `on_radio_selected_default()` when the node is ready (default `true`)
+/// - `tray_id: String` - Same as `set_tray_id()`
+/// - `icon_name: String` - Same as `set_icon_name()`
+/// - `title: String` - Same as `set_title()`, without the suffix
+/// - `tooltip_title: String` - Same as `set_tooltip_title()`
+/// - `tooltip_subtitle: String` - Same as `set_tooltip_subtitle()`
+///
+/// Setting a property refreshes the tray if it is spawned. With `auto_spawn` enabled, a
+/// tray configured entirely in the inspector needs no script.
+///
+/// The tooltip and menu are also stored when the node is saved in a scene (e.g. with
+/// `ResourceSaver.save()`) or duplicated, as the `tooltip` (in the format of
+/// `get_tooltip()`) and `menu` (an Array of Dictionaries in the format of
+/// `replace_menu_item()`) properties. Icons set from images and Callables are not stored.
 ///
 /// # Example
 ///
@@ -38,70 +120,381 @@ use std::sync::{Arc, Mutex};
 /// ```
 pub struct TrayIcon {
     base: Base<Node>,
-    handle: Option<ksni::blocking::Handle<KsniTray>>,
+    /// Whether to spawn the tray when the node enters the scene tree, after the exported
+    /// properties were applied.
+    #[export]
+    auto_spawn: bool,
+    /// Whether to connect `radio_selected` to `on_radio_selected_default()` when the node
+    /// is ready.
+    #[export]
+    radio_default_handler: bool,
+    /// Unique identifier of the tray icon, see `set_tray_id()`.
+    #[var(get = get_tray_id, set = set_tray_id)]
+    #[export]
+    tray_id: PhantomVar<GString>,
+    /// Name of the system icon used as the tray icon, see `set_icon_name()`.
+    #[var(get = get_icon_name, set = set_icon_name)]
+    #[export]
+    icon_name: PhantomVar<GString>,
+    /// Title of the tray icon without its suffix, see `set_title()`.
+    #[var(get = get_title_base, set = set_title)]
+    #[export]
+    title: PhantomVar<GString>,
+    /// Tooltip title, see `set_tooltip_title()`. Stored as part of the `tooltip` property.
+    #[var(get = get_tooltip_title, set = set_tooltip_title, usage_flags = [EDITOR])]
+    #[export]
+    tooltip_title: PhantomVar<GString>,
+    /// Tooltip subtitle, see `set_tooltip_subtitle()`. Stored as part of the `tooltip` property.
+    #[var(get = get_tooltip_subtitle, set = set_tooltip_subtitle, usage_flags = [EDITOR])]
+    #[export]
+    tooltip_subtitle: PhantomVar<GString>,
+    handle: Option<Handle<KsniTray>>,
     state: Arc<Mutex<TrayState>>,
     event_receiver: Option<std::sync::mpsc::Receiver<TrayEvent>>,
+    spawn_timeout: Duration,
+    activation_debounce: Duration,
+    last_activations: HashMap<String, Instant>,
+    last_refresh_hash: Option<u64>,
+    sync_window_icon: bool,
+    window_icon: Option<Gd<Image>>,
+    synced_icon_path: GString,
+    window_icon_poll_elapsed: f64,
+    icon_cache: Option<(Rid, u64, ksni::Icon)>,
+    enabled_providers: HashMap<String, Callable>,
+    submenu_providers: HashMap<String, Callable>,
+    loaded_submenus: HashSet<String>,
+    item_callbacks: HashMap<String, Callable>,
+    icon_float_is_srgb: bool,
+    icon_opaque: bool,
+    keep_alive: bool,
+    kept_alive_id: Option<String>,
+    stats: Arc<TrayStats>,
+    event_history: VecDeque<(String, Variant)>,
+    history_size: usize,
+    last_error: i64,
+    last_spawn_error: i64,
+    menu_open: bool,
+    menu_opened_at: Option<Instant>,
+    menu_close_timeout: Duration,
+    last_activation: Option<Vector2i>,
+    scroll: ScrollAccumulator,
+    text_direction: i64,
+    menu_fallback: bool,
+    fallback_popup: Option<Gd<PopupMenu>>,
+    fallback_actions: Vec<FallbackAction>,
+    fallback_generation: u64,
+    batch_signal: bool,
+    string_name_ids: bool,
+    id_names: HashMap<String, StringName>,
+    debug_mode: bool,
+    icon_generator: Option<Callable>,
+    icon_generator_interval: f64,
+    icon_generator_elapsed: f64,
+    last_generated_image_hash: Option<u64>,
+    source_popup: Option<Gd<PopupMenu>>,
+    popup_items: HashMap<String, PopupItem>,
+    /// Whether this is the default `TrayIcon` registered as the `Tray` engine singleton.
+    pub(crate) engine_singleton: bool,
 }
 
 #[godot_api]
 impl INode for TrayIcon {
     fn init(base: Base<Node>) -> Self {
+        logging::init();
+
+        let mut state = TrayState::from_app_name(&Self::project_name().to_string());
+        let title = project_settings::get(project_settings::DEFAULT_TITLE, GString::new());
+        if !title.is_empty() {
+            state.title_base = title.to_string();
+        }
+        let category = project_settings::get(project_settings::DEFAULT_CATEGORY, GString::new());
+        if let Some(category) = Self::category_from_name(&category.to_string()) {
+            state.category = category;
+        }
+        let stats = state.stats.clone();
+
         Self {
             base,
+            auto_spawn: project_settings::get(project_settings::AUTO_SPAWN, false),
+            radio_default_handler: true,
+            tray_id: PhantomVar::default(),
+            icon_name: PhantomVar::default(),
+            title: PhantomVar::default(),
+            tooltip_title: PhantomVar::default(),
+            tooltip_subtitle: PhantomVar::default(),
             handle: None,
-            state: Arc::new(Mutex::new(TrayState::new("godot_tray_icon".to_string()))),
+            state: Arc::new(Mutex::new(state)),
             event_receiver: None,
+            spawn_timeout: DEFAULT_SPAWN_TIMEOUT,
+            activation_debounce: Duration::ZERO,
+            last_activations: HashMap::new(),
+            last_refresh_hash: None,
+            sync_window_icon: false,
+            window_icon: None,
+            synced_icon_path: GString::new(),
+            window_icon_poll_elapsed: 0.0,
+            icon_cache: None,
+            enabled_providers: HashMap::new(),
+            submenu_providers: HashMap::new(),
+            loaded_submenus: HashSet::new(),
+            item_callbacks: HashMap::new(),
+            icon_float_is_srgb: false,
+            icon_opaque: false,
+            keep_alive: false,
+            kept_alive_id: None,
+            stats,
+            event_history: VecDeque::new(),
+            history_size: 0,
+            last_error: Error::OK,
+            last_spawn_error: Self::SPAWN_OK,
+            menu_open: false,
+            menu_opened_at: None,
+            menu_close_timeout: Duration::ZERO,
+            last_activation: None,
+            scroll: ScrollAccumulator::default(),
+            text_direction: Self::TEXT_DIRECTION_AUTO,
+            menu_fallback: false,
+            fallback_popup: None,
+            fallback_actions: Vec::new(),
+            fallback_generation: 0,
+            batch_signal: false,
+            string_name_ids: false,
+            id_names: HashMap::new(),
+            debug_mode: false,
+            icon_generator: None,
+            icon_generator_interval: 0.0,
+            icon_generator_elapsed: 0.0,
+            last_generated_image_hash: None,
+            source_popup: None,
+            popup_items: HashMap::new(),
+            engine_singleton: false,
         }
     }
 
     fn ready(&mut self) {
         self.base_mut().set_process(true);
+        if self.radio_default_handler {
+            let handler = self.base().callable("on_radio_selected_default");
+            if !self.base().is_connected("radio_selected", &handler) {
+                self.base_mut().connect("radio_selected", &handler);
+            }
+        }
+        if self.auto_spawn {
+            self.spawn_tray();
+        }
     }
 
-    fn process(&mut self, _delta: f64) {
-        let mut events = Vec::new();
-        if let Some(ref rx) = self.event_receiver {
-            while let Ok(event) = rx.try_recv() {
-                events.push(event);
+    fn on_notification(&mut self, what: NodeNotification) {
+        if what == NodeNotification::TRANSLATION_CHANGED
+            && self.text_direction == Self::TEXT_DIRECTION_AUTO
+        {
+            self.apply_text_direction();
+            self.refresh();
+        }
+    }
+
+    fn get_property_list(&mut self) -> Vec<PropertyInfo> {
+        // Stored in scenes but not shown in the inspector, like the menu built by scripts.
+        [
+            PropertyInfo::new_var::<Dictionary>("tooltip"),
+            PropertyInfo::new_var::<Array<Dictionary>>("menu"),
+        ]
+        .into_iter()
+        .map(|info| PropertyInfo {
+            usage: PropertyUsageFlags::STORAGE,
+            ..info
+        })
+        .collect()
+    }
+
+    fn get_property(&self, property: StringName) -> Option<Variant> {
+        match property.to_string().as_str() {
+            "tooltip" => Some(Variant::from(self.get_tooltip())),
+            "menu" => {
+                let state = self.state.lock().unwrap();
+                let menu: Array<Dictionary> =
+                    state.menu.iter().map(Self::menu_item_to_dict).collect();
+                Some(Variant::from(menu))
             }
+            _ => None,
         }
+    }
 
-        for event in events {
-            match event {
-                TrayEvent::MenuActivated(id) => {
-                    self.base_mut()
-                        .emit_signal("menu_activated", &[Variant::from(id)]);
-                }
-                TrayEvent::CheckmarkToggled(id, checked) => {
-                    self.base_mut().emit_signal(
-                        "checkmark_toggled",
-                        &[Variant::from(id), Variant::from(checked)],
-                    );
-                }
-                TrayEvent::RadioSelected(group_id, index, option_id) => {
-                    self.base_mut().emit_signal(
-                        "radio_selected",
-                        &[
-                            Variant::from(group_id),
-                            Variant::from(index as i64),
-                            Variant::from(option_id),
-                        ],
-                    );
+    fn set_property(&mut self, property: StringName, value: Variant) -> bool {
+        match property.to_string().as_str() {
+            "tooltip" => match value.try_to::<Dictionary>() {
+                Ok(dict) => self.load_tooltip_property(&dict),
+                Err(_) => godot_error!("Invalid TrayIcon tooltip: {}", value),
+            },
+            "menu" => match value.try_to::<VariantArray>() {
+                Ok(items) => self.load_menu_property(&items),
+                Err(_) => godot_error!("Invalid TrayIcon menu: {}", value),
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    fn process(&mut self, delta: f64) {
+        if self.sync_window_icon && self.window_icon.is_none() {
+            self.window_icon_poll_elapsed += delta;
+            if self.window_icon_poll_elapsed >= WINDOW_ICON_POLL_INTERVAL {
+                self.window_icon_poll_elapsed = 0.0;
+                if Self::project_icon_path() != self.synced_icon_path {
+                    self.apply_window_icon();
                 }
             }
         }
+
+        if let Some(generator) = self.icon_generator.clone() {
+            self.icon_generator_elapsed += delta;
+            if self.icon_generator_elapsed >= self.icon_generator_interval {
+                self.icon_generator_elapsed = 0.0;
+                self.run_icon_generator(&generator);
+            }
+        }
+
+        for (level, message) in logging::drain() {
+            match level {
+                log::Level::Error => godot_error!("{}", message),
+                log::Level::Warn => godot_warn!("{}", message),
+                _ => godot_print!("{}", message),
+            }
+        }
+
+        self.dispatch_events();
     }
 }
 
 #[godot_api]
 impl TrayIcon {
+    /// No error occurred.
+    #[constant]
+    const ERR_OK: i64 = Error::OK;
+    /// The tray service could not be spawned, usually because of a D-Bus error.
+    #[constant]
+    const ERR_SPAWN_FAILED: i64 = Error::SPAWN_FAILED;
+    /// The operation requires a spawned tray.
+    #[constant]
+    const ERR_NOT_SPAWNED: i64 = Error::NOT_SPAWNED;
+    /// The tray is already spawned.
+    #[constant]
+    const ERR_ALREADY_SPAWNED: i64 = Error::ALREADY_SPAWNED;
+    /// Tray icons are not supported on this platform.
+    #[constant]
+    const ERR_UNSUPPORTED: i64 = Error::UNSUPPORTED;
+    /// No menu item with the given ID exists.
+    #[constant]
+    const ERR_ITEM_NOT_FOUND: i64 = Error::ITEM_NOT_FOUND;
+    /// Pixel data does not match the given dimensions.
+    #[constant]
+    const ERR_INVALID_ICON_DATA: i64 = Error::INVALID_ICON_DATA;
+    /// A menu item with the given ID already exists.
+    #[constant]
+    const ERR_DUPLICATE_ID: i64 = Error::DUPLICATE_ID;
+    /// No kept-alive tray with the given ID exists.
+    #[constant]
+    const ERR_KEPT_ALIVE_NOT_FOUND: i64 = Error::KEPT_ALIVE_NOT_FOUND;
+    /// The kept-alive tray is owned by another node.
+    #[constant]
+    const ERR_ALREADY_ADOPTED: i64 = Error::ALREADY_ADOPTED;
+    /// The tray did not register within the spawn timeout.
+    #[constant]
+    const ERR_TIMEOUT: i64 = Error::TIMEOUT;
+    /// The tray ID has no characters usable in a D-Bus name.
+    #[constant]
+    const ERR_INVALID_TRAY_ID: i64 = Error::INVALID_TRAY_ID;
+    /// The row stride of pixel data is less than a row of pixels.
+    #[constant]
+    const ERR_INVALID_ICON_STRIDE: i64 = Error::INVALID_ICON_STRIDE;
+    /// No StatusNotifierWatcher runs on the session bus, so no tray host can show the tray.
+    #[constant]
+    const ERR_NO_WATCHER: i64 = Error::NO_WATCHER;
+    /// Icon dimensions are not positive, or exceed 4096x4096 pixels.
+    #[constant]
+    const ERR_INVALID_ICON_SIZE: i64 = Error::INVALID_ICON_SIZE;
+    /// Spawning the tray service panicked on its thread.
+    #[constant]
+    const ERR_SPAWN_PANICKED: i64 = Error::SPAWN_PANICKED;
+    /// A TOML tray configuration could not be parsed.
+    #[cfg(feature = "toml")]
+    #[constant]
+    const ERR_TOML: i64 = Error::TOML;
+
+    /// The last `spawn_tray()` succeeded, or none was made.
+    #[constant]
+    const SPAWN_OK: i64 = 0;
+    /// The tray was already spawned.
+    #[constant]
+    const SPAWN_ALREADY: i64 = 1;
+    /// No tray host is available: no StatusNotifierWatcher runs, it did not answer in
+    /// time, or the platform has no StatusNotifierItem trays.
+    #[constant]
+    const SPAWN_NO_HOST: i64 = 2;
+    /// The tray service failed on D-Bus, e.g. because no session bus is running.
+    #[constant]
+    const SPAWN_DBUS_ERROR: i64 = 3;
+
+    /// Text direction follows the current locale.
+    #[constant]
+    const TEXT_DIRECTION_AUTO: i64 = 0;
+    /// Left-to-right text direction.
+    #[constant]
+    const TEXT_DIRECTION_LTR: i64 = 1;
+    /// Right-to-left text direction.
+    #[constant]
+    const TEXT_DIRECTION_RTL: i64 = 2;
+
+    /// The state change was caused by a click in the tray menu.
+    #[constant]
+    const SOURCE_USER: i64 = 0;
+    /// The state change was caused by a call such as `set_checkmark_state()`.
+    #[constant]
+    const SOURCE_PROGRAM: i64 = 1;
+
+    /// RGBA pixel data, 4 bytes per pixel.
+    #[constant]
+    const PIXEL_FORMAT_RGBA8: i64 = 0;
+    /// RGB pixel data without alpha, 3 bytes per pixel.
+    #[constant]
+    const PIXEL_FORMAT_RGB8: i64 = 1;
+    /// BGRA pixel data, 4 bytes per pixel.
+    #[constant]
+    const PIXEL_FORMAT_BGRA8: i64 = 2;
+
+    /// Vertical scrolling, passed to `scrolled`.
+    #[constant]
+    const ORIENTATION_VERTICAL: i64 = 0;
+    /// Horizontal scrolling, passed to `scrolled`.
+    #[constant]
+    const ORIENTATION_HORIZONTAL: i64 = 1;
+
+    /// Selects the play/pause item in `add_media_controls()`.
+    #[constant]
+    const MEDIA_PLAY_PAUSE: i64 = media::PLAY_PAUSE;
+    /// Selects the previous track item in `add_media_controls()`.
+    #[constant]
+    const MEDIA_PREVIOUS: i64 = media::PREVIOUS;
+    /// Selects the next track item in `add_media_controls()`.
+    #[constant]
+    const MEDIA_NEXT: i64 = media::NEXT;
+    /// Selects the stop item in `add_media_controls()`.
+    #[constant]
+    const MEDIA_STOP: i64 = media::STOP;
+    /// Selects all items in `add_media_controls()`.
+    #[constant]
+    const MEDIA_ALL: i64 = media::ALL;
+
     /// Signal emitted when a standard menu item is clicked.
     ///
+    /// Menu signals pass IDs as Strings, or as StringNames after
+    /// `set_string_name_ids(true)`, so their ID parameters are declared as Variant.
+    ///
     /// # Parameters
     ///
     /// - `id` - The unique identifier of the menu item that was clicked
     #[signal]
-    fn menu_activated(id: GString);
+    fn menu_activated(id: Variant);
 
     /// Signal emitted when a checkmark menu item is toggled.
     ///
@@ -110,7 +503,7 @@ impl TrayIcon {
     /// - `id` - The unique identifier of the checkmark item
     /// - `checked` - The new checked state (true if checked, false if unchecked)
     #[signal]
-    fn checkmark_toggled(id: GString, checked: bool);
+    fn checkmark_toggled(id: Variant, checked: bool);
 
     /// Signal emitted when a radio button option is selected.
     ///
@@ -120,17 +513,212 @@ impl TrayIcon {
     /// - `index` - The index of the selected option (0-based)
     /// - `option_id` - The unique identifier of the selected option
     #[signal]
-    fn radio_selected(group_id: GString, index: i64, option_id: GString);
+    fn radio_selected(group_id: Variant, index: i64, option_id: Variant);
+
+    /// Signal emitted when a checkmark changes, either by a click in the tray menu or from code.
+    ///
+    /// Unlike `checkmark_toggled`, which is only emitted for clicks, this is also emitted
+    /// by `set_checkmark_state()` when the state changes and by `emit_current_states()`.
+    /// Use `source` to avoid re-applying state the application set itself. It is emitted
+    /// even when batching is enabled.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - The unique identifier of the checkmark item
+    /// - `checked` - The new checked state
+    /// - `source` - `SOURCE_USER` or `SOURCE_PROGRAM`
+    #[signal]
+    fn checkmark_state_changed(id: Variant, checked: bool, source: i64);
+
+    /// Signal emitted when a radio selection changes, either by a click in the tray menu
+    /// or from code.
+    ///
+    /// Unlike `radio_selected`, which is only emitted for clicks, this is also emitted by
+    /// `set_radio_selected()` when the selection changes and by `emit_current_states()`.
+    /// It is emitted even when batching is enabled.
+    ///
+    /// # Parameters
+    ///
+    /// - `group_id` - The unique identifier of the radio group
+    /// - `index` - The index of the selected option (0-based)
+    /// - `option_id` - The unique identifier of the selected option
+    /// - `source` - `SOURCE_USER` or `SOURCE_PROGRAM`
+    #[signal]
+    fn radio_state_changed(group_id: Variant, index: i64, option_id: Variant, source: i64);
+
+    /// Signal emitted when the tray icon itself is activated, usually by a left click.
+    ///
+    /// The coordinates are the raw values reported by the tray host.
+    ///
+    /// # Parameters
+    ///
+    /// - `x` - Horizontal position reported by the host
+    /// - `y` - Vertical position reported by the host
+    #[signal]
+    fn activated(x: i64, y: i64);
+
+    /// Signal emitted when the tray icon itself is activated, with the position in screen coordinates.
+    ///
+    /// On X11, hosts report positions in root window coordinates, which are the same global
+    /// coordinates used by `DisplayServer` (e.g. `DisplayServer.window_set_position()`), so no
+    /// conversion is needed. Wayland has no global coordinates; hosts there usually report
+    /// `(0, 0)`, which should be treated as "position unknown". Use
+    /// `get_last_activation_position()` to find the monitor the position is on.
+    ///
+    /// # Parameters
+    ///
+    /// - `pos` - The activation position in screen coordinates
+    #[signal]
+    fn activated_at_screen(pos: Vector2i);
+
+    /// Signal emitted when the user scrolls over the tray icon, e.g. to change the volume.
+    ///
+    /// By default each scroll event of the host is passed on as is, and hosts use
+    /// different magnitudes: KDE Plasma sends 120 per mouse wheel notch, others use much
+    /// smaller units. Use `set_scroll_accumulation()` to receive whole steps instead.
+    ///
+    /// # Parameters
+    ///
+    /// - `steps` - The scroll delta, or the number of whole steps when accumulating;
+    ///   negative values scroll up or left
+    /// - `orientation` - `ORIENTATION_VERTICAL` or `ORIENTATION_HORIZONTAL`
+    #[signal]
+    fn scrolled(steps: i64, orientation: i64);
+
+    /// Signal emitted when a menu operation fails, if enabled with `set_debug_mode()`.
+    ///
+    /// Menu functions report failures, such as adding to a submenu that does not
+    /// exist, only by returning `false`. This signal surfaces them during development.
+    ///
+    /// # Parameters
+    ///
+    /// - `op` - Name of the function that failed (e.g. `"add_submenu_item"`)
+    /// - `detail` - What went wrong
+    #[signal]
+    fn operation_failed(op: GString, detail: GString);
+
+    /// Signal emitted when the tray has been re-registered under a new tray ID.
+    ///
+    /// Emitted after `set_tray_id()` is called on a spawned tray and the tray was
+    /// spawned again with its full state.
+    #[signal]
+    fn tray_respawned();
+
+    /// Signal emitted when the host is about to show the tray menu.
+    #[signal]
+    fn menu_opened();
+
+    /// Signal emitted when the tray menu was closed.
+    ///
+    /// This is best-effort: the close notification sent by hosts is not surfaced by
+    /// ksni, so a close is detected when a menu item is clicked or the menu is opened
+    /// again. A menu dismissed without a click is only reported on the next
+    /// `menu_opened`, or after the timeout set with `set_menu_close_timeout_ms()`, and
+    /// some hosts do not report opening the menu at all. `get_capabilities()` tells
+    /// whether close notifications are delivered.
+    #[signal]
+    fn menu_closed();
+
+    /// Signal emitted once per frame with all events of that frame, when batching is
+    /// enabled with `set_batch_signal()`.
+    ///
+    /// Each event is a Dictionary with a `"type"` key holding the name of the signal
+    /// it replaces, plus that signal's arguments:
+    ///
+    /// - `menu_activated` - `"id"`
+    /// - `checkmark_toggled` - `"id"`, `"checked"`
+    /// - `radio_selected` - `"id"` (the group ID), `"index"`, `"option_id"`
+    /// - `activated` - `"x"`, `"y"`
+    /// - `scrolled` - `"steps"`, `"orientation"`
+    #[signal]
+    fn menu_events_batch(events: Array<Dictionary>);
+
+    /// Signal emitted when the play/pause item added by `add_media_controls()` is clicked.
+    ///
+    /// Like the other media control signals, it is emitted in addition to
+    /// `menu_activated`, and also when batching is enabled.
+    #[signal]
+    fn play_pause_pressed();
+
+    /// Signal emitted when the previous track item added by `add_media_controls()` is clicked.
+    #[signal]
+    fn previous_pressed();
+
+    /// Signal emitted when the next track item added by `add_media_controls()` is clicked.
+    #[signal]
+    fn next_pressed();
+
+    /// Signal emitted when the stop item added by `add_media_controls()` is clicked.
+    #[signal]
+    fn stop_pressed();
+
+    /// Creates, configures and spawns a tray icon in one call.
+    ///
+    /// Covers the simplest case of showing an icon. The returned node still has the
+    /// full API, so signals can be connected to it. Add it to the scene tree, as for
+    /// any `TrayIcon`, so its events are delivered.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - A unique identifier string, as for `set_tray_id()`
+    /// - `icon_path` - A Godot resource path, as for `set_icon_from_path()`
+    /// - `title` - The title of the tray icon
+    ///
+    /// # Returns
+    ///
+    /// Returns the spawned `TrayIcon`, or `null` if the ID is invalid, the icon could
+    /// not be loaded or the tray could not be spawned.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// var tray = TrayIcon.quick_tray("com.example.mygame", "res://icon.svg", "My Game")
+    /// if tray:
+    ///     add_child(tray)
+    ///     tray.activated.connect(func(_x, _y): get_window().grab_focus())
+    /// ```
+    #[func]
+    fn quick_tray(id: GString, icon_path: GString, title: GString) -> Option<Gd<TrayIcon>> {
+        let mut tray = TrayIcon::new_alloc();
+        let configured = {
+            let mut this = tray.bind_mut();
+            this.set_tray_id(id);
+            this.last_error == Error::OK && this.set_icon_from_path(icon_path) && {
+                this.set_title(title);
+                this.spawn_tray()
+            }
+        };
+
+        if configured {
+            Some(tray)
+        } else {
+            tray.free();
+            None
+        }
+    }
 
     /// Spawns the system tray icon.
     ///
     /// This method must be called after configuring the tray icon to make it visible in the system tray.
-    /// It should only be called once. Subsequent calls will be ignored and return false.
+    /// It should only be called once. Subsequent calls will be ignored and return false; use
+    /// `respawn_tray()` to tear down the existing tray and spawn it again.
     ///
     /// # Returns
     ///
     /// Returns `true` if the tray was successfully spawned, `false` if it was already spawned or if an error occurred.
     ///
+    /// StatusNotifierItem trays are only available on Linux. On other platforms this method
+    /// only prints a warning and returns `false`, while all other methods keep working on the
+    /// tray state, so projects can call them unconditionally.
+    ///
+    /// Looking for the tray host and registering with it is abandoned after the timeout
+    /// set with `set_spawn_timeout_ms()` (5 seconds by default), in which case
+    /// `get_last_error()` returns `ERR_TIMEOUT` and spawning can be retried later.
+    ///
+    /// If no StatusNotifierWatcher runs on the session bus, no tray host could show the
+    /// tray. The tray is not spawned, a warning with advice for the current desktop is
+    /// printed (e.g. to install the AppIndicator extension on GNOME), and
+    /// `get_last_error()` returns `ERR_NO_WATCHER`.
+    ///
     /// # Example
     ///
     /// ```gdscript
@@ -141,627 +729,4544 @@ impl TrayIcon {
     /// ```
     #[func]
     fn spawn_tray(&mut self) -> bool {
-        if self.handle.is_some() {
-            godot_warn!("Tray already spawned");
-            return false;
-        }
-
-        let (tx, rx) = channel();
-        self.event_receiver = Some(rx);
-
-        {
-            let mut state = self.state.lock().unwrap();
-            state.event_sender = Some(tx);
-        }
-
-        let state_arc = self.state.clone();
-        let tray = KsniTray { state: state_arc };
-
-        match tray.spawn() {
-            Ok(handle) => {
-                self.handle = Some(handle);
-                true
-            }
-            Err(e) => {
-                godot_error!("Failed to spawn tray: {}", e);
-                false
+        let result = self.spawn();
+        match result {
+            Err(Error::AlreadySpawned) => godot_warn!("Tray already spawned"),
+            Err(Error::Unsupported) => godot_warn!("Tray icons are not supported on this platform"),
+            Err(Error::NoWatcher) => godot_warn!(
+                "No StatusNotifierWatcher found; {}",
+                watcher::missing_watcher_hint(
+                    &std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default()
+                )
+            ),
+            Err(ref e) => godot_error!("{}", e),
+            Ok(()) => {
+                if self.engine_singleton && !self.base().is_inside_tree() {
+                    singleton::enter_tree(self.to_gd());
+                }
             }
         }
+        self.last_spawn_error = match result {
+            Ok(()) => Self::SPAWN_OK,
+            Err(Error::AlreadySpawned) => Self::SPAWN_ALREADY,
+            Err(Error::NoWatcher | Error::Timeout | Error::Unsupported) => Self::SPAWN_NO_HOST,
+            Err(_) => Self::SPAWN_DBUS_ERROR,
+        };
+        self.record_result(result)
     }
 
-    /// Sets the unique identifier for this tray icon.
+    /// Returns why the last `spawn_tray()` failed, as one of the `SPAWN_*` constants.
     ///
-    /// The ID is used by the system to identify this tray icon. It should be unique per application.
+    /// A coarser view of `get_last_error()` for spawning, kept until the next
+    /// `spawn_tray()` (or `respawn_tray()`) even when other calls change the last error.
     ///
-    /// # Parameters
+    /// # Returns
     ///
-    /// - `tray_id` - A unique identifier string (e.g., "com.example.myapp")
+    /// Returns `SPAWN_OK` if the last spawn succeeded or no spawn was attempted yet.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// if not tray_icon.spawn_tray():
+    ///     match tray_icon.get_spawn_error_code():
+    ///         TrayIcon.SPAWN_NO_HOST:
+    ///             print("No system tray available, showing the window instead")
+    ///         TrayIcon.SPAWN_DBUS_ERROR:
+    ///             print("Is a D-Bus session running?")
+    /// ```
     #[func]
-    fn set_tray_id(&mut self, tray_id: GString) {
-        let mut state = self.state.lock().unwrap();
-        state.tray_id = tray_id.to_string();
+    fn get_spawn_error_code(&self) -> i64 {
+        self.last_spawn_error
     }
 
-    /// Sets the tray icon using a system icon name.
+    /// Sets how long `spawn_tray()` waits for the tray to register with the tray host.
     ///
-    /// Uses the freedesktop icon naming specification. Common names include:
-    /// - "application-x-executable"
-    /// - "applications-games"
-    /// - "help-about"
+    /// A hung tray host can otherwise block `spawn_tray()`, and with it the game, for
+    /// the D-Bus default timeout of about 25 seconds. The same timeout bounds
+    /// `query_preferred_icon_size()`.
     ///
     /// # Parameters
     ///
-    /// - `icon_name` - The name of the system icon to use
+    /// - `ms` - Timeout in milliseconds (0 waits without a timeout; 5000 by default)
     #[func]
-    fn set_icon_name(&mut self, icon_name: GString) {
-        let mut state = self.state.lock().unwrap();
-        state.icon_name = icon_name.to_string();
+    fn set_spawn_timeout_ms(&mut self, ms: i64) {
+        self.spawn_timeout = Duration::from_millis(ms.max(0) as u64);
     }
 
-    /// Sets the path to search for icon themes.
+    /// Returns the error code of the last fallible call.
     ///
-    /// # Parameters
+    /// Set by `spawn_tray()`, `adopt_existing()`, `set_tray_id()`,
+    /// `set_icon_from_data()`, `set_icon_from_data_with_format()`,
+    /// `set_icon_pixmaps_raw()`, `pin_menu_item_to_top()`, `unpin_menu_item()` and
+    /// `set_menu_item_priority()`.
     ///
-    /// - `path` - The filesystem path to the icon theme directory
+    /// # Returns
+    ///
+    /// Returns one of the `ERR_*` constants, `ERR_OK` if the last call succeeded.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// if not tray_icon.spawn_tray() and tray_icon.get_last_error() == TrayIcon.ERR_SPAWN_FAILED:
+    ///     print("Is a D-Bus session running?")
+    /// ```
     #[func]
-    fn set_icon_theme_path(&mut self, path: GString) {
-        let mut state = self.state.lock().unwrap();
-        state.icon_theme_path = path.to_string();
+    fn get_last_error(&self) -> i64 {
+        self.last_error
     }
 
-    /// Sets the tray icon from a Godot Image resource.
+    /// Shuts down the spawned tray, if any, and spawns it again from the current state.
     ///
-    /// # Parameters
-    /// * `image` - A Godot Image resource
+    /// Unlike `refresh()`, which pushes changes to the existing registration, this
+    /// removes the icon from the host and registers a new one. The old tray service is
+    /// fully shut down first so two icons never show at once, and a new event channel
+    /// is created; events not yet delivered from the old tray are discarded. Use it to
+    /// apply changes that hosts only read at registration time.
     ///
     /// # Returns
-    /// `true` if the icon was set successfully, `false` otherwise
     ///
-    /// # Example (GDScript)
-    /// ```gdscript
-    /// var texture = load("res://icon.svg")
-    /// var image = texture.get_image()
-    /// tray_icon.set_icon_from_image(image)
-    /// ```
+    /// Returns `true` if the tray was spawned again, `false` if spawning failed.
     #[func]
-    fn set_icon_from_image(&mut self, image: Gd<Image>) -> bool {
-        // Get image dimensions
-        let width = image.get_width();
-        let height = image.get_height();
-
-        if width <= 0 || height <= 0 {
-            godot_error!("Invalid image dimensions: {}x{}", width, height);
-            return false;
-        }
-
-        // Convert to RGBA8 if needed
-        let mut img = image.duplicate().unwrap().cast::<Image>();
-        img.convert(godot::classes::image::Format::RGBA8);
-
-        // Get pixel data
-        let data = img.get_data();
-        let bytes: Vec<u8> = data.to_vec();
+    fn respawn_tray(&mut self) -> bool {
+        self.shutdown_tray();
+        self.spawn_tray()
+    }
 
-        if bytes.len() != (width * height * 4) as usize {
-            godot_error!(
-                "Image data size mismatch: expected {}, got {}",
-                width * height * 4,
-                bytes.len()
-            );
-            return false;
+    /// Sets whether the spawned tray outlives this node.
+    ///
+    /// In keep-alive mode, destroying the node (e.g. on a scene change) does not remove
+    /// the tray. Instead, the spawned tray with its state and pending events is parked
+    /// under its tray ID until another `TrayIcon` node calls `adopt_existing()` with
+    /// that ID, which resumes signal delivery on the new node.
+    ///
+    /// A kept-alive tray is owned by exactly one node at a time. Only a parked tray can
+    /// be adopted; adopting a tray that is owned by a live node fails.
+    ///
+    /// # Parameters
+    ///
+    /// - `enabled` - Whether to keep the tray alive after this node is destroyed
+    #[func]
+    fn set_keep_alive(&mut self, enabled: bool) {
+        self.keep_alive = enabled;
+        if self.handle.is_none() {
+            return;
         }
 
-        // Convert RGBA to ARGB for ksni
-        let mut argb_data = bytes.clone();
-        for pixel in argb_data.chunks_exact_mut(4) {
-            pixel.rotate_right(1);
+        if enabled {
+            self.register_kept_alive();
+        } else if let Some(tray_id) = self.kept_alive_id.take() {
+            registry::unregister(&tray_id);
         }
-
-        let mut state = self.state.lock().unwrap();
-        state.icon_pixmap = vec![ksni::Icon {
-            width,
-            height,
-            data: argb_data,
-        }];
-        state.icon_name = String::new();
-        true
     }
 
-    /// Sets the tray icon from a Godot Texture2D resource.
-    /// This is the recommended method for most use cases.
+    /// Takes over a kept-alive tray left behind by a destroyed `TrayIcon` node.
     ///
-    /// Works with exported games because it uses Godot's resource system.
+    /// The adopted tray keeps its state, including menu toggle states, and any events
+    /// that arrived while it was parked are emitted on the next frame. The node stays in
+    /// keep-alive mode.
     ///
     /// # Parameters
-    /// * `texture` - A Godot Texture2D resource (CompressedTexture2D, ImageTexture, etc.)
+    ///
+    /// - `tray_id` - ID of the kept-alive tray to adopt
     ///
     /// # Returns
-    /// `true` if the icon was set successfully, `false` otherwise
+    ///
+    /// Returns `true` if the tray was adopted, `false` if this node already has a spawned
+    /// tray, no kept-alive tray with this ID exists, or it is owned by another node.
     ///
     /// # Example (GDScript)
     /// ```gdscript
-    /// var texture = load("res://icon.svg")
-    /// tray_icon.set_icon_from_texture(texture)
+    /// if not tray_icon.adopt_existing("my_app"):
+    ///     tray_icon.set_keep_alive(true)
+    ///     tray_icon.spawn_tray()
     /// ```
     #[func]
-    fn set_icon_from_texture(&mut self, texture: Gd<Texture2D>) -> bool {
-        let image = texture.get_image();
-
-        if image.is_none() {
-            godot_error!("Failed to get image from texture");
+    fn adopt_existing(&mut self, tray_id: GString) -> bool {
+        if self.handle.is_some() {
+            godot_error!("Cannot adopt a tray: this TrayIcon already has a spawned tray");
             return false;
         }
 
-        self.set_icon_from_image(image.unwrap())
+        let tray_id = tray_id.to_string();
+        let result = registry::adopt(&tray_id).map(|tray| {
+            self.stats = tray.state.lock().unwrap().stats.clone();
+            self.state = tray.state;
+            self.handle = Some(tray.handle);
+            self.event_receiver = Some(tray.event_receiver);
+            self.last_refresh_hash = None;
+            self.keep_alive = true;
+            self.kept_alive_id = Some(tray_id);
+        });
+        if let Err(ref e) = result {
+            godot_error!("Failed to adopt tray: {}", e);
+        }
+        self.record_result(result)
     }
 
-    /// Sets the tray icon by loading a texture from a Godot resource path.
-    /// This is a convenience wrapper around set_icon_from_texture().
+    /// Re-emits the current state of every checkmark and radio group as signals.
     ///
-    /// Works with exported games because it uses ResourceLoader.
-    ///
-    /// # Parameters
-    /// * `path` - A Godot resource path (e.g., "res://icon.svg")
-    ///
-    /// # Returns
-    /// `true` if the icon was loaded and set successfully, `false` otherwise
+    /// Emits `checkmark_toggled` for each checkmark and `radio_selected` for each radio
+    /// group with its current value, in menu order, including items inside submenus.
+    /// `checkmark_state_changed` and `radio_state_changed` are emitted alongside with
+    /// `SOURCE_PROGRAM`. Only reads the tray state, so it is safe to call before
+    /// `spawn_tray()`.
     ///
     /// # Example (GDScript)
     /// ```gdscript
-    /// tray_icon.set_icon_from_path("res://icon.svg")
+    /// func _on_settings_opened():
+    ///     tray_icon.emit_current_states()
     /// ```
     #[func]
-    fn set_icon_from_path(&mut self, path: GString) -> bool {
-        let mut loader = ResourceLoader::singleton();
-        let resource = loader.load(&path);
-
-        if resource.is_none() {
-            godot_error!("Failed to load resource from path: {}", path);
-            return false;
-        }
+    fn emit_current_states(&mut self) {
+        let events = self.state.lock().unwrap().current_toggle_states();
 
-        let texture = resource.unwrap().try_cast::<Texture2D>();
-        if texture.is_err() {
-            godot_error!("Resource is not a Texture2D: {}", path);
-            return false;
+        for event in events {
+            self.emit_state_changed(&event, Self::SOURCE_PROGRAM);
+            match event {
+                TrayEvent::CheckmarkToggled(id, checked) => {
+                    let id = self.id_variant(&id);
+                    self.base_mut()
+                        .emit_signal("checkmark_toggled", &[id, Variant::from(checked)]);
+                }
+                TrayEvent::RadioSelected(group_id, index, option_id) => {
+                    let group_id = self.id_variant(&group_id);
+                    let option_id = self.id_variant(&option_id);
+                    self.base_mut().emit_signal(
+                        "radio_selected",
+                        &[group_id, Variant::from(index as i64), option_id],
+                    );
+                }
+                _ => {}
+            }
         }
-
-        self.set_icon_from_texture(texture.unwrap())
     }
 
-    /// Sets the tray icon from raw RGBA pixel data.
-    ///
-    /// The data should be in RGBA format with 4 bytes per pixel.
+    /// Emits all events waiting in the tray's event channel immediately, instead of on
+    /// the next frame.
     ///
-    /// # Parameters
-    ///
-    /// - `width` - Width of the icon in pixels
-    /// - `height` - Height of the icon in pixels
-    /// - `data` - Raw pixel data as RGBA bytes (length must be width * height * 4)
+    /// Call this from a quit handler (e.g. on `NOTIFICATION_WM_CLOSE_REQUEST`) so a last
+    /// click is not lost when the node is freed before it is processed again. Does
+    /// nothing if the tray is not spawned.
     ///
     /// # Returns
     ///
-    /// Returns `true` if the icon was set successfully, `false` if the data size is invalid.
+    /// Returns the number of events taken from the channel, including ones dropped by
+    /// debouncing.
     ///
     /// # Example (GDScript)
     /// ```gdscript
-    /// var data = PackedByteArray()
-    /// # Fill data with RGBA values...
-    /// tray_icon.set_icon_from_data(32, 32, data)
+    /// func _notification(what):
+    ///     if what == NOTIFICATION_WM_CLOSE_REQUEST:
+    ///         tray_icon.flush_events()
+    ///         get_tree().quit()
     /// ```
     #[func]
-    fn set_icon_from_data(&mut self, width: i32, height: i32, data: PackedByteArray) -> bool {
-        let bytes: Vec<u8> = data.to_vec();
+    fn flush_events(&mut self) -> i64 {
+        self.dispatch_events()
+    }
 
-        if bytes.len() != (width * height * 4) as usize {
-            godot_error!("Invalid icon data size");
+    /// Pushes the current tray state to the system tray.
+    ///
+    /// Changes made through the setters are stored immediately but only become visible
+    /// to the system tray host once the tray is refreshed. The update is skipped if
+    /// nothing changed since the last refresh, so calling this defensively every frame
+    /// does not cause extra D-Bus traffic.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if an update was sent, `false` if the tray is not spawned or nothing changed.
+    #[func]
+    fn refresh(&mut self) -> bool {
+        let Some(ref handle) = self.handle else {
             return false;
-        }
+        };
 
-        let mut argb_data = bytes.clone();
-        for pixel in argb_data.chunks_exact_mut(4) {
-            pixel.rotate_right(1);
+        let changed = self
+            .state
+            .lock()
+            .unwrap()
+            .update_content_hash(&mut self.last_refresh_hash);
+        if !changed {
+            return false;
         }
 
-        let mut state = self.state.lock().unwrap();
-        state.icon_pixmap = vec![ksni::Icon {
-            width,
-            height,
-            data: argb_data,
-        }];
-        state.icon_name = String::new();
+        let started = Instant::now();
+        handle.update(|_tray: &mut KsniTray| {});
+        TrayStats::increment(&self.stats.update_pushes);
+        self.stats
+            .last_update_usec
+            .store(started.elapsed().as_micros() as u64, Ordering::Relaxed);
         true
     }
 
-    /// Clears the custom icon pixmap data.
+    /// Returns runtime counters for diagnosing performance issues.
     ///
-    /// After calling this, the tray will fall back to using the icon name set by
-    /// `set_icon_name()` if one was specified.
+    /// The counters are kept since the node was created or `reset_stats()` was last
+    /// called, and survive respawns.
+    ///
+    /// # Returns
+    ///
+    /// Returns a Dictionary with the following keys:
+    ///
+    /// - `update_pushes` - Changes pushed to the host by `refresh()`
+    /// - `menu_rebuilds` - Times the host had the menu rebuilt from the tray state
+    /// - `events_received` - Events taken from the tray's event channel
+    /// - `events_emitted` - Events emitted as signals
+    /// - `events_dropped` - Events discarded by debouncing or because the node was gone
+    /// - `channel_backlog` - Events waiting in the channel for the next frame
+    /// - `last_update_usec` - Duration of the last update push, in microseconds
+    /// - `icon_conversions` - Images converted into icon pixmaps
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// print(tray_icon.get_stats())
+    /// ```
     #[func]
-    fn clear_icon_pixmap(&mut self) {
-        let mut state = self.state.lock().unwrap();
-        state.icon_pixmap.clear();
+    fn get_stats(&self) -> Dictionary {
+        let stats = &self.stats;
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed) as i64;
+        vdict! {
+            "update_pushes": load(&stats.update_pushes),
+            "menu_rebuilds": load(&stats.menu_rebuilds),
+            "events_received": load(&stats.events_received),
+            "events_emitted": load(&stats.events_emitted),
+            "events_dropped": load(&stats.events_dropped),
+            "channel_backlog": stats.channel_backlog() as i64,
+            "last_update_usec": load(&stats.last_update_usec),
+            "icon_conversions": load(&stats.icon_conversions),
+        }
     }
 
-    /// Sets the title text displayed next to the tray icon.
-    ///
-    /// # Parameters
-    ///
-    /// - `title` - The title text to display
+    /// Resets all counters reported by `get_stats()` to zero.
     #[func]
-    fn set_title(&mut self, title: GString) {
-        let mut state = self.state.lock().unwrap();
-        state.title = title.to_string();
+    fn reset_stats(&mut self) {
+        self.stats.reset();
     }
 
-    /// Sets the tooltip displayed when hovering over the tray icon.
+    /// Sets how many of the most recent tray events are kept for `get_event_history()`.
+    ///
+    /// History is disabled by default. Shrinking the size drops the oldest entries, and
+    /// a size of 0 disables recording and clears the history.
     ///
     /// # Parameters
     ///
-    /// - `title` - The main tooltip text
-    /// - `subtitle` - Additional tooltip text displayed below the title
-    /// - `icon_name` - System icon name to display in the tooltip
+    /// - `size` - Maximum number of events to keep
     #[func]
-    fn set_tooltip(&mut self, title: GString, subtitle: GString, icon_name: GString) {
-        let mut state = self.state.lock().unwrap();
-        state.tooltip_title = title.to_string();
-        state.tooltip_subtitle = subtitle.to_string();
-        state.tooltip_icon_name = icon_name.to_string();
+    fn set_event_history_size(&mut self, size: i64) {
+        self.history_size = size.max(0) as usize;
+        while self.event_history.len() > self.history_size {
+            self.event_history.pop_front();
+        }
     }
 
-    /// Clears all menu items from the tray menu.
+    /// Returns the most recent events received from the tray, for debugging.
     ///
-    /// This is useful when rebuilding the menu from scratch.
+    /// Events are recorded as they are taken from the tray, before debouncing, so the
+    /// history also shows events that did not emit a signal.
+    ///
+    /// # Returns
+    ///
+    /// Returns an Array of Dictionaries, oldest first, each with the keys `type` (the
+    /// event name, e.g. `"menu_activated"`) and `value` (the event's first argument, or
+    /// `null` for events without arguments).
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_event_history_size(20)
+    /// # ...
+    /// for entry in tray_icon.get_event_history():
+    ///     print(entry.type, ": ", entry.value)
+    /// ```
     #[func]
-    fn clear_menu(&mut self) {
-        let mut state = self.state.lock().unwrap();
-        state.menu.clear();
+    fn get_event_history(&self) -> Array<Dictionary> {
+        self.event_history
+            .iter()
+            .map(|(event_type, value)| {
+                vdict! {
+                    "type": event_type.as_str(),
+                    "value": value.clone(),
+                }
+            })
+            .collect()
     }
 
-    /// Adds a standard clickable menu item.
+    /// Sets the level of internal log messages printed to the Godot console.
     ///
-    /// When clicked, emits the `menu_activated` signal with the item's ID.
+    /// Messages from the tray background thread (D-Bus errors, the host going away) are
+    /// queued and printed on the next frame: errors with `push_error`, warnings with
+    /// `push_warning`, and everything else with `print`. Only warnings and errors are
+    /// printed by default. The level is global to all TrayIcon nodes.
+    ///
+    /// The level is the process-wide maximum level of the Rust `log` crate, so it also
+    /// applies to other Rust extensions in the same process that log through `log`.
+    /// Up to 256 queued messages are kept between frames; older ones are dropped.
     ///
     /// # Parameters
     ///
-    /// - `id` - Unique identifier for this menu item
-    /// - `label` - Text displayed in the menu
-    /// - `icon_name` - System icon name (empty string for no icon)
-    /// - `enabled` - Whether the item can be clicked
-    /// - `visible` - Whether the item is visible
+    /// - `level` - 0 = off, 1 = error, 2 = warn, 3 = info, 4 = debug, 5 = trace
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_log_level(4)
+    /// ```
     #[func]
-    fn add_menu_item(
-        &mut self,
-        id: GString,
-        label: GString,
-        icon_name: GString,
-        enabled: bool,
-        visible: bool,
-    ) {
-        let mut state = self.state.lock().unwrap();
-        state.menu.push(MenuItemData::Standard {
-            id: id.to_string(),
-            label: label.to_string(),
-            icon_name: icon_name.to_string(),
-            enabled,
-            visible,
-        });
+    fn set_log_level(&mut self, level: i64) {
+        logging::set_level(logging::level_filter(level));
     }
 
-    /// Adds a menu item with a checkmark that can be toggled.
+    /// Returns version information about this build of godot-ksni.
     ///
-    /// When toggled, emits the `checkmark_toggled` signal with the item's ID and new state.
+    /// # Returns
     ///
-    /// # Parameters
+    /// Returns a Dictionary with the following keys:
     ///
-    /// - `id` - Unique identifier for this checkmark item
-    /// - `label` - Text displayed in the menu
-    /// - `icon_name` - System icon name (empty string for no icon)
-    /// - `checked` - Initial checked state
-    /// - `enabled` - Whether the item can be clicked
-    /// - `visible` - Whether the item is visible
+    /// - `version` - Version of godot-ksni
+    /// - `ksni_version` - Version of ksni it was compiled against, or `"unknown"`
+    /// - `git_hash` - Short git commit hash it was built from, or an empty string
+    /// - `features` - Enabled Cargo feature flags, as a PackedStringArray
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// print(TrayIcon.get_version().version)
+    /// ```
     #[func]
-    fn add_checkmark_item(
-        &mut self,
-        id: GString,
-        label: GString,
-        icon_name: GString,
-        checked: bool,
-        enabled: bool,
-        visible: bool,
-    ) {
-        let mut state = self.state.lock().unwrap();
-        state.menu.push(MenuItemData::Checkmark {
-            id: id.to_string(),
-            label: label.to_string(),
-            icon_name: icon_name.to_string(),
-            enabled,
-            visible,
-            checked,
-        });
+    fn get_version() -> Dictionary {
+        let features: PackedStringArray = Self::enabled_features()
+            .iter()
+            .map(|feature| GString::from(*feature))
+            .collect();
+        vdict! {
+            "version": env!("CARGO_PKG_VERSION"),
+            "ksni_version": env!("GODOT_KSNI_KSNI_VERSION"),
+            "git_hash": env!("GODOT_KSNI_GIT_HASH"),
+            "features": features,
+        }
     }
 
-    /// Creates a new radio button group.
+    /// Returns version information about this build as a single line for bug reports.
     ///
-    /// Radio options must be added to this group using `add_radio_option`.
-    /// Only one option in a group can be selected at a time.
-    ///
-    /// # Parameters
-    ///
-    /// - `id` - Unique identifier for this radio group
-    /// - `selected` - Index of the initially selected option (0-based)
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// print(TrayIcon.get_version_string())
+    /// # godot-ksni 0.1.2 (ksni 0.3.6, git 1a2b3c4, features: gdextension)
+    /// ```
     #[func]
-    fn add_radio_group(&mut self, id: GString, selected: i64) {
-        let mut state = self.state.lock().unwrap();
-        state.menu.push(MenuItemData::RadioGroup {
-            id: id.to_string(),
-            selected: selected as usize,
-            options: Vec::new(),
-        });
+    fn get_version_string() -> GString {
+        let mut details = vec![format!("ksni {}", env!("GODOT_KSNI_KSNI_VERSION"))];
+        let git_hash = env!("GODOT_KSNI_GIT_HASH");
+        if !git_hash.is_empty() {
+            details.push(format!("git {}", git_hash));
+        }
+        let features = Self::enabled_features();
+        if !features.is_empty() {
+            details.push(format!("features: {}", features.join(", ")));
+        }
+
+        let version = format!(
+            "godot-ksni {} ({})",
+            env!("CARGO_PKG_VERSION"),
+            details.join(", ")
+        );
+        GString::from(version.as_str())
     }
 
-    /// Adds a radio button option to an existing radio group.
+    /// Enables or disables the whole tray without despawning it.
     ///
-    /// When selected, emits the `radio_selected` signal with the group ID, option index, and option ID.
+    /// When disabled, the tray status is set to passive (hiding the icon on hosts that
+    /// respect it) and all menu interactions are disabled. Re-enabling restores the
+    /// previous status. All other state is preserved, and the tray is refreshed.
     ///
     /// # Parameters
     ///
-    /// - `group_id` - ID of the radio group to add this option to
-    /// - `option_id` - Unique identifier for this option
-    /// - `label` - Text displayed in the menu
-    /// - `icon_name` - System icon name (empty string for no icon)
-    /// - `enabled` - Whether the option can be selected
-    /// - `visible` - Whether the option is visible
+    /// - `enabled` - Whether the tray is enabled
+    #[func]
+    fn set_tray_enabled(&mut self, enabled: bool) {
+        self.state.lock().unwrap().set_tray_enabled(enabled);
+        self.refresh();
+    }
+
+    /// Returns whether the tray is enabled.
     ///
     /// # Returns
     ///
-    /// Returns `true` if the option was added successfully, `false` if the group was not found.
+    /// Returns `false` after `set_tray_enabled(false)`, while the tray status is passive.
     #[func]
-    fn add_radio_option(
-        &mut self,
-        group_id: GString,
-        option_id: GString,
-        label: GString,
-        icon_name: GString,
-        enabled: bool,
-        visible: bool,
-    ) -> bool {
-        let mut state = self.state.lock().unwrap();
-        let group_id_str = group_id.to_string();
-
-        for item in &mut state.menu {
-            if let MenuItemData::RadioGroup { id, options, .. } = item
-                && id == &group_id_str
-            {
-                options.push(RadioItemData {
-                    id: option_id.to_string(),
-                    label: label.to_string(),
-                    icon_name: icon_name.to_string(),
-                    enabled,
-                    visible,
-                });
-                return true;
-            }
-        }
-        false
+    fn is_tray_enabled(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.tray_enabled
     }
 
-    /// Adds a visual separator line to the menu.
+    /// Sets whether `menu_activated` reports items inside submenus by their path.
+    ///
+    /// When enabled, standard items inside submenus are reported with the labels of their
+    /// enclosing submenus as a slash-separated prefix, e.g. `"File/Recent/open_file_1"`,
+    /// so items with the same ID in different submenus can be told apart. Top-level
+    /// items are reported by their plain ID. Disabled by default.
+    ///
+    /// # Parameters
+    ///
+    /// - `enabled` - Whether to emit path IDs
     #[func]
-    fn add_separator(&mut self) {
+    fn set_use_path_ids(&mut self, enabled: bool) {
         let mut state = self.state.lock().unwrap();
-        state.menu.push(MenuItemData::Separator);
+        state.use_path_ids = enabled;
     }
 
-    /// Creates a submenu that can contain other menu items.
+    /// Sets whether activating the tray icon opens the menu as a Godot `PopupMenu`.
     ///
-    /// After calling this, use `add_submenu_item`, `add_submenu_checkmark`, and `add_submenu_separator`
-    /// to add items to the submenu.
+    /// Some minimal tray hosts never fetch the dbusmenu, so the tray menu cannot be
+    /// opened there. With the fallback enabled, each activation (usually a left click)
+    /// opens a PopupMenu built from the same menu items at the activation position, or
+    /// at the mouse position if the host reports none. Checkmarks and radio groups
+    /// behave as in the tray menu, and clicks emit the same signals. Disabled by default.
+    ///
+    /// Whether a host supports dbusmenu cannot be detected, so the fallback must be
+    /// enabled explicitly, e.g. from a user setting.
     ///
     /// # Parameters
     ///
-    /// - `label` - Text displayed for the submenu
-    /// - `icon_name` - System icon name (empty string for no icon)
-    /// - `enabled` - Whether the submenu can be opened
-    /// - `visible` - Whether the submenu is visible
+    /// - `enabled` - Whether to open the fallback menu on activation
     #[func]
-    fn begin_submenu(&mut self, label: GString, icon_name: GString, enabled: bool, visible: bool) {
-        let mut state = self.state.lock().unwrap();
-        state.menu.push(MenuItemData::SubMenu {
-            label: label.to_string(),
-            icon_name: icon_name.to_string(),
-            enabled,
-            visible,
-            submenu: Vec::new(),
-        });
+    fn set_menu_fallback(&mut self, enabled: bool) {
+        self.menu_fallback = enabled;
+        if !enabled && let Some(mut popup) = self.fallback_popup.take() {
+            popup.queue_free();
+        }
     }
 
-    /// Adds a standard menu item to an existing submenu.
+    /// Sets whether events are delivered in a single `menu_events_batch` signal per frame.
+    ///
+    /// When enabled, `menu_activated`, `checkmark_toggled`, `radio_selected`,
+    /// `activated` and `activated_at_screen` are no longer emitted. Instead, the events
+    /// received during a frame are emitted together, in order, as one Array of
+    /// Dictionaries. Debouncing, item callbacks and suppression still apply. Disabled
+    /// by default.
     ///
     /// # Parameters
     ///
-    /// - `submenu_label` - Label of the parent submenu
-    /// - `id` - Unique identifier for this menu item
-    /// - `label` - Text displayed in the submenu
-    /// - `icon_name` - System icon name (empty string for no icon)
-    /// - `enabled` - Whether the item can be clicked
-    /// - `visible` - Whether the item is visible
+    /// - `enabled` - Whether to batch events
     ///
-    /// # Returns
+    /// # Example (GDScript)
     ///
-    /// Returns `true` if the item was added successfully, `false` if the submenu was not found.
+    /// ```gdscript
+    /// tray.set_batch_signal(true)
+    /// tray.menu_events_batch.connect(func(events):
+    ///     for event in events:
+    ///         if event["type"] == "checkmark_toggled":
+    ///             settings[event["id"]] = event["checked"]
+    /// )
+    /// ```
     #[func]
-    fn add_submenu_item(
-        &mut self,
-        submenu_label: GString,
-        id: GString,
-        label: GString,
-        icon_name: GString,
-        enabled: bool,
-        visible: bool,
-    ) -> bool {
-        let mut state = self.state.lock().unwrap();
-        let submenu_label_str = submenu_label.to_string();
-
-        for item in &mut state.menu {
-            if let MenuItemData::SubMenu {
-                label: sub_label,
-                submenu,
-                ..
-            } = item
-                && sub_label == &submenu_label_str
-            {
-                submenu.push(MenuItemData::Standard {
-                    id: id.to_string(),
-                    label: label.to_string(),
-                    icon_name: icon_name.to_string(),
-                    enabled,
-                    visible,
-                });
-                return true;
-            }
-        }
-        false
+    fn set_batch_signal(&mut self, enabled: bool) {
+        self.batch_signal = enabled;
     }
 
-    /// Adds a checkmark item to an existing submenu.
+    /// Sets whether menu signals pass IDs as StringName instead of String.
+    ///
+    /// Applies to `menu_activated`, `checkmark_toggled`, `radio_selected`, their
+    /// `*_state_changed` counterparts and `menu_events_batch`. The StringName of each ID
+    /// is created on its first event and reused afterwards, so frequent events for the
+    /// same items do not build new strings, and matching them against `&"id"` literals
+    /// is cheap. Handlers taking the IDs as `String` in GDScript keep working, but
+    /// handlers with typed `GString` parameters in other GDExtensions do not, so this is
+    /// disabled by default.
+    ///
+    /// Functions taking IDs accept StringNames as well as Strings either way, as Godot
+    /// converts between the two when calling them.
     ///
     /// # Parameters
     ///
-    /// - `submenu_label` - Label of the parent submenu
-    /// - `id` - Unique identifier for this checkmark item
-    /// - `label` - Text displayed in the submenu
-    /// - `icon_name` - System icon name (empty string for no icon)
-    /// - `checked` - Initial checked state
-    /// - `enabled` - Whether the item can be clicked
-    /// - `visible` - Whether the item is visible
+    /// - `enabled` - Whether to pass IDs as StringName
     ///
-    /// # Returns
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_string_name_ids(true)
+    /// tray_icon.menu_activated.connect(func(id):
+    ///     match id:
+    ///         &"quit":
+    ///             get_tree().quit()
+    /// )
+    /// ```
+    #[func]
+    fn set_string_name_ids(&mut self, enabled: bool) {
+        self.string_name_ids = enabled;
+        self.id_names.clear();
+    }
+
+    /// Sets whether failed menu operations emit `operation_failed`.
     ///
-    /// Returns `true` if the item was added successfully, `false` if the submenu was not found.
+    /// Covers the submenu, radio option and item replacement functions and the setters
+    /// of toggle states, which otherwise only return `false`. Disabled by default to
+    /// avoid noise in production.
+    ///
+    /// # Parameters
+    ///
+    /// - `enabled` - Whether to emit `operation_failed`
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_debug_mode(OS.is_debug_build())
+    /// tray_icon.operation_failed.connect(func(op, detail): push_warning(op + ": " + detail))
+    /// ```
     #[func]
-    fn add_submenu_checkmark(
-        &mut self,
-        submenu_label: GString,
-        id: GString,
-        label: GString,
-        icon_name: GString,
-        checked: bool,
-        enabled: bool,
-        visible: bool,
-    ) -> bool {
-        let mut state = self.state.lock().unwrap();
-        let submenu_label_str = submenu_label.to_string();
+    fn set_debug_mode(&mut self, enabled: bool) {
+        self.debug_mode = enabled;
+    }
 
-        for item in &mut state.menu {
-            if let MenuItemData::SubMenu {
-                label: sub_label,
-                submenu,
-                ..
-            } = item
-                && sub_label == &submenu_label_str
-            {
-                submenu.push(MenuItemData::Checkmark {
-                    id: id.to_string(),
-                    label: label.to_string(),
-                    icon_name: icon_name.to_string(),
-                    enabled,
-                    visible,
-                    checked,
-                });
-                return true;
+    /// Handles a click in the fallback menu opened by `set_menu_fallback()`.
+    #[func]
+    fn _on_fallback_menu_id_pressed(&mut self, id: i64) {
+        if let Some(action) = self.fallback_actions.get(id as usize) {
+            let mut state = self.state.lock().unwrap();
+            if state.is_current_menu(self.fallback_generation) {
+                action.run(&mut state);
             }
         }
-        false
     }
 
-    /// Adds a separator to an existing submenu.
+    /// Returns where the tray icon was last activated, mapped onto Godot's screens.
+    ///
+    /// The position reported by the host is matched against the screen rectangles of
+    /// `DisplayServer`, for example to place a window next to the tray icon. A position
+    /// outside every screen is attributed to the nearest screen.
+    ///
+    /// # Returns
+    ///
+    /// Returns a Dictionary with the following keys:
+    ///
+    /// - `available` - `false` if the tray icon was not activated yet, or when running on
+    ///   Wayland, which has no global coordinates; the other values are then meaningless
+    /// - `position` - The position reported by the host, as a `Vector2i` in global
+    ///   screen coordinates
+    /// - `screen` - Index of the screen the position is on, or -1 if unavailable
+    /// - `screen_position` - The position relative to the top left corner of that screen
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// func _on_tray_activated(_x, _y):
+    ///     var activation = tray_icon.get_last_activation_position()
+    ///     if activation.available:
+    ///         popup.current_screen = activation.screen
+    ///         popup.position = activation.position
+    /// ```
+    #[func]
+    fn get_last_activation_position(&self) -> Dictionary {
+        let display_server = DisplayServer::singleton();
+        let position = self.last_activation.unwrap_or(Vector2i::ZERO);
+        // Wayland has no global coordinates to map.
+        let mappable =
+            self.last_activation.is_some() && display_server.get_name().to_string() != "Wayland";
+        let located = if mappable {
+            let screens: Vec<ScreenRect> = (0..display_server.get_screen_count())
+                .map(|index| {
+                    let origin = display_server.screen_get_position_ex().screen(index).done();
+                    let size = display_server.screen_get_size_ex().screen(index).done();
+                    ScreenRect {
+                        x: origin.x,
+                        y: origin.y,
+                        width: size.x,
+                        height: size.y,
+                    }
+                })
+                .collect();
+            screen::locate(position.x, position.y, &screens)
+        } else {
+            None
+        };
+
+        match located {
+            Some(located) => vdict! {
+                "available": true,
+                "position": position,
+                "screen": located.screen as i64,
+                "screen_position": Vector2i::new(located.x, located.y),
+            },
+            None => vdict! {
+                "available": false,
+                "position": position,
+                "screen": -1,
+                "screen_position": Vector2i::ZERO,
+            },
+        }
+    }
+
+    /// Sets how scroll deltas are accumulated before `scrolled` is emitted.
+    ///
+    /// Deltas are summed per orientation, and `scrolled` is emitted with the number of
+    /// whole steps once the sum reaches `threshold`. The remainder carries over to the
+    /// next events, and scrolling in the opposite direction discards it. This turns the
+    /// many small deltas of high resolution touchpads into discrete steps. For KDE
+    /// Plasma, a threshold of 120 gives one step per mouse wheel notch.
     ///
     /// # Parameters
     ///
-    /// - `submenu_label` - Label of the parent submenu
+    /// - `threshold` - Delta per step, or 0 to emit every delta unchanged (the default)
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_scroll_accumulation(120)
+    /// tray_icon.scrolled.connect(func(steps, orientation):
+    ///     if orientation == TrayIcon.ORIENTATION_VERTICAL:
+    ///         volume = clampi(volume - steps * 5, 0, 100)
+    /// )
+    /// ```
+    #[func]
+    fn set_scroll_accumulation(&mut self, threshold: i64) {
+        self.scroll = ScrollAccumulator::new(threshold.clamp(0, i32::MAX as i64) as i32);
+    }
+
+    /// Sets how long after `menu_opened` the menu is assumed to be closed.
+    ///
+    /// ksni does not deliver the close notifications of hosts, so a menu dismissed
+    /// without a click is otherwise only reported by `menu_closed` when it is opened
+    /// again. With a timeout, `menu_closed` is also emitted once the menu has been open
+    /// for that long, even if it is still shown.
+    ///
+    /// # Parameters
+    ///
+    /// - `ms` - Timeout in milliseconds (0 disables the fallback, the default)
+    #[func]
+    fn set_menu_close_timeout_ms(&mut self, ms: i64) {
+        self.menu_close_timeout = Duration::from_millis(ms.max(0) as u64);
+    }
+
+    /// Returns which host notifications this tray can deliver as signals.
     ///
     /// # Returns
     ///
-    /// Returns `true` if the separator was added successfully, `false` if the submenu was not found.
+    /// A Dictionary with the following boolean keys:
+    /// - `menu_opened` - `menu_opened` is emitted when the host is about to show the menu
+    /// - `menu_closed` - `menu_closed` is emitted when the host reports the menu closed.
+    ///   Always `false`, since ksni does not deliver close notifications; `menu_closed`
+    ///   then relies on the fallbacks documented on the signal
+    /// - `scrolled` - `scrolled` is emitted for scroll events over the icon
     #[func]
-    fn add_submenu_separator(&mut self, submenu_label: GString) -> bool {
+    fn get_capabilities(&self) -> Dictionary {
+        vdict! {
+            "menu_opened": true,
+            "menu_closed": false,
+            "scrolled": true,
+        }
+    }
+
+    /// Sets the debounce window for standard menu item activations.
+    ///
+    /// Some tray hosts occasionally deliver a single click as two activations. When a
+    /// debounce window is set, repeated `menu_activated` events for the same item ID
+    /// within the window are collapsed into one. Checkmark and radio events are never
+    /// debounced since each of them carries state.
+    ///
+    /// # Parameters
+    ///
+    /// - `ms` - Debounce window in milliseconds (0 disables debouncing, the default)
+    #[func]
+    fn set_activation_debounce_ms(&mut self, ms: i64) {
+        self.activation_debounce = Duration::from_millis(ms.max(0) as u64);
+        self.last_activations.clear();
+    }
+
+    /// Sets a cooldown that throttles rapid activations of a menu item.
+    ///
+    /// Activations of the item that happen less than `ms` milliseconds after the last
+    /// accepted one are swallowed and do not emit `menu_activated`.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - ID of the menu item
+    /// - `ms` - Cooldown in milliseconds (0 disables the cooldown)
+    #[func]
+    fn set_menu_item_activation_cooldown(&mut self, id: GString, ms: i64) {
         let mut state = self.state.lock().unwrap();
-        let submenu_label_str = submenu_label.to_string();
+        state.set_activation_cooldown(&id.to_string(), Duration::from_millis(ms.max(0) as u64));
+    }
 
-        for item in &mut state.menu {
-            if let MenuItemData::SubMenu {
-                label: sub_label,
-                submenu,
-                ..
-            } = item
-                && sub_label == &submenu_label_str
-            {
-                submenu.push(MenuItemData::Separator);
-                return true;
+    /// Sets the unique identifier for this tray icon.
+    ///
+    /// The ID is used by the system to identify this tray icon. It should be unique per application.
+    ///
+    /// Defaults to the project name in lowercase with other characters replaced by
+    /// underscores (e.g. `"My Game"` becomes `"my_game"`), or `"godot_tray_icon"` if unset.
+    ///
+    /// The ID must be usable as a D-Bus name: ASCII letters, digits, `_` and `-`, in
+    /// elements separated by dots that do not start with a digit. Other IDs are sanitized
+    /// with a warning (e.g. `"My App!"` becomes `"My_App_"` and `"2048"` becomes
+    /// `"_2048"`); use `get_tray_id()` to read the ID in use. An ID without any ASCII
+    /// letter or digit is rejected, keeping the current ID, and `get_last_error()`
+    /// returns `ERR_INVALID_TRAY_ID`.
+    ///
+    /// If the tray is already spawned, it is shut down and spawned again under the new ID,
+    /// keeping its full state, and `tray_respawned` is emitted once that succeeds. Events
+    /// not yet delivered from the old registration are discarded.
+    ///
+    /// # Parameters
+    ///
+    /// - `tray_id` - A unique identifier string (e.g., "com.example.myapp")
+    #[func]
+    fn set_tray_id(&mut self, tray_id: GString) {
+        let requested = tray_id.to_string();
+        let tray_id = match TrayState::validate_tray_id(&requested) {
+            Ok(tray_id) => tray_id,
+            Err(e) => {
+                godot_error!("{}", e);
+                self.record_result(Err(e));
+                return;
             }
+        };
+        self.record_result(Ok(()));
+        if tray_id != requested {
+            godot_warn!(
+                "Tray ID '{}' is not a valid D-Bus name, using '{}'",
+                requested,
+                tray_id
+            );
+        }
+
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.tray_id == tray_id {
+                return;
+            }
+            state.tray_id = tray_id;
+        }
+
+        if self.handle.is_none() {
+            return;
+        }
+
+        if self.respawn_tray() {
+            self.base_mut().emit_signal("tray_respawned", &[]);
         }
-        false
     }
 
-    /// Programmatically sets the state of a checkmark item.
+    /// Returns the identifier of this tray icon.
+    #[func]
+    fn get_tray_id(&self) -> GString {
+        let state = self.state.lock().unwrap();
+        GString::from(&state.tray_id)
+    }
+
+    /// Associates the tray icon with a window for taskbar integration.
+    ///
+    /// Sets the StatusNotifierItem `WindowId` property, which lets hosts link the tray
+    /// icon to an X11 window. Pass 0 to clear the association. The property is a 32-bit
+    /// integer, so negative IDs and IDs above 2147483647 are rejected with an error and
+    /// the association is left unchanged.
     ///
     /// # Parameters
     ///
-    /// - `id` - ID of the checkmark item to modify
-    /// - `checked` - New checked state
+    /// - `wid` - The native window ID (e.g. from `DisplayServer.window_get_native_handle(DisplayServer.WINDOW_HANDLE)`)
+    #[func]
+    fn set_window_id(&mut self, wid: i64) {
+        if !i32::try_from(wid).is_ok_and(|wid| wid >= 0) {
+            godot_error!("Window ID {} is out of range for the tray's WindowId", wid);
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.window_id = (wid != 0).then_some(wid);
+    }
+
+    /// Returns the window ID associated with the tray icon.
     ///
     /// # Returns
     ///
-    /// Returns `true` if the checkmark was found and updated, `false` otherwise.
+    /// Returns the window ID set with `set_window_id()`, or 0 if none is set.
     #[func]
-    fn set_checkmark_state(&mut self, id: GString, checked: bool) -> bool {
+    fn get_window_id(&self) -> i64 {
+        let state = self.state.lock().unwrap();
+        state.window_id.unwrap_or(0)
+    }
+
+    /// Sets the tray icon using a system icon name, and refreshes the tray if it is spawned.
+    ///
+    /// Uses the freedesktop icon naming specification. Common names include:
+    /// - "application-x-executable"
+    /// - "applications-games"
+    /// - "help-about"
+    ///
+    /// # Parameters
+    ///
+    /// - `icon_name` - The name of the system icon to use
+    #[func]
+    fn set_icon_name(&mut self, icon_name: GString) {
+        self.state
+            .lock()
+            .unwrap()
+            .set_icon_name(icon_name.to_string());
+        self.refresh();
+    }
+
+    /// Returns the name of the system icon, or an empty string if the icon was set from pixmaps.
+    #[func]
+    fn get_icon_name(&self) -> GString {
+        let state = self.state.lock().unwrap();
+        GString::from(&state.icon_name)
+    }
+
+    /// Sets the path to search for icon themes.
+    ///
+    /// # Parameters
+    ///
+    /// - `path` - The filesystem path to the icon theme directory
+    #[func]
+    fn set_icon_theme_path(&mut self, path: GString) {
         let mut state = self.state.lock().unwrap();
-        let id_str = id.to_string();
+        state.icon_theme_path = path.to_string();
+    }
 
-        for item in &mut state.menu {
-            if let MenuItemData::Checkmark {
-                id: item_id,
-                checked: item_checked,
-                ..
-            } = item
-                && item_id == &id_str
-            {
-                *item_checked = checked;
-                return true;
-            }
-        }
-        false
+    /// Sets the tray icon from a Godot Image resource.
+    ///
+    /// # Parameters
+    /// * `image` - A Godot Image resource
+    ///
+    /// # Returns
+    /// `true` if the icon was set successfully, `false` otherwise
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// var texture = load("res://icon.svg")
+    /// var image = texture.get_image()
+    /// tray_icon.set_icon_from_image(image)
+    /// ```
+    #[func]
+    fn set_icon_from_image(&mut self, image: Gd<Image>) -> bool {
+        let Some(icon) = self.image_to_icon(&image) else {
+            return false;
+        };
+
+        let mut state = self.state.lock().unwrap();
+        state.set_icon(vec![icon]);
+        true
     }
 
-    /// Programmatically selects a radio option in a radio group.
+    /// Sets the tray icon from several images of different sizes.
+    ///
+    /// All pixmaps are sent to the host, which picks the one that fits its panel. This
+    /// keeps icons crisp on HiDPI panels instead of upscaling a single small pixmap.
+    /// See `set_icon_dpi_scale()` for the size preferred when the host does not choose.
     ///
     /// # Parameters
     ///
-    /// - `group_id` - ID of the radio group
-    /// - `index` - Index of the option to select (0-based)
+    /// - `images` - Images of the same icon at different sizes
     ///
     /// # Returns
     ///
-    /// Returns `true` if the group was found and the selection was updated, `false` otherwise.
+    /// Returns `true` if all images were converted, `false` if the array is empty or
+    /// any image is invalid. The icon is left unchanged on failure.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// var images: Array[Image] = []
+    /// for size in [16, 22, 32, 48, 64]:
+    ///     images.append(load("res://icons/tray_%d.png" % size).get_image())
+    /// tray_icon.set_icon_pixmaps(images)
+    /// ```
     #[func]
-    fn set_radio_selected(&mut self, group_id: GString, index: i64) -> bool {
+    fn set_icon_pixmaps(&mut self, images: Array<Gd<Image>>) -> bool {
+        if images.is_empty() {
+            godot_error!("Cannot set icon pixmaps from an empty array");
+            return false;
+        }
+
+        let mut icons = Vec::with_capacity(images.len());
+        for image in images.iter_shared() {
+            let Some(icon) = self.image_to_icon(&image) else {
+                return false;
+            };
+            icons.push(icon);
+        }
+
         let mut state = self.state.lock().unwrap();
-        let group_id_str = group_id.to_string();
+        state.set_icon(icons);
+        true
+    }
 
-        for item in &mut state.menu {
-            if let MenuItemData::RadioGroup {
-                id,
-                selected,
-                options,
-            } = item
-                && id == &group_id_str
-                && (index as usize) < options.len()
-            {
-                *selected = index as usize;
-                return true;
+    /// Sets the tray icon from raw ARGB32 pixmaps of different sizes.
+    ///
+    /// The pixel data is sent to the host as is, in the byte order of the
+    /// StatusNotifierItem spec: 4 bytes per pixel, A, R, G, B. Use this for pixmaps
+    /// already in that format, e.g. ones read back with `get_icon_pixmaps_raw()`.
+    ///
+    /// # Parameters
+    ///
+    /// - `icons` - Dictionaries with the keys `width`, `height` and `argb` (a
+    ///   `PackedByteArray` of `width * height * 4` bytes)
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the icon was set, `false` if the array is empty or any pixmap
+    /// is malformed. The icon is left unchanged on failure.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// var saved = tray_icon.get_icon_pixmaps_raw()
+    /// tray_icon.set_icon_name("dialog-warning")
+    /// # Later...
+    /// tray_icon.set_icon_pixmaps_raw(saved)
+    /// ```
+    #[func]
+    fn set_icon_pixmaps_raw(&mut self, icons: Array<Dictionary>) -> bool {
+        if icons.is_empty() {
+            godot_error!("Cannot set icon pixmaps from an empty array");
+            return false;
+        }
+
+        let mut pixmaps = Vec::with_capacity(icons.len());
+        for (index, dict) in icons.iter_shared().enumerate() {
+            let parsed = Self::dict_required::<i32>(&dict, "width").and_then(|width| {
+                Ok(ksni::Icon {
+                    width,
+                    height: Self::dict_required(&dict, "height")?,
+                    data: Self::dict_required::<PackedByteArray>(&dict, "argb")?.to_vec(),
+                })
+            });
+            match parsed {
+                Ok(pixmap) => pixmaps.push(pixmap),
+                Err(e) => {
+                    godot_error!("Invalid icon pixmap {}: {}", index, e);
+                    return false;
+                }
             }
         }
-        false
+
+        let result = self.state.lock().unwrap().set_icon_argb(pixmaps);
+        if let Err(ref e) = result {
+            godot_error!("{}", e);
+        }
+        self.record_result(result)
+    }
+
+    /// Returns the pixmaps of the tray icon as sent to the host.
+    ///
+    /// # Returns
+    ///
+    /// Returns an Array of Dictionaries with the keys `width`, `height` and `argb`, as
+    /// accepted by `set_icon_pixmaps_raw()`. Empty if the icon is set by name.
+    #[func]
+    fn get_icon_pixmaps_raw(&self) -> Array<Dictionary> {
+        let state = self.state.lock().unwrap();
+        state
+            .icon_pixmap
+            .iter()
+            .map(|pixmap| {
+                let mut dict = Dictionary::new();
+                dict.set("width", pixmap.width);
+                dict.set("height", pixmap.height);
+                dict.set("argb", PackedByteArray::from(pixmap.data.as_slice()));
+                dict
+            })
+            .collect()
+    }
+
+    /// Asks the tray host for the icon size it recommends.
+    ///
+    /// Makes a synchronous D-Bus call reading the `IconSize` property of the
+    /// StatusNotifierWatcher. The property is not part of the StatusNotifierItem
+    /// specification and most hosts, including Plasma and the GNOME AppIndicator
+    /// extension, do not publish it, so be ready for `-1`. Works whether or not the tray
+    /// is spawned.
+    ///
+    /// The call gives up after the spawn timeout (see `set_spawn_timeout_ms()`), so a
+    /// hung session bus cannot freeze the game; `-1` is returned then and
+    /// `get_last_error()` reports `ERR_TIMEOUT`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the recommended size in pixels, or `-1` if the host does not say.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// var size = tray_icon.query_preferred_icon_size()
+    /// if size > 0:
+    ///     tray_icon.set_icon_from_path("res://icons/tray_%d.png" % size)
+    /// ```
+    #[func]
+    fn query_preferred_icon_size(&mut self) -> i64 {
+        let size = if self.spawn_timeout.is_zero() {
+            Ok(watcher::preferred_icon_size())
+        } else {
+            watcher::preferred_icon_size_within(self.spawn_timeout)
+        };
+        match size {
+            Ok(size) => {
+                self.record_result(Ok(()));
+                size.map_or(-1, i64::from)
+            }
+            Err(e) => {
+                godot_error!("Failed to query the preferred icon size: {}", e);
+                self.record_result(Err(e));
+                -1
+            }
+        }
+    }
+
+    /// Sets the display scale used to pick the preferred icon pixmap.
+    ///
+    /// The SNI host chooses from all pixmaps set with `set_icon_pixmaps()`, but ksni
+    /// does not report which size the host asked for. As a fallback, the pixmap
+    /// nearest to 22 pixels multiplied by this scale is listed first, so hosts that
+    /// simply take the first pixmap still get a crisp icon on HiDPI panels.
+    ///
+    /// # Parameters
+    ///
+    /// - `scale` - Display scale factor (default `1.0`); values below `1.0` are clamped
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_icon_dpi_scale(DisplayServer.screen_get_scale())
+    /// ```
+    #[func]
+    fn set_icon_dpi_scale(&mut self, scale: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.icon_dpi_scale = scale.max(1.0) as f32;
+    }
+
+    /// Sets the tray icon from a Godot Image with transparent padding around it.
+    ///
+    /// Useful for tightly cropped icon designs that look cramped at tray size. The image
+    /// is centered on a transparent canvas of `width + 2 * padding` by
+    /// `height + 2 * padding` pixels.
+    ///
+    /// # Parameters
+    ///
+    /// - `image` - A Godot Image resource
+    /// - `padding` - Padding added on each side, in pixels, at most 1024
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the icon was set successfully, `false` if `padding` is out of
+    /// range, the padded icon exceeds 4096x4096 pixels (`ERR_INVALID_ICON_SIZE`), or the
+    /// image cannot be converted.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// var image = load("res://icon.svg").get_image()
+    /// tray_icon.set_icon_from_image_with_padding(image, 4)
+    /// ```
+    #[func]
+    fn set_icon_from_image_with_padding(&mut self, image: Gd<Image>, padding: i32) -> bool {
+        let mut source = image.duplicate().unwrap().cast::<Image>();
+        if source.is_compressed() {
+            source.decompress();
+        }
+
+        // Use a format with alpha so the padding can be transparent.
+        let format = if Self::is_float_format(source.get_format()) {
+            Format::RGBAF
+        } else {
+            Format::RGBA8
+        };
+        source.convert(format);
+
+        let width = source.get_width();
+        let height = source.get_height();
+        let (padded_width, padded_height) = match icon::padded_size(width, height, padding) {
+            Ok(size) => size,
+            Err(e) => {
+                godot_error!(
+                    "Invalid icon padding {} for a {}x{} image",
+                    padding,
+                    width,
+                    height
+                );
+                return self.record_result(Err(e));
+            }
+        };
+        let Some(mut canvas) = Image::create_empty(padded_width, padded_height, false, format)
+        else {
+            godot_error!("Failed to create padded icon canvas");
+            return false;
+        };
+
+        // A new image is zero-filled, so everything outside the blit is transparent.
+        canvas.blit_rect(
+            &source,
+            Rect2i::new(Vector2i::ZERO, Vector2i::new(width, height)),
+            Vector2i::new(padding, padding),
+        );
+
+        self.set_icon_from_image(canvas)
+    }
+
+    /// Sets whether icons converted from images are made fully opaque.
+    ///
+    /// For icons without transparency the alpha channel is redundant, and some hosts
+    /// draw known-opaque pixmaps faster, e.g. on low-power devices. When enabled, the
+    /// alpha of every pixel is set to 255 while converting images, textures and image
+    /// files, so transparent areas show their color. Raw pixel data is used as given.
+    ///
+    /// # Parameters
+    ///
+    /// - `enabled` - Whether to force icons to be opaque (default `false`)
+    #[func]
+    fn set_icon_opaque(&mut self, enabled: bool) {
+        self.icon_opaque = enabled;
+        self.icon_cache = None;
+    }
+
+    /// Sets whether floating-point images already contain sRGB data.
+    ///
+    /// Images in floating-point formats (`FORMAT_RF`, `FORMAT_RGBAF`, `FORMAT_RGBAH`, ...)
+    /// are assumed to hold linear color, such as images rendered from a 3D viewport, and
+    /// are converted to sRGB when used as icons. Enable this if your float data is
+    /// already in sRGB to skip that conversion.
+    ///
+    /// # Parameters
+    ///
+    /// - `enabled` - Whether float image data is already sRGB (default `false`)
+    #[func]
+    fn set_icon_float_is_srgb(&mut self, enabled: bool) {
+        self.icon_float_is_srgb = enabled;
+        self.icon_cache = None;
+    }
+
+    /// Sets the tray icon from a Godot Texture2D resource.
+    /// This is the recommended method for most use cases.
+    ///
+    /// Works with exported games because it uses Godot's resource system.
+    ///
+    /// # Parameters
+    /// * `texture` - A Godot Texture2D resource (CompressedTexture2D, ImageTexture, etc.)
+    ///
+    /// # Returns
+    /// `true` if the icon was set successfully, `false` otherwise
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// var texture = load("res://icon.svg")
+    /// tray_icon.set_icon_from_texture(texture)
+    /// ```
+    ///
+    /// The converted icon is cached per texture and image content, so calling this
+    /// repeatedly with an unchanged texture skips the conversion. The cache is also
+    /// cleared when the texture emits `changed`.
+    #[func]
+    fn set_icon_from_texture(&mut self, texture: Gd<Texture2D>) -> bool {
+        let Some(image) = texture.get_image() else {
+            godot_error!("Failed to get image from texture");
+            return false;
+        };
+
+        let rid = texture.get_rid();
+        let hash = Self::image_hash(&image);
+        if let Some((cached_rid, cached_hash, ref icon)) = self.icon_cache
+            && cached_rid == rid
+            && cached_hash == hash
+        {
+            let icon = icon.clone();
+            self.state.lock().unwrap().set_icon(vec![icon]);
+            return true;
+        }
+
+        let Some(icon) = self.image_to_icon(&image) else {
+            return false;
+        };
+
+        let clear_cache = Callable::from_object_method(&self.to_gd(), "clear_icon_cache");
+        let mut texture = texture;
+        if !texture.is_connected("changed", &clear_cache) {
+            texture.connect("changed", &clear_cache);
+        }
+
+        self.icon_cache = Some((rid, hash, icon.clone()));
+        self.state.lock().unwrap().set_icon(vec![icon]);
+        true
+    }
+
+    /// Sets the tray icon from a CompressedTexture2D.
+    ///
+    /// `ResourceLoader` returns a CompressedTexture2D for imported images such as
+    /// `res://icon.svg` and `res://icon.png`, including in exported games. Its image is
+    /// read back from the imported `.ctex` data and decompressed if it was imported with
+    /// VRAM compression, which works but loses some quality; prefer the lossless or
+    /// lossy import modes for tray icons. The converted icon is cached as with
+    /// `set_icon_from_texture()`.
+    ///
+    /// # Parameters
+    /// * `texture` - A CompressedTexture2D loaded from an imported image
+    ///
+    /// # Returns
+    /// `true` if the icon was set successfully, `false` if the texture has no data loaded
+    /// or cannot be converted
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// var texture: CompressedTexture2D = load("res://icon.svg")
+    /// tray_icon.set_icon_from_compressed_texture_2d(texture)
+    /// ```
+    #[func]
+    fn set_icon_from_compressed_texture_2d(&mut self, texture: Gd<CompressedTexture2D>) -> bool {
+        if texture.get_load_path().is_empty() {
+            godot_error!("CompressedTexture2D has no data loaded");
+            return false;
+        }
+        self.set_icon_from_texture(texture.upcast())
+    }
+
+    /// Clears the cached icon conversion used by `set_icon_from_texture()`.
+    ///
+    /// Changed image content is detected on its own, so this is only needed to free the
+    /// cached icon, or to force the next `set_icon_from_texture()` call to convert the
+    /// texture again.
+    #[func]
+    fn clear_icon_cache(&mut self) {
+        self.icon_cache = None;
+    }
+
+    /// Sets the tray icon by loading a texture or image from a Godot resource path.
+    /// This is a convenience wrapper around set_icon_from_texture() and set_icon_from_image().
+    ///
+    /// Any Texture2D resource is accepted (CompressedTexture2D, AtlasTexture,
+    /// PortableCompressedTexture2D, ImageTexture, ...), as well as saved Image resources.
+    ///
+    /// Works with exported games because it uses ResourceLoader, which follows the
+    /// import remaps to the imported texture (e.g. `.ctex`) when the original image file
+    /// is not exported. Paths to `.import` or `.remap` files are resolved to their source
+    /// path. Image files that were never imported (e.g. in `user://`) are loaded directly.
+    ///
+    /// # Parameters
+    /// * `path` - A Godot resource path (e.g., "res://icon.svg")
+    ///
+    /// # Returns
+    /// `true` if the icon was loaded and set successfully, `false` otherwise
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_icon_from_path("res://icon.svg")
+    /// ```
+    #[func]
+    fn set_icon_from_path(&mut self, path: GString) -> bool {
+        match Self::load_icon_resource(&path) {
+            Ok(IconResource::Texture(texture)) => self.set_icon_from_texture(texture),
+            Ok(IconResource::Image(image)) => self.set_icon_from_image(image),
+            Err(e) => {
+                godot_error!("{}", e);
+                false
+            }
+        }
+    }
+
+    /// Sets the tray icon from a resource of any supported type.
+    ///
+    /// Dispatches on the runtime type of the resource: a CompressedTexture2D is set as
+    /// with `set_icon_from_compressed_texture_2d()`, any other Texture2D (AtlasTexture,
+    /// ImageTexture, ...) as with `set_icon_from_texture()`, and an Image as with
+    /// `set_icon_from_image()`. Useful when holding a loaded resource of unknown type.
+    ///
+    /// # Parameters
+    /// * `resource` - A Texture2D or Image resource
+    ///
+    /// # Returns
+    /// `true` if the icon was set successfully, `false` if the resource has an unsupported
+    /// type or cannot be converted
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_icon_from_resource(theme.get_icon("tray", "App"))
+    /// ```
+    #[func]
+    fn set_icon_from_resource(&mut self, resource: Gd<Resource>) -> bool {
+        let resource = match resource.try_cast::<CompressedTexture2D>() {
+            Ok(texture) => return self.set_icon_from_compressed_texture_2d(texture),
+            Err(resource) => resource,
+        };
+        let resource = match resource.try_cast::<Texture2D>() {
+            Ok(texture) => return self.set_icon_from_texture(texture),
+            Err(resource) => resource,
+        };
+        match resource.try_cast::<Image>() {
+            Ok(image) => self.set_icon_from_image(image),
+            Err(resource) => {
+                godot_error!(
+                    "Resource is neither a Texture2D nor an Image: {}",
+                    resource.get_class()
+                );
+                false
+            }
+        }
+    }
+
+    /// Checks whether `set_icon_from_path()` would succeed for a path, without changing the icon.
+    ///
+    /// The resource is loaded and converted the same way as by `set_icon_from_path()`,
+    /// so a settings screen can disable icon choices that cannot be used.
+    ///
+    /// # Parameters
+    /// * `path` - A Godot resource path (e.g., "res://icon.svg")
+    ///
+    /// # Returns
+    /// `true` if the resource can be used as the tray icon, `false` otherwise
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// button.disabled = not tray_icon.can_use_icon("user://custom_icon.png")
+    /// ```
+    #[func]
+    fn can_use_icon(&self, path: GString) -> bool {
+        let image = match Self::load_icon_resource(&path) {
+            Ok(IconResource::Texture(texture)) => texture.get_image(),
+            Ok(IconResource::Image(image)) => Some(image),
+            Err(_) => None,
+        };
+
+        image.is_some_and(|image| self.image_to_icon(&image).is_some())
+    }
+
+    /// Sets the tray icon from raw RGBA pixel data.
+    ///
+    /// The data should be in RGBA format with 4 bytes per pixel.
+    ///
+    /// # Parameters
+    ///
+    /// - `width` - Width of the icon in pixels
+    /// - `height` - Height of the icon in pixels
+    /// - `data` - Raw pixel data as RGBA bytes (length must be width * height * 4)
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the icon was set successfully, `false` if the dimensions are not
+    /// positive or exceed 4096x4096 pixels (`ERR_INVALID_ICON_SIZE`), or the data size is
+    /// invalid.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// var data = PackedByteArray()
+    /// # Fill data with RGBA values...
+    /// tray_icon.set_icon_from_data(32, 32, data)
+    /// ```
+    #[func]
+    fn set_icon_from_data(&mut self, width: i32, height: i32, data: PackedByteArray) -> bool {
+        self.set_icon_from_data_with_format(width, height, data, Self::PIXEL_FORMAT_RGBA8, 0)
+    }
+
+    /// Sets the tray icon from raw pixel data in another format or with padded rows.
+    ///
+    /// The pixels are converted to the ARGB format of the tray in one pass. Pixels of
+    /// formats without alpha are opaque.
+    ///
+    /// # Parameters
+    ///
+    /// - `width` - Width of the icon in pixels
+    /// - `height` - Height of the icon in pixels
+    /// - `data` - Raw pixel data
+    /// - `format` - One of the `PIXEL_FORMAT_*` constants
+    /// - `stride_bytes` - Bytes from the start of one row to the next, or 0 if the rows
+    ///   are not padded
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the icon was set successfully, `false` if the format is unknown
+    /// or the stride or data size is inconsistent with the dimensions.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// var frame = capture.get_frame()
+    /// tray_icon.set_icon_from_data_with_format(frame.width, frame.height, frame.pixels,
+    ///         TrayIcon.PIXEL_FORMAT_RGB8, frame.stride)
+    /// ```
+    #[func]
+    fn set_icon_from_data_with_format(
+        &mut self,
+        width: i32,
+        height: i32,
+        data: PackedByteArray,
+        format: i64,
+        stride_bytes: i64,
+    ) -> bool {
+        let pixel_format = match format {
+            Self::PIXEL_FORMAT_RGBA8 => icon::PixelFormat::Rgba8,
+            Self::PIXEL_FORMAT_RGB8 => icon::PixelFormat::Rgb8,
+            Self::PIXEL_FORMAT_BGRA8 => icon::PixelFormat::Bgra8,
+            _ => {
+                godot_error!("Unknown pixel format: {}", format);
+                return false;
+            }
+        };
+
+        let stride = stride_bytes.max(0) as usize;
+        let result = self.state.lock().unwrap().set_icon_pixels(
+            width,
+            height,
+            data.as_slice(),
+            pixel_format,
+            stride,
+        );
+        match result {
+            Err(Error::InvalidIconSize { .. }) if width <= 0 || height <= 0 => {
+                godot_error!("width ({}) and height ({}) must be positive", width, height)
+            }
+            Err(Error::InvalidIconSize { .. }) => godot_error!(
+                "{}x{} exceeds the maximum of {} pixels for an icon",
+                width,
+                height,
+                icon::MAX_ICON_PIXELS
+            ),
+            Err(Error::InvalidIconStride { stride, row_bytes }) => godot_error!(
+                "stride_bytes ({}) is less than width ({}) times {} bytes per pixel ({})",
+                stride,
+                width,
+                pixel_format.bytes_per_pixel(),
+                row_bytes
+            ),
+            Err(Error::InvalidIconData { expected, got }) if stride_bytes > 0 => godot_error!(
+                "data has {} bytes, but height ({}) rows of stride_bytes ({}) need {}",
+                got,
+                height,
+                stride,
+                expected
+            ),
+            Err(Error::InvalidIconData { expected, got }) => godot_error!(
+                "data has {} bytes, but width ({}) and height ({}) need {}",
+                got,
+                width,
+                height,
+                expected
+            ),
+            Err(ref e) => godot_error!("{}", e),
+            Ok(()) => {}
+        }
+        self.record_result(result)
+    }
+
+    /// Shows a count in a red badge over the tray icon, e.g. for unread messages, and
+    /// refreshes the tray if it is spawned.
+    ///
+    /// The badge is sent as the StatusNotifierItem overlay icon, so it is combined with
+    /// any tray icon. Counts above 99 are shown as `99+`. Not all hosts show overlay
+    /// icons.
+    ///
+    /// # Parameters
+    ///
+    /// - `count` - The number to show, or 0 or less to remove the badge
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_icon_badge_count(unread_messages.size())
+    /// ```
+    #[func]
+    fn set_icon_badge_count(&mut self, count: i64) {
+        let overlay = if count > 0 {
+            let count = count.min(icon::MAX_BADGE_COUNT as i64 + 1) as u32;
+            vec![icon::render_badge(count, icon::DEFAULT_ICON_SIZE)]
+        } else {
+            Vec::new()
+        };
+
+        self.state.lock().unwrap().overlay_icon_pixmap = overlay;
+        self.refresh();
+    }
+
+    /// Sets the tray icon to a gauge filled to the given fraction.
+    ///
+    /// Renders a square icon where the filled part uses `full` and the rest `empty`, e.g.
+    /// for battery or disk usage indicators. Call it again with a new fraction and then
+    /// `refresh()` to update the icon live.
+    ///
+    /// # Parameters
+    ///
+    /// - `fraction` - Fill level from `0.0` to `1.0` (clamped)
+    /// - `empty` - Color of the unfilled part
+    /// - `full` - Color of the filled part
+    /// - `size` - Width and height of the icon in pixels
+    /// - `orientation` - `VERTICAL` to fill from the bottom up, `HORIZONTAL` to fill from left to right
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the icon was set, `false` if `orientation` is invalid or `size`
+    /// is not positive or exceeds 4096x4096 pixels (`ERR_INVALID_ICON_SIZE`).
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_icon_gauge(0.75, Color.DIM_GRAY, Color.LIME_GREEN, 32, VERTICAL)
+    /// tray_icon.refresh()
+    /// ```
+    #[func]
+    fn set_icon_gauge(
+        &mut self,
+        fraction: f64,
+        empty: Color,
+        full: Color,
+        size: i32,
+        orientation: i64,
+    ) -> bool {
+        let vertical = match orientation {
+            GAUGE_HORIZONTAL => false,
+            GAUGE_VERTICAL => true,
+            _ => {
+                godot_error!("Invalid gauge orientation: {}", orientation);
+                return false;
+            }
+        };
+
+        let to_rgba8 = |color: Color| {
+            [
+                icon::quantize(color.r),
+                icon::quantize(color.g),
+                icon::quantize(color.b),
+                icon::quantize(color.a),
+            ]
+        };
+        let result = icon::render_gauge(
+            fraction as f32,
+            to_rgba8(empty),
+            to_rgba8(full),
+            size,
+            vertical,
+        )
+        .map(|gauge| self.state.lock().unwrap().set_icon(vec![gauge]));
+        if result.is_err() {
+            godot_error!("Invalid gauge icon size: {}", size);
+        }
+        self.record_result(result)
+    }
+
+    /// Converts the tray icon pixmaps to grayscale, e.g. to show a disabled state.
+    ///
+    /// All pixmap sizes are converted, keeping their transparency. Icons set by name
+    /// cannot be converted; set the icon from an image first.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the icon was converted, `false` if no pixmap is set.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// func _on_connection_lost():
+    ///     tray_icon.apply_icon_grayscale()
+    /// ```
+    #[func]
+    fn apply_icon_grayscale(&mut self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.icon_pixmap.is_empty() {
+            return false;
+        }
+
+        for pixmap in &mut state.icon_pixmap {
+            icon::grayscale_argb(&mut pixmap.data);
+        }
+        true
+    }
+
+    /// Clears the custom icon pixmap data.
+    ///
+    /// After calling this, the tray will fall back to using the icon name set by
+    /// `set_icon_name()` if one was specified.
+    #[func]
+    fn clear_icon_pixmap(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.icon_pixmap.clear();
+    }
+
+    /// Resets the tray icon to the default icon.
+    ///
+    /// Unlike `clear_icon_pixmap()`, this also replaces the icon name with the default
+    /// `"application-x-executable"`, so no custom icon remains.
+    #[func]
+    fn clear_all_icon_data(&mut self) {
+        self.state.lock().unwrap().reset_icon();
+    }
+
+    /// Regenerates the tray icon periodically from a Callable.
+    ///
+    /// The Callable takes no arguments and must return an Image, which is converted and
+    /// sent as the live tray icon, e.g. for clocks or level meters drawn at runtime. It is
+    /// first called on the next frame. When it returns an image identical to the previous
+    /// one, the conversion and the update are skipped. The generator overrides icons set
+    /// by other methods until `stop_icon_generator()` is called.
+    ///
+    /// # Parameters
+    ///
+    /// - `callback` - A Callable returning the icon as an Image
+    /// - `interval` - Seconds between calls (0 to call it every frame)
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_icon_generator(func():
+    ///     var image = Image.create(22, 22, false, Image.FORMAT_RGBA8)
+    ///     image.fill_rect(Rect2i(0, 22 - level, 22, level), Color.GREEN)
+    ///     return image
+    /// , 0.25)
+    /// ```
+    #[func]
+    fn set_icon_generator(&mut self, callback: Callable, interval: f64) {
+        if interval < 0.0 {
+            godot_error!("Invalid icon generator interval: {}", interval);
+            return;
+        }
+
+        self.icon_generator = Some(callback);
+        self.icon_generator_interval = interval;
+        self.icon_generator_elapsed = interval;
+        self.last_generated_image_hash = None;
+    }
+
+    /// Stops calling the Callable set with `set_icon_generator()`.
+    ///
+    /// The last generated icon is kept.
+    #[func]
+    fn stop_icon_generator(&mut self) {
+        self.icon_generator = None;
+        self.last_generated_image_hash = None;
+    }
+
+    /// Enables or disables mirroring the window icon to the tray icon.
+    ///
+    /// Godot has no API to read back the current window icon, so the mirrored icon is
+    /// the last image passed to `set_window_icon()`, or the project icon
+    /// (`application/config/icon`) if none was set. While enabled, the project icon
+    /// setting is polled every few seconds and re-applied when it changes.
+    ///
+    /// Disabling stops the mirroring but keeps the last applied icon.
+    ///
+    /// # Parameters
+    ///
+    /// - `enabled` - Whether to mirror the window icon
+    #[func]
+    fn sync_with_window_icon(&mut self, enabled: bool) {
+        self.sync_window_icon = enabled;
+        self.window_icon_poll_elapsed = 0.0;
+        if enabled {
+            self.apply_window_icon();
+        }
+    }
+
+    /// Sets the main window icon and, if syncing is enabled, the tray icon as well.
+    ///
+    /// This is a convenience wrapper around `DisplayServer.set_icon()` that keeps the tray
+    /// icon in sync without duplicating code.
+    ///
+    /// # Parameters
+    ///
+    /// - `image` - The image to use as the window icon
+    #[func]
+    fn set_window_icon(&mut self, image: Gd<Image>) {
+        DisplayServer::singleton().set_icon(&image);
+        self.window_icon = Some(image);
+        if self.sync_window_icon {
+            self.apply_window_icon();
+        }
+    }
+
+    /// Re-applies the window icon to the tray icon if syncing is enabled.
+    ///
+    /// Call this after changing the project icon setting at runtime to update the tray
+    /// immediately instead of waiting for the next poll.
+    #[func]
+    fn notify_window_icon_changed(&mut self) {
+        if self.sync_window_icon {
+            self.apply_window_icon();
+        }
+    }
+
+    /// Sets the title text displayed next to the tray icon, and refreshes the tray if it
+    /// is spawned.
+    ///
+    /// This sets the static base of the title. Any suffix set with `set_title_suffix()`
+    /// is still appended to it.
+    ///
+    /// Defaults to the project name (`application/config/name`), or `"Tray Icon"` if unset.
+    ///
+    /// # Parameters
+    ///
+    /// - `title` - The title text to display
+    #[func]
+    fn set_title(&mut self, title: GString) {
+        self.state.lock().unwrap().set_title_base(title.to_string());
+        self.refresh();
+    }
+
+    /// Sets dynamic text appended to the title.
+    ///
+    /// Useful for showing changing data after a static title without rebuilding the
+    /// full string each time.
+    ///
+    /// # Parameters
+    ///
+    /// - `suffix` - The text appended to the title (empty string to remove it)
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_title("My App")
+    /// tray_icon.set_title_suffix(" [5 notifications]")
+    /// ```
+    #[func]
+    fn set_title_suffix(&mut self, suffix: GString) {
+        let mut state = self.state.lock().unwrap();
+        state.set_title_suffix(suffix.to_string());
+    }
+
+    /// Returns the full title, including any suffix.
+    #[func]
+    fn get_title(&self) -> GString {
+        let state = self.state.lock().unwrap();
+        GString::from(&state.title())
+    }
+
+    /// Returns the title set with `set_title()`, without the suffix.
+    #[func]
+    fn get_title_base(&self) -> GString {
+        let state = self.state.lock().unwrap();
+        GString::from(&state.title_base)
+    }
+
+    /// Sets the tooltip displayed when hovering over the tray icon.
+    ///
+    /// The text is shown as-is: characters such as `<` are escaped so hosts that
+    /// render markup never interpret them. Use `set_tooltip_rich()` for formatting.
+    ///
+    /// # Parameters
+    ///
+    /// - `title` - The main tooltip text
+    /// - `subtitle` - Additional tooltip text displayed below the title
+    /// - `icon_name` - System icon name to display in the tooltip
+    #[func]
+    fn set_tooltip(&mut self, title: GString, subtitle: GString, icon_name: GString) {
+        let mut state = self.state.lock().unwrap();
+        state.set_tooltip(
+            title.to_string(),
+            subtitle.to_string(),
+            icon_name.to_string(),
+        );
+    }
+
+    /// Sets the tooltip title, keeping the subtitle and icon, and refreshes the tray if it
+    /// is spawned.
+    ///
+    /// The text is plain, as with `set_tooltip()`, unless the tooltip was set with
+    /// `set_tooltip_rich()`, in which case it is sanitized markup.
+    ///
+    /// # Parameters
+    ///
+    /// - `title` - The main tooltip text
+    #[func]
+    fn set_tooltip_title(&mut self, title: GString) {
+        self.state
+            .lock()
+            .unwrap()
+            .set_tooltip_title(&title.to_string());
+        self.refresh();
+    }
+
+    /// Returns the tooltip title, as in `get_tooltip()`.
+    #[func]
+    fn get_tooltip_title(&self) -> GString {
+        let state = self.state.lock().unwrap();
+        GString::from(&state.tooltip_title)
+    }
+
+    /// Sets the tooltip subtitle, keeping the title and icon, and refreshes the tray if it
+    /// is spawned.
+    ///
+    /// The text is plain, as with `set_tooltip()`, unless the tooltip was set with
+    /// `set_tooltip_rich()`, in which case it is sanitized markup.
+    ///
+    /// # Parameters
+    ///
+    /// - `subtitle` - Text displayed below the tooltip title
+    #[func]
+    fn set_tooltip_subtitle(&mut self, subtitle: GString) {
+        self.state
+            .lock()
+            .unwrap()
+            .set_tooltip_subtitle(&subtitle.to_string());
+        self.refresh();
+    }
+
+    /// Returns the tooltip subtitle, as in `get_tooltip()`.
+    #[func]
+    fn get_tooltip_subtitle(&self) -> GString {
+        let state = self.state.lock().unwrap();
+        GString::from(&state.tooltip_subtitle)
+    }
+
+    /// Sets the tooltip title and body from markup.
+    ///
+    /// Only `<b>`, `<i>`, `<br>` and `<img src="icon-name">` (an icon theme name, not a
+    /// path) are kept; any other tag is escaped and shown as text, and unclosed tags are
+    /// closed. Hosts such as Plasma render the body as rich text. The title is always
+    /// sent as plain text derived from the markup, since the StatusNotifierItem
+    /// specification does not allow markup there. The tooltip icon name is kept.
+    ///
+    /// # Parameters
+    ///
+    /// - `title_markup` - The main tooltip text
+    /// - `body_markup` - Text displayed below the title
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_tooltip_rich("Downloads", "<b>3</b> active<br><i>12 MB/s</i>")
+    /// ```
+    #[func]
+    fn set_tooltip_rich(&mut self, title_markup: GString, body_markup: GString) {
+        let mut state = self.state.lock().unwrap();
+        state.set_tooltip_rich(&title_markup.to_string(), &body_markup.to_string());
+    }
+
+    /// Returns the current tooltip.
+    ///
+    /// # Returns
+    ///
+    /// Returns a Dictionary with the `title`, `subtitle`, and `icon_name` Strings, and
+    /// `rich`, which is `true` if the title and subtitle hold sanitized markup set with
+    /// `set_tooltip_rich()`. Plain text is returned unescaped. The tooltip icon texture
+    /// is not included.
+    #[func]
+    fn get_tooltip(&self) -> Dictionary {
+        let state = self.state.lock().unwrap();
+        vdict! {
+            "title": state.tooltip_title.as_str(),
+            "subtitle": state.tooltip_subtitle.as_str(),
+            "icon_name": state.tooltip_icon_name.as_str(),
+            "rich": state.tooltip_markup,
+        }
+    }
+
+    /// Sets the whole tooltip from a Dictionary, and refreshes the tray if it is spawned.
+    ///
+    /// Supported keys are `title`, `subtitle`, `icon_name` (Strings), and `icon_texture`
+    /// (a Texture2D shown as the tooltip icon). Missing keys are cleared, and unknown
+    /// keys are ignored. The text is plain, as with `set_tooltip()`.
+    ///
+    /// # Parameters
+    ///
+    /// - `dict` - Description of the tooltip
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the tooltip was set, `false` if a value has the wrong type or
+    /// the texture cannot be converted.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_tooltip_from_dict({
+    ///     "title": "My Game",
+    ///     "subtitle": "Connected",
+    ///     "icon_texture": preload("res://status_online.png"),
+    /// })
+    /// ```
+    #[func]
+    fn set_tooltip_from_dict(&mut self, dict: Dictionary) -> bool {
+        let (title, subtitle, icon_name, icon_texture) = match Self::tooltip_from_dict(&dict) {
+            Ok(values) => values,
+            Err(e) => {
+                godot_error!("Invalid tooltip Dictionary: {}", e);
+                return false;
+            }
+        };
+
+        let icon_pixmap = match icon_texture {
+            Some(texture) => {
+                let Some(image) = texture.get_image() else {
+                    godot_error!("Failed to get image from tooltip texture");
+                    return false;
+                };
+                let Some(icon) = self.image_to_icon(&image) else {
+                    return false;
+                };
+                vec![icon]
+            }
+            None => Vec::new(),
+        };
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.set_tooltip(
+                title.to_string(),
+                subtitle.to_string(),
+                icon_name.to_string(),
+            );
+            state.tooltip_icon_pixmap = icon_pixmap;
+        }
+        self.refresh();
+        true
+    }
+
+    /// Sets the direction in which hosts lay out the title, tooltip, and menu texts.
+    ///
+    /// The direction applies to the whole menu; dbusmenu has no per-item direction.
+    /// With `TEXT_DIRECTION_AUTO` (the default), the direction follows whether the
+    /// current `TranslationServer` locale is written right to left, and is updated
+    /// when the locale changes.
+    ///
+    /// # Parameters
+    ///
+    /// - `direction` - One of `TEXT_DIRECTION_AUTO`, `TEXT_DIRECTION_LTR`, or
+    ///   `TEXT_DIRECTION_RTL`
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_text_direction(TrayIcon.TEXT_DIRECTION_RTL)
+    /// ```
+    #[func]
+    fn set_text_direction(&mut self, direction: i64) {
+        if !(Self::TEXT_DIRECTION_AUTO..=Self::TEXT_DIRECTION_RTL).contains(&direction) {
+            godot_error!("Invalid text direction: {}", direction);
+            return;
+        }
+
+        self.text_direction = direction;
+        self.apply_text_direction();
+    }
+
+    /// Sets the category of the tray icon, which hosts may use to group or sort icons.
+    ///
+    /// Hosts read the category when the tray registers, so a change to a spawned tray
+    /// only takes effect after `respawn_tray()`.
+    ///
+    /// # Parameters
+    ///
+    /// - `category` - One of `"ApplicationStatus"` (the default), `"Communications"`,
+    ///   `"SystemServices"`, or `"Hardware"`, as named by the StatusNotifierItem
+    ///   specification
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the category was set, `false` if the name is unknown.
+    #[func]
+    fn set_tray_category(&mut self, category: GString) -> bool {
+        let Some(category) = Self::category_from_name(&category.to_string()) else {
+            godot_error!("Unknown tray category: {}", category);
+            return false;
+        };
+
+        self.state.lock().unwrap().category = category;
+        true
+    }
+
+    /// Returns the category of the tray icon, as accepted by `set_tray_category()`.
+    #[func]
+    fn get_tray_category(&self) -> GString {
+        let state = self.state.lock().unwrap();
+        GString::from(match state.category {
+            ksni::Category::ApplicationStatus => "ApplicationStatus",
+            ksni::Category::Communications => "Communications",
+            ksni::Category::SystemServices => "SystemServices",
+            ksni::Category::Hardware => "Hardware",
+        })
+    }
+
+    /// Replaces the whole menu with items described by Dictionaries, and refreshes the
+    /// tray if it is spawned.
+    ///
+    /// The items are described as on `replace_menu_item()`. The menu is swapped in one
+    /// step, so the host never sees a partly built menu. Like `clear_menu()`, this drops
+    /// dynamic enabled providers, submenu providers and item callbacks of the old menu.
+    ///
+    /// # Parameters
+    ///
+    /// - `items` - Descriptions of the new top-level menu items
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the menu was replaced, `false` if any item is invalid, in which
+    /// case the menu is left unchanged.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.rebuild_menu_from_array([
+    ///     {"type": "standard", "id": "open", "label": "Open"},
+    ///     {"type": "separator"},
+    ///     {"type": "standard", "id": "quit", "label": "Quit"},
+    /// ])
+    /// ```
+    #[func]
+    fn rebuild_menu_from_array(&mut self, items: Array<Dictionary>) -> bool {
+        let menu: Result<Vec<_>, String> = items
+            .iter_shared()
+            .map(|dict| Self::menu_item_from_dict(&dict))
+            .collect();
+        let menu = match menu {
+            Ok(menu) => menu,
+            Err(e) => {
+                godot_error!("Invalid menu item Dictionary: {}", e);
+                self.report_failure(
+                    "rebuild_menu_from_array",
+                    format!("invalid Dictionary: {}", e),
+                );
+                return false;
+            }
+        };
+
+        self.state.lock().unwrap().rebuild_from_items(menu);
+        self.forget_menu_bindings();
+        self.refresh();
+        true
+    }
+
+    /// Clears all menu items from the tray menu.
+    ///
+    /// This is useful when rebuilding the menu from scratch. Entries of the previous menu
+    /// that a host still shows stop emitting events, even if the rebuilt menu reuses
+    /// their IDs; call `refresh()` after rebuilding so the host shows the new menu.
+    #[func]
+    fn clear_menu(&mut self) {
+        self.state.lock().unwrap().clear_menu();
+        self.forget_menu_bindings();
+    }
+
+    /// Resets the tray to its defaults, optionally keeping the menu and the icon, and
+    /// refreshes the tray if it is spawned.
+    ///
+    /// More surgical than building a new node: the tray ID stays, so a spawned tray keeps
+    /// its identity, and a kept menu keeps the current checkmark and radio states without
+    /// reading and re-applying each of them. Node settings such as the debounce window or
+    /// batching are not affected.
+    ///
+    /// # Parameters
+    ///
+    /// - `preserve_menu` - Keep the menu items, their states, pins, groups, item callbacks
+    ///   and providers. Otherwise the menu is cleared like with `clear_menu()`
+    /// - `preserve_icon` - Keep the icon name, theme path, pixmaps and overlay
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// # Start over with a new title and tooltip, keeping the menu toggles
+    /// tray_icon.migrate_state(true, true)
+    /// tray_icon.set_title("My Game")
+    /// ```
+    #[func]
+    fn migrate_state(&mut self, preserve_menu: bool, preserve_icon: bool) {
+        self.state
+            .lock()
+            .unwrap()
+            .migrate_state(preserve_menu, preserve_icon);
+        if !preserve_menu {
+            self.forget_menu_bindings();
+        }
+        self.refresh();
+    }
+
+    /// Replaces the tray menu with the items of a Godot `PopupMenu`.
+    ///
+    /// Lets an app reuse the PopupMenu that defines its actions in-window. Items,
+    /// checkable items, radio-checkable items, separators (with text, as headers), and
+    /// submenus are converted; icons and tooltips are not. Consecutive radio-checkable
+    /// items become one radio group with the ID of its first option plus `"_group"`.
+    ///
+    /// Item IDs are taken from String metadata set with `set_item_metadata()`, or else
+    /// from the PopupMenu item ID, prefixed by the labels of enclosing submenus
+    /// (e.g. `"File/3"`).
+    ///
+    /// Clicking a converted item in the tray emits `id_pressed` and `index_pressed` on
+    /// the PopupMenu holding it, as a click in the PopupMenu would, in addition to this
+    /// node's own signals. The PopupMenu is not watched for changes; call
+    /// `resync_from_popup()` after modifying it.
+    ///
+    /// # Parameters
+    ///
+    /// - `popup` - The PopupMenu to mirror
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_menu_from_popup($MenuBar/File)
+    /// ```
+    #[func]
+    fn set_menu_from_popup(&mut self, popup: Gd<PopupMenu>) {
+        self.source_popup = Some(popup);
+        self.resync_from_popup();
+    }
+
+    /// Rebuilds the tray menu from the PopupMenu set with `set_menu_from_popup()`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the menu was rebuilt, `false` if no PopupMenu is set or it was freed.
+    #[func]
+    fn resync_from_popup(&mut self) -> bool {
+        let Some(popup) = self.source_popup.clone() else {
+            godot_error!("No PopupMenu set, call set_menu_from_popup() first");
+            return false;
+        };
+        if !popup.is_instance_valid() {
+            godot_error!("The PopupMenu set with set_menu_from_popup() was freed");
+            self.source_popup = None;
+            self.popup_items.clear();
+            return false;
+        }
+
+        let mut popup_items = HashMap::new();
+        let menu = popup_source::convert(&popup, &mut popup_items, "");
+        self.popup_items = popup_items;
+        self.state.lock().unwrap().rebuild_from_items(menu);
+        self.refresh();
+        true
+    }
+
+    /// Pins a top-level menu item so it always appears first in the menu.
+    ///
+    /// Pinned items are shown in the order they were pinned, before all other items,
+    /// regardless of when they were added.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - ID of the top-level menu item to pin
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the item was found and pinned, `false` otherwise.
+    #[func]
+    fn pin_menu_item_to_top(&mut self, id: GString) -> bool {
+        let result = self.state.lock().unwrap().pin_item(&id.to_string());
+        self.record_result(result)
+    }
+
+    /// Unpins a menu item, returning it to its insertion position.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - ID of the menu item to unpin
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the item was pinned, `false` otherwise.
+    #[func]
+    fn unpin_menu_item(&mut self, id: GString) -> bool {
+        let result = self.state.lock().unwrap().unpin_item(&id.to_string());
+        self.record_result(result)
+    }
+
+    /// Sets the display priority of a menu item.
+    ///
+    /// Priorities only take effect when `sort_menu_by_priority()` is called. All items
+    /// start with priority 0. Values outside the 32-bit range are clamped.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - ID of the menu item (can be inside a submenu)
+    /// - `priority` - New priority; higher values are shown first
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the item was found, `false` otherwise.
+    #[func]
+    fn set_menu_item_priority(&mut self, id: GString, priority: i64) -> bool {
+        let priority = priority.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        let result = self
+            .state
+            .lock()
+            .unwrap()
+            .set_item_priority(&id.to_string(), priority);
+        self.record_result(result)
+    }
+
+    /// Sorts the menu by descending priority.
+    ///
+    /// Submenu contents are sorted too. Items with equal priority keep their current
+    /// order, and pinned items are still shown first.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_menu_item_priority("active_session", 10)
+    /// tray_icon.sort_menu_by_priority()
+    /// tray_icon.refresh()
+    /// ```
+    #[func]
+    fn sort_menu_by_priority(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.sort_menu_by_priority();
+    }
+
+    /// Replaces a menu item in place with a new item described by a Dictionary.
+    ///
+    /// The new item keeps the position of the replaced one, including inside submenus.
+    ///
+    /// The Dictionary must contain a `type` key with one of `"standard"`, `"checkmark"`,
+    /// `"radio_group"`, `"submenu"`, `"header"`, or `"separator"`. Other keys match the
+    /// parameters of the corresponding `add_*` methods: `id`, `label`, `icon_name`,
+    /// `enabled`, `visible`, `checked`, `selected`, `emit_on_reselect`, `priority`, and
+    /// `suppress_global`. Radio groups take an `options` Array of Dictionaries with `id`,
+    /// `label`, `icon_name`, `enabled`, and `visible`, and submenus take a `submenu`
+    /// Array of item Dictionaries. Missing optional keys use the same defaults as the
+    /// `add_*` methods.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - ID of the menu item to replace
+    /// - `new_item_dict` - Description of the new menu item
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the item was replaced, `false` if it was not found or the
+    /// Dictionary is invalid.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.replace_menu_item("connect", {
+    ///     "type": "standard",
+    ///     "id": "disconnect",
+    ///     "label": "Disconnect",
+    ///     "icon_name": "network-offline",
+    /// })
+    /// ```
+    #[func]
+    fn replace_menu_item(&mut self, id: GString, new_item_dict: Dictionary) -> bool {
+        let new_item = match Self::menu_item_from_dict(&new_item_dict) {
+            Ok(item) => item,
+            Err(e) => {
+                godot_error!("Invalid menu item Dictionary: {}", e);
+                self.report_failure("replace_menu_item", format!("invalid Dictionary: {}", e));
+                return false;
+            }
+        };
+
+        let id = id.to_string();
+        if let Some(item) = self.state.lock().unwrap().find_item_mut(&id) {
+            *item = new_item;
+            return true;
+        }
+
+        self.report_failure("replace_menu_item", format!("no item with ID '{}'", id));
+        false
+    }
+
+    /// Replaces a menu item like `replace_menu_item()`, keeping its toggle state.
+    ///
+    /// When a checkmark is replaced by a checkmark, the existing `checked` value is kept
+    /// unless the Dictionary sets `checked`. Likewise, when a radio group is replaced by
+    /// a radio group, the existing `selected` index is kept unless the Dictionary sets
+    /// `selected` or the new group has too few options. This keeps user-toggled state
+    /// when rebuilding the menu.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - ID of the menu item to replace
+    /// - `dict` - Description of the new menu item
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the item was replaced, `false` if it was not found or the
+    /// Dictionary is invalid.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.find_and_rebuild_menu_item("autostart", {
+    ///     "type": "checkmark",
+    ///     "id": "autostart",
+    ///     "label": tr("Start on Boot"),
+    /// })
+    /// ```
+    #[func]
+    fn find_and_rebuild_menu_item(&mut self, id: GString, dict: Dictionary) -> bool {
+        let mut new_item = match Self::menu_item_from_dict(&dict) {
+            Ok(item) => item,
+            Err(e) => {
+                godot_error!("Invalid menu item Dictionary: {}", e);
+                return false;
+            }
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let Some(item) = state.find_item_mut(&id.to_string()) else {
+            return false;
+        };
+
+        match (&*item, &mut new_item) {
+            (
+                MenuItemData::Checkmark { checked, .. },
+                MenuItemData::Checkmark {
+                    checked: new_checked,
+                    ..
+                },
+            ) if !dict.contains_key("checked") => *new_checked = *checked,
+            (
+                MenuItemData::RadioGroup { selected, .. },
+                MenuItemData::RadioGroup {
+                    selected: new_selected,
+                    options,
+                    ..
+                },
+            ) if !dict.contains_key("selected") && *selected < options.len() => {
+                *new_selected = *selected
+            }
+            _ => {}
+        }
+
+        *item = new_item;
+        true
+    }
+
+    /// Adds a standard clickable menu item.
+    ///
+    /// When clicked, emits the `menu_activated` signal with the item's ID.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - Unique identifier for this menu item
+    /// - `label` - Text displayed in the menu
+    /// - `icon_name` - System icon name (empty string for no icon)
+    /// - `enabled` - Whether the item can be clicked
+    /// - `visible` - Whether the item is visible
+    #[func]
+    fn add_menu_item(
+        &mut self,
+        id: GString,
+        label: GString,
+        icon_name: GString,
+        enabled: bool,
+        visible: bool,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        state.menu.push(MenuItemData::Standard {
+            id: id.to_string(),
+            label: label.to_string(),
+            icon_name: icon_name.to_string(),
+            enabled,
+            visible,
+            priority: 0,
+            suppress_global: false,
+        });
+    }
+
+    /// Adds a standard menu item whose enabled state is decided by a Callable.
+    ///
+    /// The provider is called with no arguments and must return a `bool`. It is evaluated
+    /// when the item is added and again every time the tray menu is about to be shown,
+    /// which is useful for context-dependent items (e.g. "Paste" enabled only when the
+    /// clipboard has content).
+    ///
+    /// The menu-open notification arrives on the tray thread and is handled on the next
+    /// frame, so the updated state reaches the host about one frame after the menu opens.
+    /// Most hosts update an open menu live; on others the change shows the next time the
+    /// menu is opened.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - Unique identifier for this menu item
+    /// - `label` - Text displayed in the menu
+    /// - `icon_name` - System icon name (empty string for no icon)
+    /// - `visible` - Whether the item is visible
+    /// - `enabled_provider` - Callable returning whether the item is enabled
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.add_menu_item_dynamic("paste", "Paste", "edit-paste", true,
+    ///     func(): return DisplayServer.clipboard_has())
+    /// ```
+    #[func]
+    fn add_menu_item_dynamic(
+        &mut self,
+        id: GString,
+        label: GString,
+        icon_name: GString,
+        visible: bool,
+        enabled_provider: Callable,
+    ) {
+        let enabled = Self::evaluate_enabled_provider(&enabled_provider);
+        self.enabled_providers
+            .insert(id.to_string(), enabled_provider);
+        self.add_menu_item(id, label, icon_name, enabled, visible);
+    }
+
+    /// Fills a submenu from a Callable the first time the tray menu is opened.
+    ///
+    /// For submenus with many entries (e.g. a list of save files), this avoids building
+    /// them before the user looks at the menu. Until the provider has run, the submenu
+    /// shows a disabled "Loading..." placeholder, passed through `tr()` so projects can
+    /// translate it. The result is cached; call `invalidate_submenu()` when the entries
+    /// change to have them loaded again.
+    ///
+    /// The provider is called with no arguments and must return an Array of item
+    /// Dictionaries, as described on `replace_menu_item()`. It runs when the tray menu
+    /// is about to be shown, since hosts do not report opening a submenu to the tray, so
+    /// the entries reach the host about one frame after the menu opens.
+    ///
+    /// # Parameters
+    ///
+    /// - `submenu_label` - Label of the submenu, which must already exist
+    /// - `provider` - Callable returning the items of the submenu
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the submenu was found, `false` otherwise.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.begin_submenu("Browse saves", "document-open", true, true)
+    /// tray_icon.set_submenu_provider("Browse saves", func():
+    ///     var items = []
+    ///     for save in DirAccess.get_files_at("user://saves"):
+    ///         items.append({"type": "standard", "id": "load:" + save, "label": save})
+    ///     return items
+    /// )
+    /// ```
+    #[func]
+    fn set_submenu_provider(&mut self, submenu_label: GString, provider: Callable) -> bool {
+        let label = submenu_label.to_string();
+        let placeholder = self.submenu_placeholder();
+        {
+            let mut state = self.state.lock().unwrap();
+            let Some(submenu) = state.find_submenu_items_mut(&label) else {
+                return false;
+            };
+            *submenu = vec![placeholder];
+        }
+
+        self.loaded_submenus.remove(&label);
+        self.submenu_providers.insert(label, provider);
+        true
+    }
+
+    /// Has the provider of a submenu run again the next time the tray menu is opened.
+    ///
+    /// The current entries stay visible until then.
+    ///
+    /// # Parameters
+    ///
+    /// - `submenu_label` - Label of a submenu filled by `set_submenu_provider()`
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the submenu has a provider, `false` otherwise.
+    #[func]
+    fn invalidate_submenu(&mut self, submenu_label: GString) -> bool {
+        let label = submenu_label.to_string();
+        self.loaded_submenus.remove(&label);
+        self.submenu_providers.contains_key(&label)
+    }
+
+    /// Adds a standard menu item with its own handler.
+    ///
+    /// The callback is called with no arguments when the item is activated. By default
+    /// the `menu_activated` signal is emitted too; set `suppress_global` to skip it for
+    /// items whose callback is their only handler.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - Unique identifier for this menu item
+    /// - `label` - Text displayed in the menu
+    /// - `icon_name` - System icon name (empty string for no icon)
+    /// - `enabled` - Whether the item can be clicked
+    /// - `visible` - Whether the item is visible
+    /// - `callback` - Callable invoked when the item is activated
+    /// - `suppress_global` - Whether to skip emitting `menu_activated` for this item
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.add_menu_item_callable("quit", "Quit", "application-exit", true, true,
+    ///     get_tree().quit, true)
+    /// ```
+    #[func]
+    #[allow(clippy::too_many_arguments)]
+    fn add_menu_item_callable(
+        &mut self,
+        id: GString,
+        label: GString,
+        icon_name: GString,
+        enabled: bool,
+        visible: bool,
+        callback: Callable,
+        suppress_global: bool,
+    ) {
+        self.item_callbacks.insert(id.to_string(), callback);
+
+        let mut state = self.state.lock().unwrap();
+        state.menu.push(MenuItemData::Standard {
+            id: id.to_string(),
+            label: label.to_string(),
+            icon_name: icon_name.to_string(),
+            enabled,
+            visible,
+            priority: 0,
+            suppress_global,
+        });
+    }
+
+    /// Adds a menu item with a checkmark that can be toggled.
+    ///
+    /// When toggled, emits the `checkmark_toggled` signal with the item's ID and new state.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - Unique identifier for this checkmark item
+    /// - `label` - Text displayed in the menu
+    /// - `icon_name` - System icon name (empty string for no icon)
+    /// - `checked` - Initial checked state
+    /// - `enabled` - Whether the item can be clicked
+    /// - `visible` - Whether the item is visible
+    #[func]
+    fn add_checkmark_item(
+        &mut self,
+        id: GString,
+        label: GString,
+        icon_name: GString,
+        checked: bool,
+        enabled: bool,
+        visible: bool,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        state.menu.push(MenuItemData::Checkmark {
+            id: id.to_string(),
+            label: label.to_string(),
+            icon_name: icon_name.to_string(),
+            enabled,
+            visible,
+            checked,
+            priority: 0,
+        });
+    }
+
+    /// Adds several checkmark items at once.
+    ///
+    /// Each key of `items` is the ID of a checkmark item, and each value a Dictionary
+    /// with a required `label` String and the optional keys `icon_name` (String, default
+    /// empty), `checked` (bool, default `false`), `enabled` and `visible` (bool, default
+    /// `true`). Items are added in the order of the Dictionary. Malformed entries and IDs
+    /// already in the menu are skipped with a warning.
+    ///
+    /// # Parameters
+    ///
+    /// - `items` - Dictionary mapping item IDs to item descriptions
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of items added.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.add_checkmark_items_from_dict({
+    ///     "autostart": {"label": "Start on Boot", "checked": true},
+    ///     "notifications": {"label": "Show Notifications"},
+    /// })
+    /// ```
+    #[func]
+    fn add_checkmark_items_from_dict(&mut self, items: Dictionary) -> i64 {
+        let mut state = self.state.lock().unwrap();
+        let mut added = 0;
+
+        for (key, value) in items.iter_shared() {
+            let parsed = key
+                .try_to::<GString>()
+                .map_err(|_| "the key must be a String".to_string())
+                .and_then(|id| {
+                    let dict = value
+                        .try_to::<Dictionary>()
+                        .map_err(|_| "the value must be a Dictionary".to_string())?;
+                    Ok(MenuItemData::Checkmark {
+                        id: id.to_string(),
+                        label: Self::dict_required::<GString>(&dict, "label")?.to_string(),
+                        icon_name: Self::dict_or(&dict, "icon_name", GString::new())?.to_string(),
+                        enabled: Self::dict_or(&dict, "enabled", true)?,
+                        visible: Self::dict_or(&dict, "visible", true)?,
+                        checked: Self::dict_or(&dict, "checked", false)?,
+                        priority: 0,
+                    })
+                });
+
+            match parsed {
+                Ok(item) => match state.push_item(item) {
+                    Ok(()) => added += 1,
+                    Err(e) => godot_warn!("Skipping checkmark item: {}", e),
+                },
+                Err(e) => godot_warn!("Skipping checkmark item {}: {}", key, e),
+            }
+        }
+        added
+    }
+
+    /// Creates a new radio button group.
+    ///
+    /// Radio options must be added to this group using `add_radio_option`.
+    /// Only one option in a group can be selected at a time.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - Unique identifier for this radio group
+    /// - `selected` - Index of the initially selected option (0-based)
+    #[func]
+    fn add_radio_group(&mut self, id: GString, selected: i64) {
+        let mut state = self.state.lock().unwrap();
+        state.menu.push(MenuItemData::RadioGroup {
+            id: id.to_string(),
+            selected: selected as usize,
+            options: Vec::new(),
+            emit_on_reselect: true,
+            priority: 0,
+        });
+    }
+
+    /// Adds a radio button option to an existing radio group.
+    ///
+    /// When selected, emits the `radio_selected` signal with the group ID, option index, and option ID.
+    ///
+    /// # Parameters
+    ///
+    /// - `group_id` - ID of the radio group to add this option to
+    /// - `option_id` - Unique identifier for this option
+    /// - `label` - Text displayed in the menu
+    /// - `icon_name` - System icon name (empty string for no icon)
+    /// - `enabled` - Whether the option can be selected
+    /// - `visible` - Whether the option is visible
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the option was added successfully, `false` if the group was not found.
+    #[func]
+    fn add_radio_option(
+        &mut self,
+        group_id: GString,
+        option_id: GString,
+        label: GString,
+        icon_name: GString,
+        enabled: bool,
+        visible: bool,
+    ) -> bool {
+        let group_id_str = group_id.to_string();
+        let option = RadioItemData {
+            id: option_id.to_string(),
+            label: label.to_string(),
+            icon_name: icon_name.to_string(),
+            enabled,
+            visible,
+        };
+        let result = self
+            .state
+            .lock()
+            .unwrap()
+            .add_radio_option(&group_id_str, option);
+        if let Err(e) = &result {
+            self.report_failure("add_radio_option", e.to_string());
+        }
+        result.is_ok()
+    }
+
+    /// Adds a visual separator line to the menu.
+    #[func]
+    fn add_separator(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.menu.push(MenuItemData::Separator);
+    }
+
+    /// Adds a named section to the menu: a disabled header item, the given items, and
+    /// a trailing separator.
+    ///
+    /// Items are described by Dictionaries in the format documented on
+    /// `replace_menu_item()`. Nothing is added if any of them is invalid.
+    ///
+    /// # Parameters
+    ///
+    /// - `section_label` - Text of the header item
+    /// - `items` - Array of item Dictionaries
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the section was added, `false` if an item Dictionary is invalid.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.add_menu_item_group("Actions", [
+    ///     {"type": "standard", "id": "new", "label": "New Window"},
+    ///     {"type": "standard", "id": "open", "label": "Open..."},
+    /// ])
+    /// tray_icon.add_menu_item("quit", "Quit")
+    /// ```
+    #[func]
+    fn add_menu_item_group(&mut self, section_label: GString, items: VariantArray) -> bool {
+        let mut section = vec![MenuItemData::Header {
+            label: section_label.to_string(),
+        }];
+        for item in items.iter_shared() {
+            let parsed = item
+                .try_to::<Dictionary>()
+                .map_err(|_| "'items' must only contain Dictionaries".to_string())
+                .and_then(|dict| Self::menu_item_from_dict(&dict));
+            match parsed {
+                Ok(item) => section.push(item),
+                Err(e) => {
+                    godot_error!("Invalid menu item in group '{}': {}", section_label, e);
+                    return false;
+                }
+            }
+        }
+        section.push(MenuItemData::Separator);
+
+        let mut state = self.state.lock().unwrap();
+        state.menu.extend(section);
+        true
+    }
+
+    /// Adds the standard media player controls to the end of the menu.
+    ///
+    /// The items use freedesktop icon names and, besides `menu_activated`, emit the
+    /// dedicated `play_pause_pressed`, `previous_pressed`, `next_pressed`, and
+    /// `stop_pressed` signals. Their IDs are `"media_play_pause"`, `"media_previous"`,
+    /// `"media_next"`, and `"media_stop"`. The play/pause item starts in the paused state;
+    /// use `set_playback_state()` to update it. The labels are passed through `tr()`, so
+    /// "Play", "Pause", "Previous", "Next" and "Stop" can be translated.
+    ///
+    /// # Parameters
+    ///
+    /// - `flags` - Combination of the `MEDIA_*` constants selecting the items to add
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the controls were added, `false` if no item was selected or
+    /// media controls are already in the menu.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.add_media_controls(TrayIcon.MEDIA_ALL)
+    /// tray_icon.add_separator()
+    /// tray_icon.add_menu_item("quit", "Quit")
+    /// tray_icon.play_pause_pressed.connect(_on_play_pause)
+    /// ```
+    #[func]
+    fn add_media_controls(&mut self, flags: i64) -> bool {
+        let items = media::items(flags, |label| self.base().tr(label).to_string());
+        if items.is_empty() {
+            godot_error!("No media controls selected");
+            return false;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        if state.menu.iter().any(media::is_control) {
+            godot_error!("Media controls were already added");
+            return false;
+        }
+        state.menu.extend(items);
+        true
+    }
+
+    /// Removes all items added by `add_media_controls()`, keeping other items.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if any item was removed.
+    #[func]
+    fn remove_media_controls(&mut self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let len = state.menu.len();
+        state.menu.retain(|item| !media::is_control(item));
+        state.menu.len() != len
+    }
+
+    /// Updates the play/pause item added by `add_media_controls()` for the playback
+    /// state, and refreshes the tray if it is spawned.
+    ///
+    /// While playing, the item shows "Pause" with the `media-playback-pause` icon,
+    /// otherwise "Play" with `media-playback-start`, both passed through `tr()`.
+    ///
+    /// # Parameters
+    ///
+    /// - `playing` - Whether media is playing
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the item was updated, `false` if there is no play/pause item.
+    #[func]
+    fn set_playback_state(&mut self, playing: bool) -> bool {
+        {
+            let mut state = self.state.lock().unwrap();
+            let Some(item) = state.find_item_mut(media::PLAY_PAUSE_ID) else {
+                return false;
+            };
+            media::set_playing(item, playing, |label| self.base().tr(label).to_string());
+        }
+        self.refresh();
+        true
+    }
+
+    /// Creates a submenu that can contain other menu items.
+    ///
+    /// After calling this, use `add_submenu_item`, `add_submenu_checkmark`, and `add_submenu_separator`
+    /// to add items to the submenu.
+    ///
+    /// # Parameters
+    ///
+    /// - `label` - Text displayed for the submenu
+    /// - `icon_name` - System icon name (empty string for no icon)
+    /// - `enabled` - Whether the submenu can be opened
+    /// - `visible` - Whether the submenu is visible
+    #[func]
+    fn begin_submenu(&mut self, label: GString, icon_name: GString, enabled: bool, visible: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.menu.push(MenuItemData::SubMenu {
+            label: label.to_string(),
+            icon_name: icon_name.to_string(),
+            enabled,
+            visible,
+            submenu: Vec::new(),
+            priority: 0,
+        });
+    }
+
+    /// Adds a standard menu item to an existing submenu.
+    ///
+    /// # Parameters
+    ///
+    /// - `submenu_label` - Label of the parent submenu
+    /// - `id` - Unique identifier for this menu item
+    /// - `label` - Text displayed in the submenu
+    /// - `icon_name` - System icon name (empty string for no icon)
+    /// - `enabled` - Whether the item can be clicked
+    /// - `visible` - Whether the item is visible
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the item was added successfully, `false` if the submenu was not found.
+    #[func]
+    fn add_submenu_item(
+        &mut self,
+        submenu_label: GString,
+        id: GString,
+        label: GString,
+        icon_name: GString,
+        enabled: bool,
+        visible: bool,
+    ) -> bool {
+        self.push_to_submenu(
+            "add_submenu_item",
+            &submenu_label,
+            MenuItemData::Standard {
+                id: id.to_string(),
+                label: label.to_string(),
+                icon_name: icon_name.to_string(),
+                enabled,
+                visible,
+                priority: 0,
+                suppress_global: false,
+            },
+        )
+    }
+
+    /// Adds a checkmark item to an existing submenu.
+    ///
+    /// # Parameters
+    ///
+    /// - `submenu_label` - Label of the parent submenu
+    /// - `id` - Unique identifier for this checkmark item
+    /// - `label` - Text displayed in the submenu
+    /// - `icon_name` - System icon name (empty string for no icon)
+    /// - `checked` - Initial checked state
+    /// - `enabled` - Whether the item can be clicked
+    /// - `visible` - Whether the item is visible
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the item was added successfully, `false` if the submenu was not found.
+    #[func]
+    fn add_submenu_checkmark(
+        &mut self,
+        submenu_label: GString,
+        id: GString,
+        label: GString,
+        icon_name: GString,
+        checked: bool,
+        enabled: bool,
+        visible: bool,
+    ) -> bool {
+        self.push_to_submenu(
+            "add_submenu_checkmark",
+            &submenu_label,
+            MenuItemData::Checkmark {
+                id: id.to_string(),
+                label: label.to_string(),
+                icon_name: icon_name.to_string(),
+                enabled,
+                visible,
+                checked,
+                priority: 0,
+            },
+        )
+    }
+
+    /// Adds a separator to an existing submenu.
+    ///
+    /// # Parameters
+    ///
+    /// - `submenu_label` - Label of the parent submenu
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the separator was added successfully, `false` if the submenu was not found.
+    #[func]
+    fn add_submenu_separator(&mut self, submenu_label: GString) -> bool {
+        self.push_to_submenu(
+            "add_submenu_separator",
+            &submenu_label,
+            MenuItemData::Separator,
+        )
+    }
+
+    /// Adds a radio group with all of its options to an existing submenu in a single call.
+    ///
+    /// Equivalent to creating the group and adding each option separately, but avoids
+    /// one GDExtension call per option. Options are enabled and visible.
+    ///
+    /// # Parameters
+    ///
+    /// - `submenu_label` - Label of the parent submenu
+    /// - `group_id` - Unique identifier for the radio group
+    /// - `selected` - Index of the initially selected option (0-based)
+    /// - `option_ids` - Unique identifiers of the options
+    /// - `labels` - Text displayed for each option (same length as `option_ids`)
+    /// - `icon_names` - System icon name for each option (same length as `option_ids`, or empty for no icons)
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the group was added successfully, `false` if the submenu was not found
+    /// or the array lengths do not match.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.add_radio_group_with_options_to_submenu("Quality", "quality", 1,
+    ///     ["low", "high"], ["Low", "High"], [])
+    /// ```
+    #[func]
+    fn add_radio_group_with_options_to_submenu(
+        &mut self,
+        submenu_label: GString,
+        group_id: GString,
+        selected: i64,
+        option_ids: PackedStringArray,
+        labels: PackedStringArray,
+        icon_names: PackedStringArray,
+    ) -> bool {
+        if labels.len() != option_ids.len()
+            || (!icon_names.is_empty() && icon_names.len() != option_ids.len())
+        {
+            let detail = format!(
+                "Radio option arrays have mismatched lengths: {} ids, {} labels, {} icon names",
+                option_ids.len(),
+                labels.len(),
+                icon_names.len()
+            );
+            godot_error!("{}", detail);
+            self.report_failure("add_radio_group_with_options_to_submenu", detail);
+            return false;
+        }
+
+        let options: Vec<RadioItemData> = option_ids
+            .as_slice()
+            .iter()
+            .enumerate()
+            .map(|(index, option_id)| RadioItemData {
+                id: option_id.to_string(),
+                label: labels.as_slice()[index].to_string(),
+                icon_name: icon_names
+                    .as_slice()
+                    .get(index)
+                    .map(GString::to_string)
+                    .unwrap_or_default(),
+                enabled: true,
+                visible: true,
+            })
+            .collect();
+
+        self.push_to_submenu(
+            "add_radio_group_with_options_to_submenu",
+            &submenu_label,
+            MenuItemData::RadioGroup {
+                id: group_id.to_string(),
+                selected: selected as usize,
+                options,
+                emit_on_reselect: true,
+                priority: 0,
+            },
+        )
+    }
+
+    /// Programmatically sets the state of a checkmark item.
+    ///
+    /// Emits `checkmark_state_changed` with `SOURCE_PROGRAM` if the state changed, but
+    /// not `checkmark_toggled`.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - ID of the checkmark item to modify
+    /// - `checked` - New checked state
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the checkmark was found and updated, `false` otherwise.
+    #[func]
+    fn set_checkmark_state(&mut self, id: GString, checked: bool) -> bool {
+        let id_str = id.to_string();
+        let changed = self.state.lock().unwrap().set_checkmark(&id_str, checked);
+
+        match &changed {
+            Ok(true) => self.emit_state_changed(
+                &TrayEvent::CheckmarkToggled(id_str, checked),
+                Self::SOURCE_PROGRAM,
+            ),
+            Ok(false) => {}
+            Err(e) => self.report_failure("set_checkmark_state", e.to_string()),
+        }
+        changed.is_ok()
+    }
+
+    /// Programmatically selects a radio option in a radio group.
+    ///
+    /// Emits `radio_state_changed` with `SOURCE_PROGRAM` if the selection changed, but
+    /// not `radio_selected`.
+    ///
+    /// # Parameters
+    ///
+    /// - `group_id` - ID of the radio group
+    /// - `index` - Index of the option to select (0-based)
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the group was found and the selection was updated, `false` otherwise.
+    #[func]
+    fn set_radio_selected(&mut self, group_id: GString, index: i64) -> bool {
+        let group_id = group_id.to_string();
+        let changed = self.select_radio_option("set_radio_selected", &group_id, index);
+
+        if let Some(Some(option_id)) = &changed {
+            self.emit_state_changed(
+                &TrayEvent::RadioSelected(group_id, index as usize, option_id.clone()),
+                Self::SOURCE_PROGRAM,
+            );
+        }
+        changed.is_some()
+    }
+
+    /// Handler of `radio_selected` keeping the radio selection in sync with the event.
+    ///
+    /// Selects the option like `set_radio_selected_silent()`, but never reports a
+    /// failure: the tray already selected the option when it was clicked, so this only
+    /// matters when `radio_selected` is emitted by other code. Refreshes the tray if the selection changed.
+    ///
+    /// Connected by default when the node is ready; set `radio_default_handler` to
+    /// `false` before that to handle `radio_selected` entirely yourself. The IDs may be
+    /// Strings or StringNames, see `set_string_name_ids()`.
+    ///
+    /// # Parameters
+    ///
+    /// - `group_id` - ID of the radio group
+    /// - `index` - Index of the selected option (0-based)
+    /// - `_option_id` - ID of the selected option, unused
+    #[func]
+    fn on_radio_selected_default(&mut self, group_id: Variant, index: i64, _option_id: Variant) {
+        let Ok(index) = usize::try_from(index) else {
+            return;
+        };
+        let group_id = group_id.stringify().to_string();
+        self.state
+            .lock()
+            .unwrap()
+            .find_and_select_radio(&group_id, index);
+        self.refresh();
+    }
+
+    /// Selects a radio option without emitting any signal.
+    ///
+    /// Unlike `set_radio_selected()`, this does not emit `radio_state_changed` either,
+    /// e.g. for restoring a saved selection before handlers should react to it.
+    ///
+    /// # Parameters
+    ///
+    /// - `group_id` - ID of the radio group
+    /// - `index` - Index of the option to select (0-based)
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the group was found and the selection was updated, `false` otherwise.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// tray_icon.set_radio_selected_silent("quality", config.get_value("video", "quality", 1))
+    /// ```
+    #[func]
+    fn set_radio_selected_silent(&mut self, group_id: GString, index: i64) -> bool {
+        self.select_radio_option("set_radio_selected_silent", &group_id.to_string(), index)
+            .is_some()
+    }
+
+    /// Sets whether re-selecting the already selected radio option emits `radio_selected`.
+    ///
+    /// By default, clicking the currently selected option emits `radio_selected` again.
+    /// When disabled, such clicks are ignored. This also applies after the selection was
+    /// changed programmatically with `set_radio_selected()`.
+    ///
+    /// # Parameters
+    ///
+    /// - `group_id` - ID of the radio group
+    /// - `emit` - Whether to emit the signal when the selected option is clicked again
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the radio group was found, `false` otherwise.
+    #[func]
+    fn set_radio_emit_on_reselect(&mut self, group_id: GString, emit: bool) -> bool {
+        let group_id = group_id.to_string();
+        if let Some(MenuItemData::RadioGroup {
+            emit_on_reselect, ..
+        }) = self.state.lock().unwrap().find_item_mut(&group_id)
+        {
+            *emit_on_reselect = emit;
+            return true;
+        }
+
+        self.report_failure(
+            "set_radio_emit_on_reselect",
+            format!("no radio group with ID '{}'", group_id),
+        );
+        false
+    }
+
+    /// Returns whether a menu item with the given ID exists.
+    ///
+    /// Items inside submenus are found as well.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - ID of the menu item
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the item exists, `false` otherwise.
+    #[func]
+    fn menu_has_id(&self, id: GString) -> bool {
+        let state = self.state.lock().unwrap();
+        state.contains_id(&id.to_string())
+    }
+
+    /// Returns the label of a menu item.
+    ///
+    /// Submenus have no ID and are looked up by their label instead.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - ID of the menu item (or label of a submenu)
+    ///
+    /// # Returns
+    ///
+    /// Returns the item's label, or an empty string if the item was not found or has no label.
+    #[func]
+    fn get_item_label(&self, id: GString) -> GString {
+        let state = self.state.lock().unwrap();
+        let id_str = id.to_string();
+        state
+            .find_item(&id_str)
+            .or_else(|| state.find_submenu(&id_str))
+            .and_then(MenuItemData::label)
+            .map(GString::from)
+            .unwrap_or_default()
+    }
+
+    /// Returns the type of a menu item.
+    ///
+    /// Submenus have no ID and are looked up by their label instead.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - ID of the menu item (or label of a submenu)
+    ///
+    /// # Returns
+    ///
+    /// Returns one of `"standard"`, `"checkmark"`, `"radio_group"`, `"submenu"`, or
+    /// `"separator"`, or an empty string if the item was not found.
+    #[func]
+    fn get_item_type(&self, id: GString) -> GString {
+        let state = self.state.lock().unwrap();
+        let id_str = id.to_string();
+        state
+            .find_item(&id_str)
+            .or_else(|| state.find_submenu(&id_str))
+            .map(|item| GString::from(item.type_name()))
+            .unwrap_or_default()
+    }
+
+    /// Returns the options of a radio group.
+    ///
+    /// # Parameters
+    ///
+    /// - `group_id` - ID of the radio group (can be inside a submenu)
+    ///
+    /// # Returns
+    ///
+    /// Returns an Array of Dictionaries with the keys `id`, `label`, `enabled`, and
+    /// `visible`, one per option in display order, or an empty Array if the group was
+    /// not found.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// for option in tray_icon.get_radio_options("theme"):
+    ///     theme_list.add_item(option.label)
+    /// ```
+    #[func]
+    fn get_radio_options(&self, group_id: GString) -> Array<Dictionary> {
+        let state = self.state.lock().unwrap();
+        let mut result = Array::new();
+        if let Some(MenuItemData::RadioGroup { options, .. }) =
+            state.find_item(&group_id.to_string())
+        {
+            for option in options {
+                result.push(&vdict! {
+                    "id": option.id.as_str(),
+                    "label": option.label.as_str(),
+                    "enabled": option.enabled,
+                    "visible": option.visible,
+                });
+            }
+        }
+        result
+    }
+
+    /// Returns the ID of the selected option of a radio group.
+    ///
+    /// # Parameters
+    ///
+    /// - `group_id` - ID of the radio group (can be inside a submenu)
+    ///
+    /// # Returns
+    ///
+    /// Returns the selected option's ID, or an empty string if the group was not found
+    /// or has no option at the selected index.
+    #[func]
+    fn get_radio_selected_option(&self, group_id: GString) -> GString {
+        let state = self.state.lock().unwrap();
+        match state.find_item(&group_id.to_string()) {
+            Some(MenuItemData::RadioGroup {
+                selected, options, ..
+            }) => options
+                .get(*selected)
+                .map(|option| GString::from(option.id.as_str()))
+                .unwrap_or_default(),
+            _ => GString::new(),
+        }
+    }
+
+    /// Finds all menu items whose label matches the given text.
+    ///
+    /// Searches the whole menu tree, including submenus and radio options. Items nested
+    /// in submenus are returned as path IDs prefixed by their parent submenu labels
+    /// (e.g. `"File/open"`), radio options as `"group_id/option_id"`, and submenus
+    /// themselves as their label path.
+    ///
+    /// # Parameters
+    ///
+    /// - `label` - The label text to search for
+    /// - `exact` - If `true` (the usual choice), labels must match exactly; if `false`, a
+    ///   case-insensitive substring match is used
+    ///
+    /// # Returns
+    ///
+    /// Returns the IDs of all matching items in menu order, or an empty array if none match.
+    ///
+    /// # Example (GDScript)
+    /// ```gdscript
+    /// var quit_ids = tray_icon.find_items_by_label("Quit", true)
+    /// var save_ids = tray_icon.find_items_by_label("save", false)
+    /// ```
+    #[func]
+    fn find_items_by_label(&self, label: GString, exact: bool) -> PackedStringArray {
+        let state = self.state.lock().unwrap();
+        state
+            .find_items_by_label(&label.to_string(), exact)
+            .iter()
+            .map(GString::from)
+            .collect()
+    }
+}
+
+/// Tears down the spawned tray when the node is freed.
+///
+/// Kept-alive trays are parked for adoption instead. Otherwise the teardown order is:
+///
+/// 1. Shut down the ksni service and wait for it, so no menu callback runs afterwards.
+/// 2. Drop the state's event sender, so menus built later cannot reach this node.
+/// 3. Drop the event receiver, then release the state.
+///
+/// Callbacks that are already running when the node is freed finish normally; events
+/// they send after the receiver is gone are discarded.
+///
+/// Menu callbacks reach the state through the ksni service and hold no references of
+/// their own. The blocking ksni backend only frees the stopped service, with its
+/// reference to the state, the next time its runtime runs (e.g. when another tray is
+/// spawned), which step 2 makes harmless.
+impl Drop for TrayIcon {
+    fn drop(&mut self) {
+        if self.engine_singleton {
+            singleton::forget();
+        }
+
+        if let Some(tray_id) = self.kept_alive_id.take()
+            && let (Some(handle), Some(event_receiver)) =
+                (self.handle.take(), self.event_receiver.take())
+        {
+            registry::park(
+                &tray_id,
+                KeptAliveTray {
+                    state: self.state.clone(),
+                    handle,
+                    event_receiver,
+                },
+            );
+            return;
+        }
+
+        self.shutdown_tray();
+    }
+}
+
+impl TrayIcon {
+    /// Takes all events waiting in the channel and emits them as signals, returning how
+    /// many were taken.
+    fn dispatch_events(&mut self) -> i64 {
+        let mut events = Vec::new();
+        if let Some(ref rx) = self.event_receiver {
+            while let Ok(event) = rx.try_recv() {
+                TrayStats::increment(&self.stats.events_received);
+                events.push(event);
+            }
+        }
+        let count = events.len() as i64;
+
+        let mut batch: Vec<Dictionary> = Vec::new();
+        for event in events {
+            self.record_event(&event);
+            if matches!(
+                event,
+                TrayEvent::MenuActivated(_)
+                    | TrayEvent::CheckmarkToggled(..)
+                    | TrayEvent::RadioSelected(..)
+            ) {
+                // Hosts close the menu when an item is clicked.
+                self.close_menu();
+            }
+            self.emit_state_changed(&event, Self::SOURCE_USER);
+            match event {
+                TrayEvent::MenuActivated(id) => {
+                    if self.is_activation_debounced(&id) {
+                        TrayStats::increment(&self.stats.events_dropped);
+                        continue;
+                    }
+                    self.forward_activation_to_popup(&id);
+
+                    let (callback, suppress_global) = self.item_activation_handling(&id);
+                    if let Some(callback) = callback {
+                        // Allow the callback to call back into this node while it runs.
+                        let _guard = self.base_mut();
+                        callback.call(&[]);
+                    }
+                    if let Some(signal) = self.media_signal(&id) {
+                        self.base_mut().emit_signal(signal, &[]);
+                    }
+                    if suppress_global {
+                        continue;
+                    }
+
+                    let id = self.id_variant(&id);
+                    if self.batch_signal {
+                        batch.push(vdict! { "type": "menu_activated", "id": id });
+                    } else {
+                        self.base_mut().emit_signal("menu_activated", &[id]);
+                    }
+                    TrayStats::increment(&self.stats.events_emitted);
+                }
+                TrayEvent::CheckmarkToggled(id, checked) => {
+                    self.forward_to_popup(&id);
+                    let id = self.id_variant(&id);
+                    if self.batch_signal {
+                        batch.push(vdict! {
+                            "type": "checkmark_toggled",
+                            "id": id,
+                            "checked": checked,
+                        });
+                    } else {
+                        self.base_mut()
+                            .emit_signal("checkmark_toggled", &[id, Variant::from(checked)]);
+                    }
+                    TrayStats::increment(&self.stats.events_emitted);
+                }
+                TrayEvent::RadioSelected(group_id, index, option_id) => {
+                    self.forward_to_popup(&option_id);
+                    let group_id = self.id_variant(&group_id);
+                    let option_id = self.id_variant(&option_id);
+                    if self.batch_signal {
+                        batch.push(vdict! {
+                            "type": "radio_selected",
+                            "id": group_id,
+                            "index": index as i64,
+                            "option_id": option_id,
+                        });
+                    } else {
+                        self.base_mut().emit_signal(
+                            "radio_selected",
+                            &[group_id, Variant::from(index as i64), option_id],
+                        );
+                    }
+                    TrayStats::increment(&self.stats.events_emitted);
+                }
+                TrayEvent::Activated(x, y) => {
+                    self.last_activation = Some(Vector2i::new(x, y));
+                    if self.batch_signal {
+                        batch.push(vdict! {
+                            "type": "activated",
+                            "x": x as i64,
+                            "y": y as i64,
+                        });
+                    } else {
+                        self.base_mut().emit_signal(
+                            "activated",
+                            &[Variant::from(x as i64), Variant::from(y as i64)],
+                        );
+                        self.base_mut().emit_signal(
+                            "activated_at_screen",
+                            &[Variant::from(Vector2i::new(x, y))],
+                        );
+                    }
+                    TrayStats::increment(&self.stats.events_emitted);
+
+                    if self.menu_fallback {
+                        self.show_fallback_menu(Vector2i::new(x, y));
+                    }
+                }
+                TrayEvent::Scrolled(delta, orientation) => {
+                    let steps = self.scroll.push(delta, orientation) as i64;
+                    if steps == 0 {
+                        continue;
+                    }
+
+                    let orientation = match orientation {
+                        ScrollOrientation::Vertical => Self::ORIENTATION_VERTICAL,
+                        ScrollOrientation::Horizontal => Self::ORIENTATION_HORIZONTAL,
+                    };
+                    if self.batch_signal {
+                        batch.push(vdict! {
+                            "type": "scrolled",
+                            "steps": steps,
+                            "orientation": orientation,
+                        });
+                    } else {
+                        self.base_mut().emit_signal(
+                            "scrolled",
+                            &[Variant::from(steps), Variant::from(orientation)],
+                        );
+                    }
+                    TrayStats::increment(&self.stats.events_emitted);
+                }
+                TrayEvent::MenuAboutToShow => {
+                    // The menu was dismissed without a click since it was last opened.
+                    self.close_menu();
+                    self.menu_open = true;
+                    self.menu_opened_at = Some(Instant::now());
+                    self.base_mut().emit_signal("menu_opened", &[]);
+                    TrayStats::increment(&self.stats.events_emitted);
+
+                    let dynamic_changed = self.update_dynamic_items();
+                    if self.load_submenus() || dynamic_changed {
+                        self.refresh();
+                    }
+                }
+            }
+        }
+
+        if !batch.is_empty() {
+            let batch: Array<Dictionary> = batch.into_iter().collect();
+            self.base_mut()
+                .emit_signal("menu_events_batch", &[Variant::from(batch)]);
+        }
+
+        if !self.menu_close_timeout.is_zero()
+            && self
+                .menu_opened_at
+                .is_some_and(|opened| opened.elapsed() >= self.menu_close_timeout)
+        {
+            self.close_menu();
+        }
+        count
+    }
+
+    /// Shuts down the spawned tray, if any, in the order documented on `Drop`.
+    fn shutdown_tray(&mut self) {
+        if let Some(tray_id) = self.kept_alive_id.take() {
+            registry::unregister(&tray_id);
+        }
+
+        if let Some(handle) = self.handle.take() {
+            handle.shutdown().wait();
+        }
+
+        // A callback that panicked while holding the lock must not abort teardown
+        if let Ok(mut state) = self.state.lock() {
+            state.event_sender = None;
+        }
+
+        self.event_receiver = None;
+    }
+
+    /// Registers the spawned tray as kept alive and owned by this node.
+    fn register_kept_alive(&mut self) {
+        let tray_id = self.state.lock().unwrap().tray_id.clone();
+        registry::register(&tray_id);
+        self.kept_alive_id = Some(tray_id);
+    }
+
+    /// Loads a texture or image from a path, as documented on `set_icon_from_path()`.
+    fn load_icon_resource(path: &GString) -> Result<IconResource, String> {
+        let path = GString::from(icon::resolve_import_path(&path.to_string()));
+        let mut loader = ResourceLoader::singleton();
+
+        if loader.exists(&path) {
+            let Some(resource) = loader.load(&path) else {
+                return Err(format!("Failed to load resource from path: {}", path));
+            };
+
+            // CompressedTexture2D (the imported form of image files), AtlasTexture,
+            // PortableCompressedTexture2D and friends are all Texture2Ds.
+            let resource = match resource.try_cast::<Texture2D>() {
+                Ok(texture) => return Ok(IconResource::Texture(texture)),
+                Err(resource) => resource,
+            };
+
+            // Image resources saved with ResourceSaver (e.g. `.res` / `.tres`).
+            if let Ok(image) = resource.try_cast::<Image>() {
+                return Ok(IconResource::Image(image));
+            }
+
+            return Err(format!(
+                "Resource is neither a Texture2D nor an Image: {}",
+                path
+            ));
+        }
+
+        if FileAccess::file_exists(&path)
+            && let Some(image) = Image::load_from_file(&path)
+        {
+            return Ok(IconResource::Image(image));
+        }
+
+        Err(format!("Failed to load resource from path: {}", path))
+    }
+
+    /// Hashes the dimensions, format and pixel data of an image.
+    fn image_hash(image: &Gd<Image>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        image.get_width().hash(&mut hasher);
+        image.get_height().hash(&mut hasher);
+        image.get_format().ord().hash(&mut hasher);
+        image.get_data().as_slice().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Converts a Godot image into an ARGB32 icon for ksni.
+    ///
+    /// Returns `None` and logs an error if the image cannot be converted.
+    fn image_to_icon(&self, image: &Gd<Image>) -> Option<ksni::Icon> {
+        // Get image dimensions
+        let width = image.get_width();
+        let height = image.get_height();
+
+        let expected_len = match icon::rgba_len(width, height) {
+            Ok(len) => len,
+            Err(_) if width <= 0 || height <= 0 => {
+                godot_error!("Invalid image dimensions: {}x{}", width, height);
+                return None;
+            }
+            Err(_) => {
+                godot_error!(
+                    "Image of {}x{} exceeds the maximum of {} pixels for an icon",
+                    width,
+                    height,
+                    icon::MAX_ICON_PIXELS
+                );
+                return None;
+            }
+        };
+
+        let mut img = image.duplicate().unwrap().cast::<Image>();
+        if img.is_compressed() {
+            img.decompress();
+        }
+
+        let bytes: Vec<u8> = if Self::is_float_format(img.get_format()) {
+            // Float formats hold linear (and possibly HDR) data, so apply the sRGB
+            // transfer with clamping before quantizing to 8 bits.
+            img.convert(Format::RGBAF);
+            icon::rgbaf_to_rgba8(img.get_data().as_slice(), !self.icon_float_is_srgb)
+        } else {
+            // Convert to RGBA8 if needed
+            img.convert(Format::RGBA8);
+            img.get_data().to_vec()
+        };
+
+        if bytes.len() != expected_len {
+            godot_error!(
+                "Image data size mismatch: expected {}, got {}",
+                expected_len,
+                bytes.len()
+            );
+            return None;
+        }
+
+        // Convert RGBA to ARGB for ksni
+        let mut argb_data = bytes;
+        if self.icon_opaque {
+            icon::rgba_to_argb_opaque(&mut argb_data);
+        } else {
+            icon::rgba_to_argb(&mut argb_data);
+        }
+        TrayStats::increment(&self.stats.icon_conversions);
+
+        Some(ksni::Icon {
+            width,
+            height,
+            data: argb_data,
+        })
+    }
+
+    /// Returns the Cargo features this crate was compiled with.
+    fn enabled_features() -> Vec<&'static str> {
+        [
+            ("gdextension", cfg!(feature = "gdextension")),
+            ("serde", cfg!(feature = "serde")),
+            ("toml", cfg!(feature = "toml")),
+        ]
+        .into_iter()
+        .filter_map(|(feature, enabled)| enabled.then_some(feature))
+        .collect()
+    }
+
+    /// Parses a menu item from a Dictionary, as documented on `replace_menu_item()`.
+    fn menu_item_from_dict(dict: &Dictionary) -> Result<MenuItemData, String> {
+        let item_type = Self::dict_required::<GString>(dict, "type")?.to_string();
+
+        let item = match item_type.as_str() {
+            "standard" => MenuItemData::Standard {
+                id: Self::dict_required::<GString>(dict, "id")?.to_string(),
+                label: Self::dict_required::<GString>(dict, "label")?.to_string(),
+                icon_name: Self::dict_or(dict, "icon_name", GString::new())?.to_string(),
+                enabled: Self::dict_or(dict, "enabled", true)?,
+                visible: Self::dict_or(dict, "visible", true)?,
+                priority: Self::dict_or(dict, "priority", 0)?,
+                suppress_global: Self::dict_or(dict, "suppress_global", false)?,
+            },
+            "checkmark" => MenuItemData::Checkmark {
+                id: Self::dict_required::<GString>(dict, "id")?.to_string(),
+                label: Self::dict_required::<GString>(dict, "label")?.to_string(),
+                icon_name: Self::dict_or(dict, "icon_name", GString::new())?.to_string(),
+                enabled: Self::dict_or(dict, "enabled", true)?,
+                visible: Self::dict_or(dict, "visible", true)?,
+                checked: Self::dict_or(dict, "checked", false)?,
+                priority: Self::dict_or(dict, "priority", 0)?,
+            },
+            "radio_group" => {
+                let mut options = Vec::new();
+                for option in Self::dict_or(dict, "options", VariantArray::new())?.iter_shared() {
+                    let option = option
+                        .try_to::<Dictionary>()
+                        .map_err(|_| "'options' must only contain Dictionaries".to_string())?;
+                    options.push(RadioItemData {
+                        id: Self::dict_required::<GString>(&option, "id")?.to_string(),
+                        label: Self::dict_required::<GString>(&option, "label")?.to_string(),
+                        icon_name: Self::dict_or(&option, "icon_name", GString::new())?.to_string(),
+                        enabled: Self::dict_or(&option, "enabled", true)?,
+                        visible: Self::dict_or(&option, "visible", true)?,
+                    });
+                }
+
+                MenuItemData::RadioGroup {
+                    id: Self::dict_required::<GString>(dict, "id")?.to_string(),
+                    selected: Self::dict_or::<i64>(dict, "selected", 0)?.max(0) as usize,
+                    options,
+                    emit_on_reselect: Self::dict_or(dict, "emit_on_reselect", true)?,
+                    priority: Self::dict_or(dict, "priority", 0)?,
+                }
+            }
+            "submenu" => {
+                let mut submenu = Vec::new();
+                for child in Self::dict_or(dict, "submenu", VariantArray::new())?.iter_shared() {
+                    let child = child
+                        .try_to::<Dictionary>()
+                        .map_err(|_| "'submenu' must only contain Dictionaries".to_string())?;
+                    submenu.push(Self::menu_item_from_dict(&child)?);
+                }
+
+                MenuItemData::SubMenu {
+                    label: Self::dict_required::<GString>(dict, "label")?.to_string(),
+                    icon_name: Self::dict_or(dict, "icon_name", GString::new())?.to_string(),
+                    enabled: Self::dict_or(dict, "enabled", true)?,
+                    visible: Self::dict_or(dict, "visible", true)?,
+                    submenu,
+                    priority: Self::dict_or(dict, "priority", 0)?,
+                }
+            }
+            "header" => MenuItemData::Header {
+                label: Self::dict_required::<GString>(dict, "label")?.to_string(),
+            },
+            "separator" => MenuItemData::Separator,
+            other => return Err(format!("unknown item type '{}'", other)),
+        };
+
+        Ok(item)
+    }
+
+    /// Describes a menu item as a Dictionary accepted by `menu_item_from_dict()`.
+    fn menu_item_to_dict(item: &MenuItemData) -> Dictionary {
+        match item {
+            MenuItemData::Standard {
+                id,
+                label,
+                icon_name,
+                enabled,
+                visible,
+                priority,
+                suppress_global,
+            } => vdict! {
+                "type": "standard",
+                "id": id.as_str(),
+                "label": label.as_str(),
+                "icon_name": icon_name.as_str(),
+                "enabled": *enabled,
+                "visible": *visible,
+                "priority": *priority,
+                "suppress_global": *suppress_global,
+            },
+            MenuItemData::Checkmark {
+                id,
+                label,
+                icon_name,
+                enabled,
+                visible,
+                checked,
+                priority,
+            } => vdict! {
+                "type": "checkmark",
+                "id": id.as_str(),
+                "label": label.as_str(),
+                "icon_name": icon_name.as_str(),
+                "enabled": *enabled,
+                "visible": *visible,
+                "checked": *checked,
+                "priority": *priority,
+            },
+            MenuItemData::RadioGroup {
+                id,
+                selected,
+                options,
+                emit_on_reselect,
+                priority,
+            } => {
+                let options: Array<Dictionary> = options
+                    .iter()
+                    .map(|option| {
+                        vdict! {
+                            "id": option.id.as_str(),
+                            "label": option.label.as_str(),
+                            "icon_name": option.icon_name.as_str(),
+                            "enabled": option.enabled,
+                            "visible": option.visible,
+                        }
+                    })
+                    .collect();
+                vdict! {
+                    "type": "radio_group",
+                    "id": id.as_str(),
+                    "selected": *selected as i64,
+                    "options": options,
+                    "emit_on_reselect": *emit_on_reselect,
+                    "priority": *priority,
+                }
+            }
+            MenuItemData::SubMenu {
+                label,
+                icon_name,
+                enabled,
+                visible,
+                submenu,
+                priority,
+            } => {
+                let submenu: Array<Dictionary> =
+                    submenu.iter().map(Self::menu_item_to_dict).collect();
+                vdict! {
+                    "type": "submenu",
+                    "label": label.as_str(),
+                    "icon_name": icon_name.as_str(),
+                    "enabled": *enabled,
+                    "visible": *visible,
+                    "submenu": submenu,
+                    "priority": *priority,
+                }
+            }
+            MenuItemData::Header { label } => vdict! {
+                "type": "header",
+                "label": label.as_str(),
+            },
+            MenuItemData::Separator => vdict! { "type": "separator" },
+        }
+    }
+
+    /// Applies the stored `tooltip` property, as returned by `get_tooltip()`.
+    fn load_tooltip_property(&mut self, dict: &Dictionary) {
+        let (title, subtitle, icon_name, _) = match Self::tooltip_from_dict(dict) {
+            Ok(fields) => fields,
+            Err(e) => {
+                godot_error!("Invalid TrayIcon tooltip: {}", e);
+                return;
+            }
+        };
+
+        let mut state = self.state.lock().unwrap();
+        if Self::dict_or(dict, "rich", false).unwrap_or(false) {
+            state.set_tooltip_rich(&title.to_string(), &subtitle.to_string());
+            state.tooltip_icon_name = icon_name.to_string();
+        } else {
+            state.set_tooltip(
+                title.to_string(),
+                subtitle.to_string(),
+                icon_name.to_string(),
+            );
+        }
+    }
+
+    /// Applies the stored `menu` property, replacing the whole menu.
+    fn load_menu_property(&mut self, items: &VariantArray) {
+        let mut menu = Vec::with_capacity(items.len());
+        for item in items.iter_shared() {
+            let item = item
+                .try_to::<Dictionary>()
+                .map_err(|_| "items must be Dictionaries".to_string())
+                .and_then(|dict| Self::menu_item_from_dict(&dict));
+            match item {
+                Ok(item) => menu.push(item),
+                Err(e) => {
+                    godot_error!("Invalid TrayIcon menu: {}", e);
+                    return;
+                }
+            }
+        }
+
+        self.state.lock().unwrap().rebuild_from_items(menu);
+    }
+
+    /// Parses the title, subtitle, icon name, and icon texture of a tooltip Dictionary.
+    fn tooltip_from_dict(dict: &Dictionary) -> Result<TooltipFields, String> {
+        Ok((
+            Self::dict_or(dict, "title", GString::new())?,
+            Self::dict_or(dict, "subtitle", GString::new())?,
+            Self::dict_or(dict, "icon_name", GString::new())?,
+            Self::dict_or(dict, "icon_texture", None)?,
+        ))
+    }
+
+    /// Reads a required Dictionary value, failing if it is missing or has the wrong type.
+    fn dict_required<T: FromGodot>(dict: &Dictionary, key: &str) -> Result<T, String> {
+        let value = dict
+            .get(key)
+            .ok_or_else(|| format!("missing required key '{}'", key))?;
+        value
+            .try_to::<T>()
+            .map_err(|_| format!("key '{}' has the wrong type", key))
+    }
+
+    /// Reads an optional Dictionary value, using `default` if it is missing.
+    fn dict_or<T: FromGodot>(dict: &Dictionary, key: &str, default: T) -> Result<T, String> {
+        match dict.get(key) {
+            Some(value) => value
+                .try_to::<T>()
+                .map_err(|_| format!("key '{}' has the wrong type", key)),
+            None => Ok(default),
+        }
+    }
+
+    /// Returns `true` if the image format stores floating-point (linear) color data.
+    fn is_float_format(format: Format) -> bool {
+        matches!(
+            format,
+            Format::RF
+                | Format::RGF
+                | Format::RGBF
+                | Format::RGBAF
+                | Format::RH
+                | Format::RGH
+                | Format::RGBH
+                | Format::RGBAH
+                | Format::RGBE9995
+        )
+    }
+
+    /// Presses the source PopupMenu item of a clicked standard item, if any.
+    fn forward_activation_to_popup(&mut self, emitted_id: &str) {
+        let id = {
+            let state = self.state.lock().unwrap();
+            match state
+                .find_item_by_emitted_id(emitted_id)
+                .and_then(MenuItemData::id)
+            {
+                Some(id) => id.to_string(),
+                None => return,
+            }
+        };
+        self.forward_to_popup(&id);
+    }
+
+    /// Presses the source PopupMenu item converted into the menu item with this ID, if
+    /// any, so its `id_pressed` and `index_pressed` handlers run.
+    fn forward_to_popup(&mut self, id: &str) {
+        if let Some(item) = self.popup_items.get(id).cloned() {
+            // Allow handlers to call back into this node while they run.
+            let _guard = self.base_mut();
+            item.press();
+        }
+    }
+
+    /// Calls the icon generator and applies the returned image, unless it is unchanged.
+    fn run_icon_generator(&mut self, generator: &Callable) {
+        let result = {
+            // Allow the generator to call back into this node while it runs.
+            let _guard = self.base_mut();
+            generator.call(&[])
+        };
+        let Ok(image) = result.try_to::<Gd<Image>>() else {
+            godot_warn!("Icon generator did not return an Image");
+            return;
+        };
+
+        let hash = Self::image_hash(&image);
+        if self.last_generated_image_hash == Some(hash) {
+            return;
+        }
+
+        let Some(icon) = self.image_to_icon(&image) else {
+            return;
+        };
+        self.last_generated_image_hash = Some(hash);
+        self.state.lock().unwrap().set_icon(vec![icon]);
+        self.refresh();
+    }
+
+    /// Calls an enabled-state provider, treating invalid return values as enabled.
+    fn evaluate_enabled_provider(provider: &Callable) -> bool {
+        provider.call(&[]).try_to::<bool>().unwrap_or_else(|_| {
+            godot_warn!("Enabled provider did not return a bool");
+            true
+        })
+    }
+
+    /// Stores the error code of a result for `get_last_error()`, returning whether it is `Ok`.
+    fn record_result(&mut self, result: error::Result<()>) -> bool {
+        self.last_error = match result {
+            Ok(()) => Error::OK,
+            Err(e) => e.code(),
+        };
+        self.last_error == Error::OK
+    }
+
+    /// Appends an event to the history, if enabled, dropping the oldest entry when full.
+    fn record_event(&mut self, event: &TrayEvent) {
+        if self.history_size == 0 {
+            return;
+        }
+
+        let value = match event {
+            TrayEvent::MenuActivated(id)
+            | TrayEvent::CheckmarkToggled(id, _)
+            | TrayEvent::RadioSelected(id, ..) => Variant::from(id.as_str()),
+            TrayEvent::Activated(x, _) => Variant::from(*x as i64),
+            TrayEvent::Scrolled(delta, _) => Variant::from(*delta as i64),
+            TrayEvent::MenuAboutToShow => Variant::nil(),
+        };
+
+        if self.event_history.len() == self.history_size {
+            self.event_history.pop_front();
+        }
+        self.event_history
+            .push_back((event.name().to_string(), value));
+    }
+
+    /// Returns the per-item callback of an activated item and whether it suppresses the
+    /// global `menu_activated` signal.
+    fn item_activation_handling(&self, emitted_id: &str) -> (Option<Callable>, bool) {
+        let state = self.state.lock().unwrap();
+        match state.find_item_by_emitted_id(emitted_id) {
+            Some(MenuItemData::Standard {
+                id,
+                suppress_global,
+                ..
+            }) => (self.item_callbacks.get(id).cloned(), *suppress_global),
+            _ => (None, false),
+        }
+    }
+
+    /// Emits `checkmark_state_changed` or `radio_state_changed` for a toggle event.
+    fn emit_state_changed(&mut self, event: &TrayEvent, source: i64) {
+        match event {
+            TrayEvent::CheckmarkToggled(id, checked) => {
+                let id = self.id_variant(id);
+                self.base_mut().emit_signal(
+                    "checkmark_state_changed",
+                    &[id, Variant::from(*checked), Variant::from(source)],
+                );
+            }
+            TrayEvent::RadioSelected(group_id, index, option_id) => {
+                let group_id = self.id_variant(group_id);
+                let option_id = self.id_variant(option_id);
+                self.base_mut().emit_signal(
+                    "radio_state_changed",
+                    &[
+                        group_id,
+                        Variant::from(*index as i64),
+                        option_id,
+                        Variant::from(source),
+                    ],
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Converts a menu item ID into the Variant passed to signals, a StringName with
+    /// `set_string_name_ids(true)` and a String otherwise.
+    ///
+    /// The StringName of each ID is created once and cached, so later events for the
+    /// same item only copy a reference instead of building and interning the name again.
+    fn id_variant(&mut self, id: &str) -> Variant {
+        if !self.string_name_ids {
+            return GString::from(id).to_variant();
+        }
+        if let Some(name) = self.id_names.get(id) {
+            return name.to_variant();
+        }
+        let name = StringName::from(id);
+        self.id_names.insert(id.to_string(), name.clone());
+        name.to_variant()
+    }
+
+    /// Selects the option at `index` of a radio group, searching submenus recursively.
+    ///
+    /// Returns `None` if the group or option was not found, reporting the failure of
+    /// `op`, otherwise the ID of the option if the selection changed.
+    fn select_radio_option(
+        &mut self,
+        op: &str,
+        group_id: &str,
+        index: i64,
+    ) -> Option<Option<String>> {
+        let changed = usize::try_from(index).ok().and_then(|index| {
+            let mut state = self.state.lock().unwrap();
+            let previous = match state.find_item(group_id) {
+                Some(MenuItemData::RadioGroup { selected, .. }) => Some(*selected),
+                _ => None,
+            };
+            state
+                .find_and_select_radio(group_id, index)
+                .map(|option_id| (previous != Some(index)).then_some(option_id))
+        });
+        if changed.is_some() {
+            return changed;
+        }
+
+        self.report_failure(
+            op,
+            format!(
+                "no radio group '{}' with an option at index {}",
+                group_id, index
+            ),
+        );
+        None
+    }
+
+    /// Appends an item to a top-level submenu, reporting the failure of `op` if the
+    /// submenu does not exist.
+    fn push_to_submenu(&mut self, op: &str, submenu_label: &GString, item: MenuItemData) -> bool {
+        let label = submenu_label.to_string();
+        if let Some(submenu) =
+            TrayState::find_submenu_mut(&mut self.state.lock().unwrap().menu, &label)
+        {
+            submenu.push(item);
+            return true;
+        }
+
+        self.report_failure(op, format!("no submenu labeled '{}'", label));
+        false
+    }
+
+    /// Emits `operation_failed` if debug mode is enabled.
+    fn report_failure(&mut self, op: &str, detail: String) {
+        if self.debug_mode {
+            self.base_mut()
+                .emit_signal("operation_failed", &[op.to_variant(), detail.to_variant()]);
+        }
+    }
+
+    /// Returns the dedicated signal of an activated media control, if it is one.
+    fn media_signal(&self, emitted_id: &str) -> Option<&'static str> {
+        let state = self.state.lock().unwrap();
+        state
+            .find_item_by_emitted_id(emitted_id)
+            .and_then(MenuItemData::id)
+            .and_then(media::signal)
+    }
+
+    /// Resolves the text direction setting into the tray state.
+    fn apply_text_direction(&mut self) {
+        let right_to_left = match self.text_direction {
+            Self::TEXT_DIRECTION_RTL => true,
+            Self::TEXT_DIRECTION_LTR => false,
+            _ => {
+                let locale = TranslationServer::singleton().get_locale();
+                TextServerManager::singleton()
+                    .get_primary_interface()
+                    .is_some_and(|text_server| text_server.is_locale_right_to_left(&locale))
+            }
+        };
+
+        self.state.lock().unwrap().text_direction = if right_to_left {
+            ksni::TextDirection::RightToLeft
+        } else {
+            ksni::TextDirection::LeftToRight
+        };
+    }
+
+    /// Opens the fallback PopupMenu at a screen position, replacing any open one.
+    fn show_fallback_menu(&mut self, screen_position: Vector2i) {
+        if let Some(mut popup) = self.fallback_popup.take() {
+            popup.queue_free();
+        }
+
+        let screen_position = if screen_position == Vector2i::ZERO {
+            DisplayServer::singleton().mouse_get_position()
+        } else {
+            screen_position
+        };
+
+        let mut popup = PopupMenu::new_alloc();
+        let on_id_pressed =
+            Callable::from_object_method(&self.to_gd(), "_on_fallback_menu_id_pressed");
+        let mut actions = Vec::new();
+        {
+            let state = self.state.lock().unwrap();
+            let items: Vec<&MenuItemData> = state.ordered_menu_items().collect();
+            fallback_menu::build(&state, &items, &mut popup, &on_id_pressed, &mut actions, "");
+            self.fallback_generation = state.menu_generation;
+        }
+        self.fallback_actions = actions;
+
+        self.base_mut().add_child(&popup);
+        // Embedded popups are positioned relative to the window that embeds them.
+        let position = match self.base().get_window() {
+            Some(window) if window.is_embedding_subwindows() => {
+                screen_position - window.get_position()
+            }
+            _ => screen_position,
+        };
+        popup.set_position(position);
+        popup.popup();
+        self.fallback_popup = Some(popup);
+    }
+
+    /// Emits `menu_closed` if the menu is open.
+    fn close_menu(&mut self) {
+        if self.menu_open {
+            self.menu_open = false;
+            self.menu_opened_at = None;
+            self.base_mut().emit_signal("menu_closed", &[]);
+            TrayStats::increment(&self.stats.events_emitted);
+        }
+    }
+
+    /// Re-evaluates the enabled-state providers of dynamic menu items.
+    ///
+    /// Returns `true` if any item's enabled state changed.
+    fn update_dynamic_items(&mut self) -> bool {
+        if self.enabled_providers.is_empty() {
+            return false;
+        }
+
+        let providers: Vec<(String, Callable)> = self
+            .enabled_providers
+            .iter()
+            .map(|(id, provider)| (id.clone(), provider.clone()))
+            .collect();
+        let results: Vec<(String, bool)> = {
+            // Allow providers to call back into this node while they run.
+            let _guard = self.base_mut();
+            providers
+                .into_iter()
+                .map(|(id, provider)| (id, Self::evaluate_enabled_provider(&provider)))
+                .collect()
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let mut changed = false;
+        for (id, new_enabled) in results {
+            if let Some(MenuItemData::Standard { enabled, .. }) = state.find_item_mut(&id)
+                && *enabled != new_enabled
+            {
+                *enabled = new_enabled;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Runs the providers of submenus that are not loaded yet, returning whether any
+    /// submenu changed.
+    fn load_submenus(&mut self) -> bool {
+        let pending: Vec<(String, Callable)> = self
+            .submenu_providers
+            .iter()
+            .filter(|(label, _)| !self.loaded_submenus.contains(*label))
+            .map(|(label, provider)| (label.clone(), provider.clone()))
+            .collect();
+        if pending.is_empty() {
+            return false;
+        }
+
+        let results: Vec<(String, Variant)> = {
+            // Allow providers to call back into this node while they run.
+            let _guard = self.base_mut();
+            pending
+                .into_iter()
+                .map(|(label, provider)| {
+                    let result = provider.call(&[]);
+                    (label, result)
+                })
+                .collect()
+        };
+
+        let mut changed = false;
+        for (label, result) in results {
+            let items = result
+                .try_to::<VariantArray>()
+                .map_err(|_| "the provider did not return an Array".to_string())
+                .and_then(|array| {
+                    array
+                        .iter_shared()
+                        .map(|item| {
+                            let dict = item
+                                .try_to::<Dictionary>()
+                                .map_err(|_| "the Array must only contain Dictionaries")?;
+                            Self::menu_item_from_dict(&dict)
+                        })
+                        .collect::<Result<Vec<_>, String>>()
+                });
+            let items = match items {
+                Ok(items) => items,
+                Err(e) => {
+                    godot_warn!("Cannot load submenu '{}': {}", label, e);
+                    continue;
+                }
+            };
+
+            if let Some(submenu) = self.state.lock().unwrap().find_submenu_items_mut(&label) {
+                *submenu = items;
+                changed = true;
+            }
+            self.loaded_submenus.insert(label);
+        }
+        changed
+    }
+
+    /// Drops the Godot-side bindings of the menu items: cached StringName IDs, enabled and
+    /// submenu providers, item callbacks, and the PopupMenu the menu was converted from.
+    fn forget_menu_bindings(&mut self) {
+        self.id_names.clear();
+        self.enabled_providers.clear();
+        self.submenu_providers.clear();
+        self.loaded_submenus.clear();
+        self.item_callbacks.clear();
+        self.source_popup = None;
+        self.popup_items.clear();
+    }
+
+    /// Returns the placeholder shown in a submenu until its provider has run, translated
+    /// to the current locale.
+    fn submenu_placeholder(&self) -> MenuItemData {
+        MenuItemData::Header {
+            label: self.base().tr("Loading...").to_string(),
+        }
+    }
+
+    /// Returns the project icon path from the project settings, or an empty string if unset.
+    fn project_icon_path() -> GString {
+        ProjectSettings::singleton()
+            .get_setting(PROJECT_ICON_SETTING)
+            .try_to::<GString>()
+            .unwrap_or_default()
+    }
+
+    /// Returns the category with the given StatusNotifierItem name, or `None` if unknown.
+    fn category_from_name(name: &str) -> Option<ksni::Category> {
+        match name {
+            "ApplicationStatus" => Some(ksni::Category::ApplicationStatus),
+            "Communications" => Some(ksni::Category::Communications),
+            "SystemServices" => Some(ksni::Category::SystemServices),
+            "Hardware" => Some(ksni::Category::Hardware),
+            _ => None,
+        }
+    }
+
+    /// Sets the icon from the `godot_ksni/tray/default_icon_path` project setting, if
+    /// it is set and no icon was set on this tray.
+    #[cfg(target_os = "linux")]
+    fn apply_default_icon(&mut self) {
+        let path = project_settings::get(project_settings::DEFAULT_ICON_PATH, GString::new());
+        let icon_set = self.state.lock().unwrap().icon_set;
+        if !path.is_empty() && !icon_set {
+            self.set_icon_from_path(path);
+        }
+    }
+
+    /// Returns the project name from the project settings, or an empty string if unset.
+    fn project_name() -> GString {
+        ProjectSettings::singleton()
+            .get_setting(PROJECT_NAME_SETTING)
+            .try_to::<GString>()
+            .unwrap_or_default()
+    }
+
+    /// Applies the current window icon to the tray icon and refreshes the tray.
+    ///
+    /// Uses the image set with `set_window_icon()`, falling back to the project icon.
+    fn apply_window_icon(&mut self) -> bool {
+        let applied = if let Some(image) = self.window_icon.clone() {
+            self.set_icon_from_image(image)
+        } else {
+            let path = Self::project_icon_path();
+            self.synced_icon_path = path.clone();
+            !path.is_empty() && self.set_icon_from_path(path)
+        };
+
+        if applied {
+            self.refresh();
+        }
+        applied
+    }
+
+    /// Returns `true` if an activation of `id` should be dropped by the debounce window.
+    ///
+    /// Records the activation time when the event is let through.
+    fn is_activation_debounced(&mut self, id: &str) -> bool {
+        if self.activation_debounce.is_zero() {
+            return false;
+        }
+
+        let now = Instant::now();
+        if let Some(last) = self.last_activations.get(id)
+            && now.duration_since(*last) < self.activation_debounce
+        {
+            return true;
+        }
+        self.last_activations.insert(id.to_string(), now);
+        false
+    }
+}
+
+/// Rust-only API for embedders using godot-ksni as a dependency.
+///
+/// These methods take typed Rust values instead of Godot strings and refresh the
+/// tray automatically if it has been spawned.
+impl TrayIcon {
+    /// Spawns the tray service from the current state.
+    ///
+    /// This is what `spawn_tray()` calls; it reports the failure instead of logging it.
+    pub fn spawn(&mut self) -> error::Result<()> {
+        if self.handle.is_some() {
+            return Err(Error::AlreadySpawned);
+        }
+        self.spawn_service()
+    }
+
+    /// Shuts down the spawned tray, removing it from the host.
+    ///
+    /// The node keeps its state, so a later `spawn()` shows the tray again. Returns
+    /// `Error::NotSpawned` if no tray is spawned.
+    pub fn shutdown(&mut self) -> error::Result<()> {
+        if self.handle.is_none() {
+            return Err(Error::NotSpawned);
+        }
+        self.shutdown_tray();
+        Ok(())
+    }
+
+    /// Registers the tray with the StatusNotifierWatcher and starts serving it, as done
+    /// by `spawn()` once the tray is known not to be spawned.
+    #[cfg(target_os = "linux")]
+    fn spawn_service(&mut self) -> error::Result<()> {
+        use ksni::blocking::TrayMethods;
+        use std::sync::mpsc::channel;
+
+        // The watcher check and the registration share the spawn timeout.
+        let started = Instant::now();
+        let present = if self.spawn_timeout.is_zero() {
+            watcher::is_present()
+        } else {
+            watcher::is_present_within(self.spawn_timeout)?
+        };
+        if present == Some(false) {
+            return Err(Error::NoWatcher);
+        }
+
+        self.apply_default_icon();
+
+        let (tx, rx) = channel();
+        self.event_receiver = Some(rx);
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.event_sender = Some(tx);
+            self.last_refresh_hash = Some(state.content_hash());
+        }
+
+        self.apply_text_direction();
+
+        let state_arc = self.state.clone();
+        let tray = KsniTray { state: state_arc };
+
+        let result = if self.spawn_timeout.is_zero() {
+            tray.spawn().map_err(Error::from)
+        } else {
+            tray.spawn_with_timeout(self.spawn_timeout.saturating_sub(started.elapsed()))
+        };
+        let handle = match result {
+            Ok(handle) => handle,
+            Err(e) => {
+                // Leave nothing behind for an abandoned registration, so spawning can be retried.
+                self.state.lock().unwrap().event_sender = None;
+                self.event_receiver = None;
+                return Err(e);
+            }
+        };
+
+        self.handle = Some(handle);
+        if self.keep_alive {
+            self.register_kept_alive();
+        }
+        Ok(())
+    }
+
+    /// Stands in for spawning on platforms without StatusNotifierItem trays.
+    #[cfg(not(target_os = "linux"))]
+    fn spawn_service(&mut self) -> error::Result<()> {
+        Err(Error::Unsupported)
+    }
+
+    /// Returns a clone of the ksni handle of the spawned tray, or `None` if the tray is
+    /// not spawned.
+    ///
+    /// The handle allows driving updates directly, e.g. `handle.update(|tray| ...)` to
+    /// change the state and push it to the host in one step. The node does not track
+    /// changes made this way: a later `refresh()` may send the same state again, and
+    /// after `handle.shutdown()` the node still considers the tray spawned until it is
+    /// respawned with `respawn_tray()` or freed.
+    pub fn handle(&self) -> Option<Handle<KsniTray>> {
+        self.handle.clone()
+    }
+
+    /// Replaces the whole menu with the given items.
+    pub fn set_menu(&mut self, menu: Vec<MenuItemData>) {
+        self.state.lock().unwrap().set_menu(menu);
+        self.refresh();
+    }
+
+    /// Sets the tray icon from ARGB32 pixmaps.
+    pub fn set_icon(&mut self, icon_pixmap: Vec<ksni::Icon>) {
+        self.state.lock().unwrap().set_icon(icon_pixmap);
+        self.refresh();
+    }
+
+    /// Sets the tooltip title, subtitle, and icon name.
+    pub fn set_tooltip_data(
+        &mut self,
+        title: impl Into<String>,
+        subtitle: impl Into<String>,
+        icon_name: impl Into<String>,
+    ) {
+        self.state
+            .lock()
+            .unwrap()
+            .set_tooltip(title, subtitle, icon_name);
+        self.refresh();
     }
 }
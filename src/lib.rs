@@ -115,24 +115,60 @@
 //!     if id == "quit":
 //!         get_tree().quit()
 //! ```
+//!
+//! ## The `Tray` Singleton
+//!
+//! When the `godot_ksni/tray/register_singleton` project setting is enabled, the
+//! standalone extension also registers a default `TrayIcon` as the `Tray` engine
+//! singleton, so small projects do not need to add a node. It offers the same methods
+//! and signals, and joins the scene tree when it is spawned:
+//!
+//! ```gdscript
+//! func _ready():
+//!     Tray.set_icon_from_path("res://icon.svg")
+//!     Tray.add_menu_item("quit", "Quit", "application-exit", true, true)
+//!     Tray.menu_activated.connect(_on_menu_activated)
+//!     Tray.spawn_tray()
+//! ```
+//!
+//! Extensions embedding godot-ksni as a dependency do not register it; see
+//! [`godot::singleton`] to opt in.
 
 // Module declarations
+pub mod error;
 pub mod godot;
 pub mod menu;
 pub mod tray;
 
 // Public re-exports
+pub use error::{Error, Result};
 pub use godot::TrayIcon;
-pub use menu::{MenuItemData, RadioItemData};
+pub use menu::{MenuItemData, MenuItemDescription, RadioItemData};
 pub use tray::{KsniTray, TrayEvent, TrayState};
 
 // Conditional GDExtension entry point
 #[cfg(feature = "gdextension")]
 mod gdextension {
+    use crate::godot::{project_settings, singleton};
     use godot::prelude::*;
 
     struct GodotKsniExtension;
 
     #[gdextension]
-    unsafe impl ExtensionLibrary for GodotKsniExtension {}
+    unsafe impl ExtensionLibrary for GodotKsniExtension {
+        fn on_level_init(level: InitLevel) {
+            if level == InitLevel::Scene {
+                project_settings::register();
+                if project_settings::get(project_settings::REGISTER_SINGLETON, false) {
+                    singleton::register();
+                }
+            }
+        }
+
+        fn on_level_deinit(level: InitLevel) {
+            if level == InitLevel::Scene {
+                singleton::unregister();
+            }
+        }
+    }
 }
@@ -7,7 +7,12 @@
 ///
 /// This enum defines all the possible menu item types supported by the tray icon,
 /// including standard items, checkmarks, radio groups, submenus, and separators.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
 pub enum MenuItemData {
     /// A standard clickable menu item.
     Standard {
@@ -16,11 +21,20 @@ pub enum MenuItemData {
         /// Display text for the menu item.
         label: String,
         /// Icon name from the freedesktop icon theme.
+        #[cfg_attr(feature = "serde", serde(default))]
         icon_name: String,
         /// Whether the item can be clicked.
+        #[cfg_attr(feature = "serde", serde(default = "default_true"))]
         enabled: bool,
         /// Whether the item is visible in the menu.
+        #[cfg_attr(feature = "serde", serde(default = "default_true"))]
         visible: bool,
+        /// Display priority; higher values sort first in `sort_menu_by_priority()`.
+        #[cfg_attr(feature = "serde", serde(default))]
+        priority: i32,
+        /// Whether activating the item skips the global `menu_activated` signal.
+        #[cfg_attr(feature = "serde", serde(default))]
+        suppress_global: bool,
     },
     /// A menu item with a checkmark that can be toggled on/off.
     Checkmark {
@@ -29,53 +43,172 @@ pub enum MenuItemData {
         /// Display text for the checkmark item.
         label: String,
         /// Icon name from the freedesktop icon theme.
+        #[cfg_attr(feature = "serde", serde(default))]
         icon_name: String,
         /// Whether the item can be clicked.
+        #[cfg_attr(feature = "serde", serde(default = "default_true"))]
         enabled: bool,
         /// Whether the item is visible in the menu.
+        #[cfg_attr(feature = "serde", serde(default = "default_true"))]
         visible: bool,
         /// Current checked state.
+        #[cfg_attr(feature = "serde", serde(default))]
         checked: bool,
+        /// Display priority; higher values sort first in `sort_menu_by_priority()`.
+        #[cfg_attr(feature = "serde", serde(default))]
+        priority: i32,
     },
     /// A group of mutually exclusive radio button options.
     RadioGroup {
         /// Unique identifier for the radio group.
         id: String,
         /// Index of the currently selected option.
+        #[cfg_attr(feature = "serde", serde(default))]
         selected: usize,
         /// List of radio button options in this group.
+        #[cfg_attr(feature = "serde", serde(default))]
         options: Vec<RadioItemData>,
+        /// Whether clicking the already selected option emits a selection event.
+        #[cfg_attr(feature = "serde", serde(default = "default_true"))]
+        emit_on_reselect: bool,
+        /// Display priority; higher values sort first in `sort_menu_by_priority()`.
+        #[cfg_attr(feature = "serde", serde(default))]
+        priority: i32,
     },
     /// A submenu that contains other menu items.
     SubMenu {
         /// Display text for the submenu.
         label: String,
         /// Icon name from the freedesktop icon theme.
+        #[cfg_attr(feature = "serde", serde(default))]
         icon_name: String,
         /// Whether the submenu can be opened.
+        #[cfg_attr(feature = "serde", serde(default = "default_true"))]
         enabled: bool,
         /// Whether the submenu is visible in the menu.
+        #[cfg_attr(feature = "serde", serde(default = "default_true"))]
         visible: bool,
         /// List of menu items contained in this submenu.
+        #[cfg_attr(feature = "serde", serde(default))]
         submenu: Vec<MenuItemData>,
+        /// Display priority; higher values sort first in `sort_menu_by_priority()`.
+        #[cfg_attr(feature = "serde", serde(default))]
+        priority: i32,
+    },
+    /// A disabled label introducing a section of the menu.
+    Header {
+        /// Display text for the header.
+        label: String,
     },
     /// A visual separator line in the menu.
     Separator,
 }
 
+impl MenuItemData {
+    /// Returns the identifier of this item, if it has one.
+    ///
+    /// Submenus, headers, and separators have no identifier.
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            MenuItemData::Standard { id, .. }
+            | MenuItemData::Checkmark { id, .. }
+            | MenuItemData::RadioGroup { id, .. } => Some(id),
+            MenuItemData::SubMenu { .. }
+            | MenuItemData::Header { .. }
+            | MenuItemData::Separator => None,
+        }
+    }
+
+    /// Returns the display label of this item, if it has one.
+    ///
+    /// Radio groups and separators have no label.
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            MenuItemData::Standard { label, .. }
+            | MenuItemData::Checkmark { label, .. }
+            | MenuItemData::SubMenu { label, .. }
+            | MenuItemData::Header { label } => Some(label),
+            MenuItemData::RadioGroup { .. } | MenuItemData::Separator => None,
+        }
+    }
+
+    /// Returns the display priority of this item.
+    ///
+    /// Headers and separators always have priority 0.
+    pub fn priority(&self) -> i32 {
+        match self {
+            MenuItemData::Standard { priority, .. }
+            | MenuItemData::Checkmark { priority, .. }
+            | MenuItemData::RadioGroup { priority, .. }
+            | MenuItemData::SubMenu { priority, .. } => *priority,
+            MenuItemData::Header { .. } | MenuItemData::Separator => 0,
+        }
+    }
+
+    /// Returns the name of this item's type.
+    ///
+    /// One of `"standard"`, `"checkmark"`, `"radio_group"`, `"submenu"`, `"header"`, or
+    /// `"separator"`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            MenuItemData::Standard { .. } => "standard",
+            MenuItemData::Checkmark { .. } => "checkmark",
+            MenuItemData::RadioGroup { .. } => "radio_group",
+            MenuItemData::SubMenu { .. } => "submenu",
+            MenuItemData::Header { .. } => "header",
+            MenuItemData::Separator => "separator",
+        }
+    }
+}
+
 /// Data for a single radio button option within a radio group.
 ///
 /// Each radio option has its own identifier, label, and visual properties.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct RadioItemData {
     /// Unique identifier for this radio option.
     pub id: String,
     /// Display text for this radio option.
     pub label: String,
     /// Icon name from the freedesktop icon theme.
+    #[cfg_attr(feature = "serde", serde(default))]
     pub icon_name: String,
     /// Whether this option can be selected.
+    #[cfg_attr(feature = "serde", serde(default = "default_true"))]
     pub enabled: bool,
     /// Whether this option is visible in the menu.
+    #[cfg_attr(feature = "serde", serde(default = "default_true"))]
+    pub visible: bool,
+}
+
+/// Default value for `enabled` and `visible` fields when deserializing.
+#[cfg(feature = "serde")]
+fn default_true() -> bool {
+    true
+}
+
+/// A plain description of a built menu item.
+///
+/// Mirrors the structure produced by `TrayState::build_menu_items()`, whose ksni items
+/// hold opaque closures, so the menu-building logic can be inspected and compared.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MenuItemDescription {
+    /// Item type name, as returned by `MenuItemData::type_name()`, or `"radio_option"`.
+    pub item_type: &'static str,
+    /// Identifier of the item, if it has one.
+    pub id: Option<String>,
+    /// Display text of the item (empty for radio groups and separators).
+    pub label: String,
+    /// Whether the item can be interacted with.
+    pub enabled: bool,
+    /// Whether the item is visible in the menu.
     pub visible: bool,
+    /// Checked state for checkmark items.
+    pub checked: Option<bool>,
+    /// Selected option index for radio groups.
+    pub selected: Option<usize>,
+    /// Submenu items, or options of a radio group.
+    pub children: Vec<MenuItemDescription>,
 }
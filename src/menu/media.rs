@@ -0,0 +1,117 @@
+//! Media player transport controls.
+//!
+//! This module builds the play/pause, previous, next, and stop items added by
+//! `TrayIcon::add_media_controls()`, using the freedesktop icon names for media
+//! playback. The items have fixed IDs, so they can be found and removed as a unit
+//! among custom items.
+
+use crate::menu::item::MenuItemData;
+
+/// Flag selecting the play/pause item.
+pub const PLAY_PAUSE: i64 = 1;
+/// Flag selecting the previous track item.
+pub const PREVIOUS: i64 = 2;
+/// Flag selecting the next track item.
+pub const NEXT: i64 = 4;
+/// Flag selecting the stop item.
+pub const STOP: i64 = 8;
+/// All media control flags.
+pub const ALL: i64 = PLAY_PAUSE | PREVIOUS | NEXT | STOP;
+
+/// ID of the play/pause item.
+pub const PLAY_PAUSE_ID: &str = "media_play_pause";
+
+/// A media control item.
+struct Control {
+    flag: i64,
+    id: &'static str,
+    label: &'static str,
+    icon_name: &'static str,
+    signal: &'static str,
+}
+
+/// The media controls, in menu order. The play/pause item is shown in its paused state.
+const CONTROLS: [Control; 4] = [
+    Control {
+        flag: PLAY_PAUSE,
+        id: PLAY_PAUSE_ID,
+        label: "Play",
+        icon_name: "media-playback-start",
+        signal: "play_pause_pressed",
+    },
+    Control {
+        flag: PREVIOUS,
+        id: "media_previous",
+        label: "Previous",
+        icon_name: "media-skip-backward",
+        signal: "previous_pressed",
+    },
+    Control {
+        flag: NEXT,
+        id: "media_next",
+        label: "Next",
+        icon_name: "media-skip-forward",
+        signal: "next_pressed",
+    },
+    Control {
+        flag: STOP,
+        id: "media_stop",
+        label: "Stop",
+        icon_name: "media-playback-stop",
+        signal: "stop_pressed",
+    },
+];
+
+/// Builds the media control items selected by `flags`, in menu order.
+///
+/// The English labels are passed through `tr`, e.g. Godot's `Object::tr()`, so they can
+/// be translated.
+pub fn items(flags: i64, tr: impl Fn(&str) -> String) -> Vec<MenuItemData> {
+    CONTROLS
+        .iter()
+        .filter(|control| flags & control.flag != 0)
+        .map(|control| MenuItemData::Standard {
+            id: control.id.to_string(),
+            label: tr(control.label),
+            icon_name: control.icon_name.to_string(),
+            enabled: true,
+            visible: true,
+            priority: 0,
+            suppress_global: false,
+        })
+        .collect()
+}
+
+/// Returns `true` if the item is one of the media controls.
+pub fn is_control(item: &MenuItemData) -> bool {
+    item.id()
+        .is_some_and(|id| CONTROLS.iter().any(|control| control.id == id))
+}
+
+/// Returns the name of the signal emitted when the media control with the given ID is
+/// clicked, or `None` if the ID is not a media control.
+pub fn signal(id: &str) -> Option<&'static str> {
+    CONTROLS
+        .iter()
+        .find(|control| control.id == id)
+        .map(|control| control.signal)
+}
+
+/// Updates the label and icon of the play/pause item for the playback state.
+///
+/// While playing, the item offers to pause. The label is passed through `tr` as in
+/// [`items`]. Does nothing if `item` is not a standard item.
+pub fn set_playing(item: &mut MenuItemData, playing: bool, tr: impl Fn(&str) -> String) {
+    if let MenuItemData::Standard {
+        label, icon_name, ..
+    } = item
+    {
+        let (new_label, new_icon_name) = if playing {
+            ("Pause", "media-playback-pause")
+        } else {
+            ("Play", "media-playback-start")
+        };
+        *label = tr(new_label);
+        *icon_name = new_icon_name.to_string();
+    }
+}
@@ -4,5 +4,6 @@
 //! including standard items, checkmarks, radio groups, submenus, and separators.
 
 pub mod item;
+pub mod media;
 
-pub use item::{MenuItemData, RadioItemData};
+pub use item::{MenuItemData, MenuItemDescription, RadioItemData};
@@ -3,10 +3,13 @@
 //! These events are used internally to communicate between the tray icon
 //! and the Godot node, and are converted to Godot signals.
 
+use crate::tray::scroll::ScrollOrientation;
+
 /// Internal events emitted by the tray icon.
 ///
 /// These events are used internally to communicate between the tray icon
 /// and the Godot node, and are converted to Godot signals.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum TrayEvent {
     /// A standard menu item was activated.
     MenuActivated(String),
@@ -14,4 +17,24 @@ pub enum TrayEvent {
     CheckmarkToggled(String, bool),
     /// A radio button option was selected.
     RadioSelected(String, usize, String),
+    /// The tray icon itself was activated (usually a left click) at the given screen position.
+    Activated(i32, i32),
+    /// The user scrolled over the tray icon by the given delta.
+    Scrolled(i32, ScrollOrientation),
+    /// The root menu is about to be shown.
+    MenuAboutToShow,
+}
+
+impl TrayEvent {
+    /// Returns the snake_case name of this event type.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TrayEvent::MenuActivated(..) => "menu_activated",
+            TrayEvent::CheckmarkToggled(..) => "checkmark_toggled",
+            TrayEvent::RadioSelected(..) => "radio_selected",
+            TrayEvent::Activated(..) => "activated",
+            TrayEvent::Scrolled(..) => "scrolled",
+            TrayEvent::MenuAboutToShow => "menu_about_to_show",
+        }
+    }
 }
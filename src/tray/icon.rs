@@ -0,0 +1,354 @@
+//! Icon pixel conversion.
+//!
+//! This module contains the pixel format conversions needed to turn image data into
+//! the ARGB32 pixmaps expected by the StatusNotifierItem specification.
+
+use crate::error::{Error, Result};
+
+/// Icon size, in pixels, assumed when the host does not say which size it wants.
+pub const DEFAULT_ICON_SIZE: i32 = 22;
+
+/// Largest number of pixels accepted in an icon image, 4096x4096.
+///
+/// Tray icons are shown at a few dozen pixels, so larger images are almost certainly
+/// wrong dimensions, and their pixel data would be sent over D-Bus on every update.
+pub const MAX_ICON_PIXELS: usize = 4096 * 4096;
+
+/// Largest padding, in pixels, accepted on each side of a padded icon.
+pub const MAX_ICON_PADDING: i32 = 1024;
+
+/// Checks that icon dimensions are positive and within [`MAX_ICON_PIXELS`].
+///
+/// Returns the dimensions as `usize`, or `Error::InvalidIconSize`. Nothing in the check
+/// can overflow, whatever the dimensions.
+pub fn validate_size(width: i32, height: i32) -> Result<(usize, usize)> {
+    if width <= 0 || height <= 0 {
+        return Err(Error::InvalidIconSize { width, height });
+    }
+    let (columns, rows) = (width as usize, height as usize);
+    match columns.checked_mul(rows) {
+        Some(pixels) if pixels <= MAX_ICON_PIXELS => Ok((columns, rows)),
+        _ => Err(Error::InvalidIconSize { width, height }),
+    }
+}
+
+/// Returns the number of bytes of tightly packed 32-bit pixel data (RGBA8 or ARGB32)
+/// with the given dimensions.
+///
+/// Returns `Error::InvalidIconSize` if the dimensions are rejected by [`validate_size`].
+pub fn rgba_len(width: i32, height: i32) -> Result<usize> {
+    let (columns, rows) = validate_size(width, height)?;
+    // Within MAX_ICON_PIXELS, so this cannot overflow even with a 32-bit usize
+    Ok(columns * rows * 4)
+}
+
+/// Returns the dimensions of an icon with `padding` pixels added on each side.
+///
+/// Returns `Error::InvalidIconSize` if `padding` is negative or above
+/// [`MAX_ICON_PADDING`], or if the padded dimensions overflow or are rejected by
+/// [`validate_size`].
+pub fn padded_size(width: i32, height: i32, padding: i32) -> Result<(i32, i32)> {
+    let invalid = Error::InvalidIconSize { width, height };
+    if !(0..=MAX_ICON_PADDING).contains(&padding) {
+        return Err(invalid);
+    }
+    let pad = |side: i32| {
+        padding
+            .checked_mul(2)
+            .and_then(|both| side.checked_add(both))
+    };
+    let (Some(padded_width), Some(padded_height)) = (pad(width), pad(height)) else {
+        return Err(invalid);
+    };
+    validate_size(padded_width, padded_height)?;
+    Ok((padded_width, padded_height))
+}
+
+/// Maps a path to an import or remap file (e.g. `res://icon.png.import`) to its source
+/// path.
+///
+/// `ResourceLoader` follows the source path to the imported data, e.g. a `.ctex` file
+/// under `res://.godot/imported/` in exported games, so other paths are kept as given.
+pub fn resolve_import_path(path: &str) -> &str {
+    path.strip_suffix(".import")
+        .or_else(|| path.strip_suffix(".remap"))
+        .unwrap_or(path)
+}
+
+/// Layout of 8-bit pixel data accepted by [`pack_argb`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Red, green, blue and alpha, 4 bytes per pixel.
+    Rgba8,
+    /// Red, green and blue, 3 bytes per pixel. Pixels are opaque.
+    Rgb8,
+    /// Blue, green, red and alpha, 4 bytes per pixel.
+    Bgra8,
+}
+
+impl PixelFormat {
+    /// Returns the number of bytes a pixel takes.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Rgba8 | PixelFormat::Bgra8 => 4,
+            PixelFormat::Rgb8 => 3,
+        }
+    }
+}
+
+/// Converts 8-bit pixel data in `format` into tightly packed ARGB32 pixel data.
+///
+/// Rows start every `stride` bytes, which may include padding after the pixels of a
+/// row. A `stride` of 0 means the rows are tightly packed. The padding after the last
+/// row may be left out.
+///
+/// Returns `Error::InvalidIconSize` if the dimensions are rejected by [`validate_size`],
+/// `Error::InvalidIconStride` if `stride` is shorter than a row of pixels, and
+/// `Error::InvalidIconData` if `data` is too short or too long for `height` rows.
+pub fn pack_argb(
+    width: i32,
+    height: i32,
+    data: &[u8],
+    format: PixelFormat,
+    stride: usize,
+) -> Result<Vec<u8>> {
+    let (width, height) = validate_size(width, height)?;
+    let bytes_per_pixel = format.bytes_per_pixel();
+    let row_bytes = width * bytes_per_pixel;
+    let stride = if stride == 0 { row_bytes } else { stride };
+    if stride < row_bytes {
+        return Err(Error::InvalidIconStride { stride, row_bytes });
+    }
+
+    // A huge stride can make the lengths overflow. No slice can be that long, so
+    // saturating reports the data as too short instead of wrapping around.
+    let min_len = (height - 1)
+        .saturating_mul(stride)
+        .saturating_add(row_bytes);
+    let max_len = height.saturating_mul(stride);
+    if data.len() < min_len || data.len() > max_len {
+        return Err(Error::InvalidIconData {
+            expected: if data.len() < min_len {
+                min_len
+            } else {
+                max_len
+            },
+            got: data.len(),
+        });
+    }
+
+    let mut argb = Vec::with_capacity(width * height * 4);
+    for row in 0..height {
+        let start = row * stride;
+        for pixel in data[start..start + row_bytes].chunks_exact(bytes_per_pixel) {
+            let [a, r, g, b] = match format {
+                PixelFormat::Rgba8 => [pixel[3], pixel[0], pixel[1], pixel[2]],
+                PixelFormat::Rgb8 => [255, pixel[0], pixel[1], pixel[2]],
+                PixelFormat::Bgra8 => [pixel[3], pixel[2], pixel[1], pixel[0]],
+            };
+            argb.extend_from_slice(&[a, r, g, b]);
+        }
+    }
+    Ok(argb)
+}
+
+/// Converts RGBA8 pixel data into ARGB32 pixel data in place.
+pub fn rgba_to_argb(data: &mut [u8]) {
+    for pixel in data.chunks_exact_mut(4) {
+        pixel.rotate_right(1);
+    }
+}
+
+/// Converts RGBA8 pixel data into ARGB32 pixel data in place, forcing every pixel to
+/// be fully opaque.
+pub fn rgba_to_argb_opaque(data: &mut [u8]) {
+    for pixel in data.chunks_exact_mut(4) {
+        pixel.rotate_right(1);
+        pixel[0] = u8::MAX;
+    }
+}
+
+/// Converts ARGB32 pixel data to grayscale in place, keeping alpha.
+///
+/// Uses the Rec. 601 luma weights (0.299 R + 0.587 G + 0.114 B).
+pub fn grayscale_argb(data: &mut [u8]) {
+    for pixel in data.chunks_exact_mut(4) {
+        let luma = 0.299 * pixel[1] as f32 + 0.587 * pixel[2] as f32 + 0.114 * pixel[3] as f32;
+        let luma = luma.round().min(255.0) as u8;
+        pixel[1..].fill(luma);
+    }
+}
+
+/// Applies the sRGB transfer function to a linear color component.
+///
+/// The input is clamped to `0.0..=1.0` first, so HDR values saturate instead of wrapping.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    let value = if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0)
+    };
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Quantizes a color component in `0.0..=1.0` to an 8-bit value, clamping out-of-range input.
+pub fn quantize(value: f32) -> u8 {
+    if value.is_nan() {
+        return 0;
+    }
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Converts RGBAF (four little-endian `f32` per pixel) data into RGBA8 data.
+///
+/// If `linear` is true, the color channels are converted from linear to sRGB before
+/// quantizing. Alpha is always quantized as-is.
+pub fn rgbaf_to_rgba8(data: &[u8], linear: bool) -> Vec<u8> {
+    data.chunks_exact(4)
+        .enumerate()
+        .map(|(index, bytes)| {
+            let value = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            if linear && index % 4 != 3 {
+                quantize(linear_to_srgb(value))
+            } else {
+                quantize(value)
+            }
+        })
+        .collect()
+}
+
+/// Orders pixmaps so the one nearest to `size` comes first.
+///
+/// Compares the larger of each pixmap's dimensions. On a tie the larger pixmap wins, as
+/// downscaling looks better than upscaling. The relative order is otherwise kept.
+pub fn order_by_preferred_size(icons: &mut [ksni::Icon], size: i32) {
+    icons.sort_by_key(|icon| {
+        let icon_size = icon.width.max(icon.height);
+        ((icon_size - size).abs(), std::cmp::Reverse(icon_size))
+    });
+}
+
+/// Renders a square gauge icon filled to `fraction`.
+///
+/// `empty` and `full` are RGBA8 colors. A vertical gauge fills from the bottom up and a
+/// horizontal one from left to right. The row or column at the fill boundary blends both
+/// colors by coverage, so small changes in `fraction` stay visible at small sizes.
+///
+/// Returns `Error::InvalidIconSize` if `size` is rejected by [`validate_size`].
+pub fn render_gauge(
+    fraction: f32,
+    empty: [u8; 4],
+    full: [u8; 4],
+    size: i32,
+    vertical: bool,
+) -> Result<ksni::Icon> {
+    let len = rgba_len(size, size)?;
+    let fraction = if fraction.is_nan() {
+        0.0
+    } else {
+        fraction.clamp(0.0, 1.0)
+    };
+    let filled = fraction * size as f32;
+
+    let mut data = Vec::with_capacity(len);
+    for y in 0..size {
+        for x in 0..size {
+            // Distance of this pixel along the fill direction, starting at the fill origin.
+            let position = if vertical { size - 1 - y } else { x };
+            let coverage = (filled - position as f32).clamp(0.0, 1.0);
+
+            let mut pixel = [0u8; 4];
+            for (channel, value) in pixel.iter_mut().enumerate() {
+                let mixed = empty[channel] as f32
+                    + (full[channel] as f32 - empty[channel] as f32) * coverage;
+                *value = mixed.round() as u8;
+            }
+            data.extend_from_slice(&pixel);
+        }
+    }
+
+    rgba_to_argb(&mut data);
+    Ok(ksni::Icon {
+        width: size,
+        height: size,
+        data,
+    })
+}
+
+/// Glyphs of the badge font, 3 pixels wide and 5 high, one bit per pixel with the
+/// leftmost pixel in the highest of the three bits: the digits `0` to `9`, then `+`.
+const BADGE_GLYPHS: [[u8; 5]; 11] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+    [0b000, 0b010, 0b111, 0b010, 0b000],
+];
+
+/// Index of the `+` glyph in `BADGE_GLYPHS`.
+const BADGE_PLUS: usize = 10;
+
+/// Largest count shown in a badge; larger counts are shown as `99+`.
+pub const MAX_BADGE_COUNT: u32 = 99;
+
+/// Renders a square notification badge: `count` in white on a red circle.
+///
+/// Counts above [`MAX_BADGE_COUNT`] are shown as `99+`. The digits are drawn from a
+/// built-in pixel font, scaled by whole pixels to stay sharp, and the circle edge is
+/// anti-aliased.
+pub fn render_badge(count: u32, size: i32) -> ksni::Icon {
+    const BACKGROUND: [u8; 3] = [220, 38, 38];
+
+    let glyphs: Vec<usize> = if count > MAX_BADGE_COUNT {
+        vec![9, 9, BADGE_PLUS]
+    } else {
+        count
+            .to_string()
+            .bytes()
+            .map(|digit| (digit - b'0') as usize)
+            .collect()
+    };
+
+    // Glyphs are 3 units wide with 1 unit between them, and 5 units high.
+    let text_units = glyphs.len() as i32 * 4 - 1;
+    let scale = (size * 2 / 3 / text_units).min(size / 2 / 5).max(1);
+    let text_left = (size - text_units * scale) / 2;
+    let text_top = (size - 5 * scale) / 2;
+    let is_text = |x: i32, y: i32| {
+        let (unit_x, unit_y) = ((x - text_left) / scale, (y - text_top) / scale);
+        if x < text_left || y < text_top || unit_x >= text_units || unit_y >= 5 {
+            return false;
+        }
+        let (glyph, column) = (unit_x / 4, unit_x % 4);
+        column < 3 && BADGE_GLYPHS[glyphs[glyph as usize]][unit_y as usize] >> (2 - column) & 1 == 1
+    };
+
+    let radius = size as f32 / 2.0;
+    let mut data = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let distance =
+                ((x as f32 + 0.5 - radius).powi(2) + (y as f32 + 0.5 - radius).powi(2)).sqrt();
+            let coverage = (radius - distance + 0.5).clamp(0.0, 1.0);
+            let [r, g, b] = if is_text(x, y) { [255; 3] } else { BACKGROUND };
+            data.extend_from_slice(&[r, g, b, quantize(coverage)]);
+        }
+    }
+
+    rgba_to_argb(&mut data);
+    ksni::Icon {
+        width: size,
+        height: size,
+        data,
+    }
+}
@@ -2,10 +2,30 @@
 //!
 //! This module provides the bridge between our internal tray state and the ksni library,
 //! implementing the `ksni::Tray` trait to connect with the StatusNotifierItem specification.
+//!
+//! The tray service only runs on Linux. Elsewhere [`Handle`] is a stand-in from
+//! [`unsupported`](crate::tray::unsupported) and trays cannot be spawned.
 
+#[cfg(target_os = "linux")]
+use crate::error::{Error, Result};
+use crate::menu::item::MenuItemData;
+use crate::tray::event::TrayEvent;
 use crate::tray::state::TrayState;
+use crate::tray::stats::TrayStats;
+#[cfg(target_os = "linux")]
+use ksni::blocking::TrayMethods;
 use ksni::menu::MenuItem;
+#[cfg(target_os = "linux")]
+use std::sync::mpsc::{RecvTimeoutError, SendError, channel};
 use std::sync::{Arc, Mutex};
+#[cfg(target_os = "linux")]
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+pub use ksni::blocking::Handle;
+
+#[cfg(not(target_os = "linux"))]
+pub use crate::tray::unsupported::Handle;
 
 /// Implementation of the ksni::Tray trait that bridges our internal state
 /// with the ksni library.
@@ -17,17 +37,91 @@ pub struct KsniTray {
     pub state: Arc<Mutex<TrayState>>,
 }
 
+impl KsniTray {
+    /// Locks the tray state and calls `f` with the menu items, returning its result.
+    pub fn with_menu<R>(&self, f: impl FnOnce(&[MenuItemData]) -> R) -> R {
+        let state = self.state.lock().unwrap();
+        f(&state.menu)
+    }
+
+    /// Locks the tray state and calls `f` with the menu items for modification, returning
+    /// its result.
+    ///
+    /// Like the other changes to the state, the modified menu is only shown by the host
+    /// after the tray is updated through its `Handle`.
+    pub fn with_menu_mut<R>(&mut self, f: impl FnOnce(&mut Vec<MenuItemData>) -> R) -> R {
+        let mut state = self.state.lock().unwrap();
+        f(&mut state.menu)
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl KsniTray {
+    /// Spawns the tray service, giving up if registering takes longer than `timeout`.
+    ///
+    /// Registering blocks on D-Bus calls to the StatusNotifierWatcher, which only time
+    /// out after about 25 seconds when the watcher is hung. The registration runs on its
+    /// own thread instead, and is abandoned with `Error::Timeout` when the timeout
+    /// expires. A tray that still finishes registering afterwards is shut down again.
+    /// If the thread panics, `Error::SpawnPanicked` is returned instead.
+    pub fn spawn_with_timeout(self, timeout: Duration) -> Result<Handle<KsniTray>> {
+        let (result_tx, result_rx) = channel();
+        std::thread::spawn(move || {
+            if let Err(SendError(Ok(handle))) = result_tx.send(self.spawn()) {
+                handle.shutdown().wait();
+            }
+        });
+
+        match result_rx.recv_timeout(timeout) {
+            Ok(result) => Ok(result?),
+            Err(RecvTimeoutError::Timeout) => Err(Error::Timeout),
+            Err(RecvTimeoutError::Disconnected) => Err(Error::SpawnPanicked),
+        }
+    }
+}
+
 impl ksni::Tray for KsniTray {
     fn id(&self) -> String {
         let state = self.state.lock().unwrap();
         state.tray_id.clone()
     }
 
+    fn activate(&mut self, x: i32, y: i32) {
+        let state = self.state.lock().unwrap();
+        if !state.tray_enabled {
+            return;
+        }
+        state.send_event(TrayEvent::Activated(x, y));
+    }
+
+    fn scroll(&mut self, delta: i32, orientation: ksni::Orientation) {
+        let state = self.state.lock().unwrap();
+        if !state.tray_enabled {
+            return;
+        }
+        state.send_event(TrayEvent::Scrolled(delta, orientation.into()));
+    }
+
     fn icon_name(&self) -> String {
         let state = self.state.lock().unwrap();
         state.icon_name.clone()
     }
 
+    fn category(&self) -> ksni::Category {
+        let state = self.state.lock().unwrap();
+        state.category
+    }
+
+    fn status(&self) -> ksni::Status {
+        let state = self.state.lock().unwrap();
+        state.status
+    }
+
+    fn window_id(&self) -> i32 {
+        let state = self.state.lock().unwrap();
+        state.sni_window_id()
+    }
+
     fn icon_theme_path(&self) -> String {
         let state = self.state.lock().unwrap();
         state.icon_theme_path.clone()
@@ -35,26 +129,61 @@ impl ksni::Tray for KsniTray {
 
     fn icon_pixmap(&self) -> Vec<ksni::Icon> {
         let state = self.state.lock().unwrap();
-        state.icon_pixmap.clone()
+        state.preferred_icon_pixmaps()
+    }
+
+    fn overlay_icon_pixmap(&self) -> Vec<ksni::Icon> {
+        let state = self.state.lock().unwrap();
+        state.overlay_icon_pixmap.clone()
+    }
+
+    fn text_direction(&self) -> ksni::TextDirection {
+        let state = self.state.lock().unwrap();
+        state.text_direction
     }
 
     fn title(&self) -> String {
         let state = self.state.lock().unwrap();
-        state.title.clone()
+        state.title()
     }
 
     fn tool_tip(&self) -> ksni::ToolTip {
         let state = self.state.lock().unwrap();
-        ksni::ToolTip {
-            icon_name: state.tooltip_icon_name.clone(),
-            icon_pixmap: vec![],
-            title: state.tooltip_title.clone(),
-            description: state.tooltip_subtitle.clone(),
-        }
+        state.tooltip()
     }
 
     fn menu(&self) -> Vec<MenuItem<Self>> {
         let state = self.state.lock().unwrap();
+        TrayStats::increment(&state.stats.menu_rebuilds);
         state.build_menu_items()
     }
+
+    fn menu_about_to_show(&mut self) {
+        let state = self.state.lock().unwrap();
+        state.send_event(TrayEvent::MenuAboutToShow);
+    }
+
+    fn watcher_online(&self) {
+        let state = self.state.lock().unwrap();
+        log::info!(
+            "Tray '{}' registered with the StatusNotifierWatcher",
+            state.tray_id
+        );
+    }
+
+    fn watcher_offline(&self, reason: ksni::OfflineReason) -> bool {
+        let state = self.state.lock().unwrap();
+        match reason {
+            ksni::OfflineReason::Error(e) => {
+                log::error!("Tray '{}' went offline: {}", state.tray_id, e);
+            }
+            _ => {
+                log::warn!(
+                    "Tray '{}' went offline: StatusNotifierWatcher is gone, waiting for it to return",
+                    state.tray_id
+                );
+            }
+        }
+        true
+    }
 }
@@ -0,0 +1,89 @@
+//! Forwarding of `log` records to the Godot console.
+//!
+//! Godot's print functions must not be called from the ksni background thread, so the
+//! logger installed here only queues records. The `TrayIcon` node drains the queue on
+//! the main thread every frame and prints them with `godot_error!`, `godot_warn!` or
+//! `godot_print!`.
+//!
+//! At most [`MAX_PENDING`] records are queued, so a chatty background thread cannot
+//! grow the queue without bound while no node drains it, e.g. before the first frame.
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::sync::{Mutex, Once};
+
+/// Largest number of queued records. The oldest records are dropped beyond it.
+pub const MAX_PENDING: usize = 256;
+
+/// Records waiting to be printed on the main thread.
+static PENDING: Mutex<VecDeque<(Level, String)>> = Mutex::new(VecDeque::new());
+
+static INIT: Once = Once::new();
+
+/// Logger that queues records for the main thread.
+struct QueueLogger;
+
+impl Log for QueueLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = format!("[{}] {}", record.target(), record.args());
+        if let Ok(mut pending) = PENDING.lock() {
+            if pending.len() >= MAX_PENDING {
+                pending.pop_front();
+            }
+            pending.push_back((record.level(), message));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs the queueing logger, once per process.
+///
+/// If the host application already installed its own logger, that one is kept and
+/// nothing is queued. The level defaults to `Warn`.
+pub fn init() {
+    static LOGGER: QueueLogger = QueueLogger;
+    INIT.call_once(|| {
+        if log::set_logger(&LOGGER).is_ok() {
+            log::set_max_level(LevelFilter::Warn);
+        }
+    });
+}
+
+/// Sets the maximum level of records that are forwarded.
+///
+/// This sets the process-global `log::max_level()`, so it also filters the records of
+/// every other crate logging in the process, whichever logger is installed.
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+/// Converts a level number, as used by `TrayIcon::set_log_level()`, into a filter.
+///
+/// 0 or less turns logging off, then 1 = error, 2 = warn, 3 = info, 4 = debug, and 5 or
+/// more = trace.
+pub fn level_filter(level: i64) -> LevelFilter {
+    match level {
+        i64::MIN..=0 => LevelFilter::Off,
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Takes all queued records.
+pub fn drain() -> Vec<(Level, String)> {
+    match PENDING.lock() {
+        Ok(mut pending) => pending.drain(..).collect(),
+        Err(_) => Vec::new(),
+    }
+}
@@ -0,0 +1,220 @@
+//! Tooltip markup handling.
+//!
+//! The StatusNotifierItem specification allows a subset of HTML in tooltip descriptions,
+//! which Plasma renders as Qt rich text. This module escapes plain text so it is never
+//! interpreted as markup, and sanitizes markup down to a small whitelist:
+//!
+//! - `<b>` and `<i>`, closed automatically if left open
+//! - `<br>`
+//! - `<img src="icon-name">`, where the source must be a plain icon name
+//!
+//! Any other tag is escaped and shown as text.
+
+/// Escapes `&`, `<` and `>` so the text is shown literally by markup-rendering hosts.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Sanitizes markup, keeping only whitelisted tags and well-formed entities.
+///
+/// The result always has balanced `<b>` and `<i>` tags.
+pub fn sanitize(markup: &str) -> String {
+    let mut sanitized = String::with_capacity(markup.len());
+    let mut open_tags: Vec<&'static str> = Vec::new();
+    let mut rest = markup;
+
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '<' => {
+                let tag = rest[1..]
+                    .find(['<', '>'])
+                    .filter(|&end| rest[1 + end..].starts_with('>'))
+                    .and_then(|end| {
+                        let tag = sanitize_tag(&rest[1..1 + end], &mut open_tags)?;
+                        Some((tag, end + 2))
+                    });
+                match tag {
+                    Some((tag, len)) => {
+                        sanitized.push_str(&tag);
+                        rest = &rest[len..];
+                    }
+                    None => {
+                        sanitized.push_str("&lt;");
+                        rest = &rest[1..];
+                    }
+                }
+            }
+            '>' => {
+                sanitized.push_str("&gt;");
+                rest = &rest[1..];
+            }
+            '&' => match entity_len(rest) {
+                Some(len) => {
+                    sanitized.push_str(&rest[..len]);
+                    rest = &rest[len..];
+                }
+                None => {
+                    sanitized.push_str("&amp;");
+                    rest = &rest[1..];
+                }
+            },
+            _ => {
+                sanitized.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    for tag in open_tags.iter().rev() {
+        sanitized.push_str(&format!("</{}>", tag));
+    }
+    sanitized
+}
+
+/// Converts markup to plain text, for hosts and fields that do not render markup.
+///
+/// Tags are removed, `<br>` becomes a line break, and entities are decoded.
+pub fn to_plain_text(markup: &str) -> String {
+    let sanitized = sanitize(markup);
+    let mut plain = String::with_capacity(sanitized.len());
+    let mut rest = sanitized.as_str();
+
+    while let Some(c) = rest.chars().next() {
+        match c {
+            // Sanitized markup only contains complete, whitelisted tags.
+            '<' => {
+                let end = rest.find('>').unwrap_or(rest.len() - 1);
+                if rest[1..end].starts_with("br") {
+                    plain.push('\n');
+                }
+                rest = &rest[end + 1..];
+            }
+            '&' => {
+                let len = entity_len(rest).unwrap_or(1);
+                plain.push(decode_entity(&rest[..len]).unwrap_or('&'));
+                rest = &rest[len..];
+            }
+            _ => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    plain
+}
+
+/// Returns the sanitized form of a tag (without its angle brackets), or `None` if it
+/// is not whitelisted. Closing tags that were never opened are dropped.
+fn sanitize_tag(tag: &str, open_tags: &mut Vec<&'static str>) -> Option<String> {
+    let tag = tag.trim();
+    let name_end = tag
+        .find(|c: char| c.is_whitespace() || c == '/')
+        .filter(|&i| i > 0)
+        .unwrap_or(tag.len());
+    let (name, attributes) = tag.split_at(name_end);
+
+    match name.to_ascii_lowercase().as_str() {
+        "b" | "i" if attributes.is_empty() => {
+            let name = if name.eq_ignore_ascii_case("b") {
+                "b"
+            } else {
+                "i"
+            };
+            open_tags.push(name);
+            Some(format!("<{}>", name))
+        }
+        "/b" | "/i" if attributes.trim().is_empty() => {
+            let name = if name.eq_ignore_ascii_case("/b") {
+                "b"
+            } else {
+                "i"
+            };
+            let Some(position) = open_tags.iter().rposition(|open| *open == name) else {
+                return Some(String::new());
+            };
+            // Close any tags opened inside this one first, then reopen them.
+            let reopened = open_tags.split_off(position + 1);
+            open_tags.pop();
+            let mut closed = String::new();
+            for tag in reopened.iter().rev() {
+                closed.push_str(&format!("</{}>", tag));
+            }
+            closed.push_str(&format!("</{}>", name));
+            for tag in reopened {
+                closed.push_str(&format!("<{}>", tag));
+                open_tags.push(tag);
+            }
+            Some(closed)
+        }
+        "br" if matches!(attributes.trim(), "" | "/") => Some("<br/>".to_string()),
+        "img" => {
+            let src = attribute_value(attributes, "src")?;
+            is_icon_name(src).then(|| format!("<img src=\"{}\"/>", src))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the value of a quoted attribute in a tag's attribute list.
+fn attribute_value<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes.trim_start();
+    while !rest.is_empty() && rest != "/" {
+        let (key, after_key) = rest.split_once('=')?;
+        let key = key.trim();
+        let after_key = after_key.trim_start();
+        let quote = after_key
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')?;
+        let (value, after_value) = after_key[1..].split_once(quote)?;
+        if key.eq_ignore_ascii_case(name) {
+            return Some(value);
+        }
+        rest = after_value.trim_start();
+    }
+    None
+}
+
+/// Returns `true` if `name` is a plain freedesktop icon name, not a path or URL.
+fn is_icon_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Returns the length of the well-formed entity at the start of `text`, if any.
+fn entity_len(text: &str) -> Option<usize> {
+    let end = text.find(';')?;
+    decode_entity(&text[..=end]).map(|_| end + 1)
+}
+
+/// Decodes a named or numeric entity such as `&amp;` or `&#60;`.
+fn decode_entity(entity: &str) -> Option<char> {
+    let name = entity.strip_prefix('&')?.strip_suffix(';')?;
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        _ => {
+            let number = name.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code).filter(|c| *c != '\0')
+        }
+    }
+}
@@ -4,8 +4,18 @@
 //! event handling, and the bridge to the KSNI library.
 
 pub mod event;
+pub mod icon;
 pub mod ksni_impl;
+pub mod logging;
+pub mod markup;
+pub mod registry;
+pub mod screen;
+pub mod scroll;
 pub mod state;
+pub mod stats;
+#[cfg(not(target_os = "linux"))]
+pub mod unsupported;
+pub mod watcher;
 
 pub use event::TrayEvent;
 pub use ksni_impl::KsniTray;
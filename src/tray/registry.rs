@@ -0,0 +1,63 @@
+//! Registry of kept-alive trays.
+//!
+//! Trays spawned in keep-alive mode outlive the node that created them. When such a node
+//! is destroyed, its spawned tray (state, handle, and event channel) is parked here so a
+//! new node can adopt it and resume delivering signals.
+//!
+//! Each tray ID is either owned by a live node or parked. Only parked trays can be
+//! adopted, so a tray can never be driven by two nodes at once.
+
+use crate::error::{Error, Result};
+use crate::tray::event::TrayEvent;
+use crate::tray::ksni_impl::{Handle, KsniTray};
+use crate::tray::state::TrayState;
+use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// A spawned tray that is kept alive independently of any node.
+pub struct KeptAliveTray {
+    /// Shared reference to the tray state.
+    pub state: Arc<Mutex<TrayState>>,
+    /// Handle to the running ksni tray service.
+    pub handle: Handle<KsniTray>,
+    /// Receiving end of the tray's event channel.
+    pub event_receiver: Receiver<TrayEvent>,
+}
+
+/// Kept-alive trays by tray ID. `None` means the tray is owned by a live node.
+static KEPT_ALIVE: LazyLock<Mutex<HashMap<String, Option<KeptAliveTray>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Registers a tray ID as kept alive and owned by a live node.
+pub fn register(tray_id: &str) {
+    let mut registry = KEPT_ALIVE.lock().unwrap();
+    registry.insert(tray_id.to_string(), None);
+}
+
+/// Removes a tray ID from the registry, returning the parked tray if there was one.
+pub fn unregister(tray_id: &str) -> Option<KeptAliveTray> {
+    let mut registry = KEPT_ALIVE.lock().unwrap();
+    registry.remove(tray_id).flatten()
+}
+
+/// Parks a tray whose owning node is going away, so it can be adopted later.
+pub fn park(tray_id: &str, tray: KeptAliveTray) {
+    let mut registry = KEPT_ALIVE.lock().unwrap();
+    registry.insert(tray_id.to_string(), Some(tray));
+}
+
+/// Takes ownership of a parked tray.
+///
+/// The tray ID stays registered as owned by the adopting node.
+pub fn adopt(tray_id: &str) -> Result<KeptAliveTray> {
+    let mut registry = KEPT_ALIVE.lock().unwrap();
+    let slot = registry
+        .get_mut(tray_id)
+        .ok_or_else(|| Error::KeptAliveNotFound {
+            tray_id: tray_id.to_string(),
+        })?;
+    slot.take().ok_or_else(|| Error::AlreadyAdopted {
+        tray_id: tray_id.to_string(),
+    })
+}
@@ -0,0 +1,63 @@
+//! Mapping of tray activation positions onto screens.
+//!
+//! Hosts report activation positions in global screen coordinates, the same space
+//! Godot's `DisplayServer` uses on X11. This module finds the screen such a position
+//! is on and converts it into coordinates relative to that screen, given the screen
+//! rectangles reported by `DisplayServer`.
+
+/// A screen rectangle in global coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScreenRect {
+    /// Horizontal position of the top left corner.
+    pub x: i32,
+    /// Vertical position of the top left corner.
+    pub y: i32,
+    /// Width in pixels.
+    pub width: i32,
+    /// Height in pixels.
+    pub height: i32,
+}
+
+impl ScreenRect {
+    /// Returns `true` if the global position lies on this screen.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    /// Returns the squared distance from the global position to the nearest point of
+    /// this screen, 0 if the position lies on it.
+    fn distance_squared(&self, x: i32, y: i32) -> i64 {
+        let dx = (self.x as i64 - x as i64).max(x as i64 - (self.x + self.width - 1) as i64);
+        let dy = (self.y as i64 - y as i64).max(y as i64 - (self.y + self.height - 1) as i64);
+        dx.max(0).pow(2) + dy.max(0).pow(2)
+    }
+}
+
+/// A position on a screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScreenPosition {
+    /// Index of the screen in the list passed to [`locate`].
+    pub screen: usize,
+    /// Horizontal position relative to the left edge of the screen.
+    pub x: i32,
+    /// Vertical position relative to the top edge of the screen.
+    pub y: i32,
+}
+
+/// Finds the screen a global position is on, and the position relative to that screen.
+///
+/// A position outside every screen, as some hosts report for panels at the edge of
+/// the desktop, is attributed to the nearest screen and clamped onto it. Returns
+/// `None` if there are no screens.
+pub fn locate(x: i32, y: i32, screens: &[ScreenRect]) -> Option<ScreenPosition> {
+    let (screen, rect) = screens
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, rect)| rect.distance_squared(x, y))?;
+
+    Some(ScreenPosition {
+        screen,
+        x: (x - rect.x).clamp(0, (rect.width - 1).max(0)),
+        y: (y - rect.y).clamp(0, (rect.height - 1).max(0)),
+    })
+}
@@ -0,0 +1,84 @@
+//! Accumulation of scroll events over the tray icon.
+//!
+//! Hosts disagree on the magnitude of scroll deltas. KDE Plasma passes Qt's wheel angle,
+//! 120 per mouse wheel notch and proportionally less for touchpads, while other hosts,
+//! such as the GNOME AppIndicator extension, use their own, usually much smaller, units.
+//! High resolution touchpads deliver many small deltas in quick succession on all of them.
+//!
+//! [`ScrollAccumulator`] sums the deltas per orientation and turns them into whole steps
+//! of a configurable size, so handlers see the same number of steps regardless of the
+//! input device.
+
+/// Direction of a scroll event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScrollOrientation {
+    /// Scrolling up (negative) or down (positive).
+    Vertical,
+    /// Scrolling left (negative) or right (positive).
+    Horizontal,
+}
+
+impl From<ksni::Orientation> for ScrollOrientation {
+    fn from(orientation: ksni::Orientation) -> Self {
+        match orientation {
+            ksni::Orientation::Vertical => ScrollOrientation::Vertical,
+            ksni::Orientation::Horizontal => ScrollOrientation::Horizontal,
+        }
+    }
+}
+
+/// Sums scroll deltas per orientation into whole steps.
+#[derive(Clone, Debug, Default)]
+pub struct ScrollAccumulator {
+    threshold: i32,
+    vertical: i32,
+    horizontal: i32,
+}
+
+impl ScrollAccumulator {
+    /// Creates an accumulator emitting one step per `threshold` of accumulated delta.
+    ///
+    /// A `threshold` of 0 or less is raw mode, in which every delta is passed through
+    /// unchanged.
+    pub fn new(threshold: i32) -> Self {
+        Self {
+            threshold: threshold.max(0),
+            vertical: 0,
+            horizontal: 0,
+        }
+    }
+
+    /// Returns the threshold, 0 in raw mode.
+    pub fn threshold(&self) -> i32 {
+        self.threshold
+    }
+
+    /// Adds a delta, returning the number of whole steps to emit, or 0 if none.
+    ///
+    /// The remainder is kept for the next delta of the same orientation. Scrolling in
+    /// the opposite direction discards it first, so reversing responds immediately.
+    pub fn push(&mut self, delta: i32, orientation: ScrollOrientation) -> i32 {
+        if self.threshold == 0 {
+            return delta;
+        }
+
+        let accumulated = match orientation {
+            ScrollOrientation::Vertical => &mut self.vertical,
+            ScrollOrientation::Horizontal => &mut self.horizontal,
+        };
+        if accumulated.signum() * delta.signum() < 0 {
+            *accumulated = 0;
+        }
+        *accumulated = accumulated.saturating_add(delta);
+
+        let steps = *accumulated / self.threshold;
+        *accumulated -= steps * self.threshold;
+        steps
+    }
+
+    /// Discards the accumulated remainders.
+    pub fn reset(&mut self) {
+        self.vertical = 0;
+        self.horizontal = 0;
+    }
+}
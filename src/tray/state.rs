@@ -3,39 +3,118 @@
 //! This module contains the internal state of the tray icon and methods for
 //! managing menu items, including finding and toggling checkmarks and radio buttons.
 
-use crate::menu::item::MenuItemData;
+use crate::error::{Error, Result};
+use crate::menu::item::{MenuItemData, MenuItemDescription, RadioItemData};
 use crate::tray::event::TrayEvent;
+use crate::tray::icon;
 use crate::tray::ksni_impl::KsniTray;
+use crate::tray::markup;
+use crate::tray::stats::TrayStats;
 use ksni::menu::*;
-use std::sync::mpsc::Sender;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::time::{Duration, Instant};
 
 /// Internal state of the tray icon.
 ///
 /// This struct holds all the configuration and state for a tray icon,
 /// including its appearance, menu items, and event communication channel.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
 pub struct TrayState {
     /// The name of the icon from the freedesktop icon theme.
     pub icon_name: String,
     /// Path to search for custom icon themes.
     pub icon_theme_path: String,
     /// Raw icon data as pixmaps.
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub icon_pixmap: Vec<ksni::Icon>,
-    /// The title text of the tray icon.
-    pub title: String,
+    /// Overlay icon drawn over the tray icon as pixmaps, e.g. a notification badge.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub overlay_icon_pixmap: Vec<ksni::Icon>,
+    /// Whether the icon name or pixmaps were set explicitly, instead of being left at
+    /// their defaults.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub icon_set: bool,
+    /// Scale applied to the default icon size when picking the preferred pixmap.
+    pub icon_dpi_scale: f32,
+    /// The static base of the title text of the tray icon.
+    #[cfg_attr(feature = "serde", serde(alias = "title"))]
+    pub title_base: String,
+    /// Dynamic text appended to the title base.
+    pub title_suffix: String,
     /// Title for the tooltip.
     pub tooltip_title: String,
     /// Subtitle for the tooltip.
     pub tooltip_subtitle: String,
     /// Icon name for the tooltip.
     pub tooltip_icon_name: String,
+    /// Icon of the tooltip as pixmaps, used by hosts that do not find the icon name.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub tooltip_icon_pixmap: Vec<ksni::Icon>,
+    /// Whether the tooltip title and subtitle hold sanitized markup instead of plain text.
+    pub tooltip_markup: bool,
     /// Unique identifier for this tray icon.
     pub tray_id: String,
+    /// Status reported to the host.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub status: ksni::Status,
+    /// Whether the tray is enabled. A disabled tray is passive and its menu is inert.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub tray_enabled: bool,
+    /// Status to restore when the tray is re-enabled.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub status_before_disable: ksni::Status,
+    /// Category reported to the host, read by hosts when the tray registers.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub category: ksni::Category,
+    /// Windowing-system identifier of the window associated with this tray icon.
+    pub window_id: Option<i64>,
+    /// Direction of the title, tooltip, and menu texts.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub text_direction: ksni::TextDirection,
     /// Menu structure containing all menu items.
     pub menu: Vec<MenuItemData>,
+    /// IDs of top-level menu items that are always shown first, in pin order.
+    pub pinned_item_ids: Vec<String>,
+    /// Whether standard items inside submenus emit their ID prefixed by the submenu path.
+    pub use_path_ids: bool,
+    /// IDs of logically grouped top-level menu items, by group name.
+    pub menu_groups: HashMap<String, Vec<String>>,
+    /// Generation of the menu, increased whenever the whole menu is replaced or cleared.
+    ///
+    /// Menu callbacks remember the generation they were built from and are ignored once
+    /// it is outdated, so a host still showing an old menu cannot activate its items.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub menu_generation: u64,
+    /// Per-item minimum time between two activations of a standard menu item.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub activation_cooldowns: HashMap<String, Duration>,
+    /// Time of the last accepted activation of each menu item with a cooldown.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub last_activation_times: HashMap<String, Instant>,
     /// Channel sender for emitting events to Godot.
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub event_sender: Option<Sender<TrayEvent>>,
+    /// Runtime counters shared with the Godot node.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub stats: Arc<TrayStats>,
+}
+
+impl Default for TrayState {
+    fn default() -> Self {
+        Self::new("godot_tray_icon".to_string())
+    }
 }
 
+/// Maximum length of a tray ID, the maximum length of a D-Bus name.
+pub const MAX_TRAY_ID_LEN: usize = 255;
+
+/// Icon name used when no icon is set, a generic executable icon.
+pub const DEFAULT_ICON_NAME: &str = "application-x-executable";
+
 impl TrayState {
     /// Creates a new `TrayState` with default values.
     ///
@@ -44,102 +123,1169 @@ impl TrayState {
     /// - `tray_id` - Unique identifier for the tray icon
     pub fn new(tray_id: String) -> Self {
         Self {
-            icon_name: "application-x-executable".to_string(),
+            icon_name: DEFAULT_ICON_NAME.to_string(),
+            icon_set: false,
             icon_theme_path: String::new(),
             icon_pixmap: Vec::new(),
-            title: "Tray Icon".to_string(),
+            overlay_icon_pixmap: Vec::new(),
+            icon_dpi_scale: 1.0,
+            title_base: "Tray Icon".to_string(),
+            title_suffix: String::new(),
             tooltip_title: String::new(),
             tooltip_subtitle: String::new(),
             tooltip_icon_name: String::new(),
+            tooltip_icon_pixmap: Vec::new(),
+            tooltip_markup: false,
             tray_id,
+            status: ksni::Status::Active,
+            tray_enabled: true,
+            status_before_disable: ksni::Status::Active,
+            category: ksni::Category::ApplicationStatus,
+            window_id: None,
+            text_direction: ksni::TextDirection::LeftToRight,
             menu: Vec::new(),
+            pinned_item_ids: Vec::new(),
+            use_path_ids: false,
+            menu_groups: HashMap::new(),
+            menu_generation: 0,
+            activation_cooldowns: HashMap::new(),
+            last_activation_times: HashMap::new(),
             event_sender: None,
+            stats: Arc::new(TrayStats::default()),
         }
     }
 
-    /// Finds a checkmark item by ID and toggles its state.
+    /// Creates a new `TrayState` with default values and a connected event channel.
     ///
-    /// Returns the new checked state if found, or None if not found.
-    pub fn find_and_toggle_checkmark(&mut self, id: &str) -> Option<bool> {
-        Self::find_and_toggle_checkmark_recursive(&mut self.menu, id)
+    /// Events sent by the state, e.g. from menu callbacks, arrive on the returned
+    /// receiver. This saves creating the channel and setting `event_sender` by hand.
+    ///
+    /// # Parameters
+    ///
+    /// - `tray_id` - Unique identifier for the tray icon
+    pub fn with_event_channel(tray_id: String) -> (Self, Receiver<TrayEvent>) {
+        let (tx, rx) = channel();
+        let mut state = Self::new(tray_id);
+        state.event_sender = Some(tx);
+        (state, rx)
     }
 
-    /// Recursively searches through menu items to find and toggle a checkmark.
-    pub fn find_and_toggle_checkmark_recursive(
-        items: &mut Vec<MenuItemData>,
+    /// Creates a new `TrayState` with the title and tray ID derived from an application name.
+    ///
+    /// The title is the trimmed name and the tray ID is `sanitize_tray_id(name)`. Each
+    /// falls back to the default of `TrayState::default()` when it would be empty.
+    pub fn from_app_name(app_name: &str) -> Self {
+        let mut state = Self::default();
+
+        let title = app_name.trim();
+        if !title.is_empty() {
+            state.title_base = title.to_string();
+        }
+
+        let tray_id = Self::sanitize_tray_id(app_name);
+        if !tray_id.is_empty() {
+            state.tray_id = tray_id;
+        }
+
+        state
+    }
+
+    /// Turns an arbitrary name into a tray ID.
+    ///
+    /// ASCII letters and digits are kept in lowercase, and every run of other characters
+    /// becomes a single underscore, with none at the start or end
+    /// (e.g. `"My Cool Game!"` becomes `"my_cool_game"`). The result is then passed
+    /// through `validate_tray_id()`, which prefixes a leading digit and truncates it, so
+    /// it always validates unchanged. Returns an empty string if no letter or digit is
+    /// left.
+    pub fn sanitize_tray_id(name: &str) -> String {
+        let words: Vec<_> = name
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+        Self::validate_tray_id(&words.join("_").to_ascii_lowercase()).unwrap_or_default()
+    }
+
+    /// Checks a tray ID against the rules for D-Bus names, sanitizing it if needed.
+    ///
+    /// The ID is split into elements at dots, as in `"com.example.my_app"`. Every run of
+    /// characters other than ASCII letters, digits, `_` and `-` becomes a single
+    /// underscore, elements starting with a digit are prefixed with an underscore, and
+    /// empty elements are removed. The result is truncated to [`MAX_TRAY_ID_LEN`]
+    /// characters. A valid ID is returned unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTrayId`] if the ID has no ASCII letter or digit.
+    pub fn validate_tray_id(tray_id: &str) -> Result<String> {
+        if !tray_id.chars().any(|c| c.is_ascii_alphanumeric()) {
+            return Err(Error::InvalidTrayId {
+                tray_id: tray_id.to_string(),
+            });
+        }
+
+        let mut elements = Vec::new();
+        for element in tray_id.split('.') {
+            let mut sanitized = String::with_capacity(element.len());
+            for c in element.chars() {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    if sanitized.is_empty() && c.is_ascii_digit() {
+                        sanitized.push('_');
+                    }
+                    sanitized.push(c);
+                } else if !sanitized.ends_with('_') {
+                    sanitized.push('_');
+                }
+            }
+            if !sanitized.is_empty() {
+                elements.push(sanitized);
+            }
+        }
+
+        let mut sanitized = elements.join(".");
+        sanitized.truncate(MAX_TRAY_ID_LEN);
+        while sanitized.ends_with('.') {
+            sanitized.pop();
+        }
+        Ok(sanitized)
+    }
+
+    /// Replaces the whole menu structure with the given items.
+    pub fn set_menu(&mut self, menu: Vec<MenuItemData>) {
+        self.menu = menu;
+        self.menu_generation += 1;
+    }
+
+    /// Removes all menu items and groups.
+    pub fn clear_menu(&mut self) {
+        self.menu.clear();
+        self.menu_groups.clear();
+        self.menu_generation += 1;
+    }
+
+    /// Replaces the whole menu with the given items in one step, dropping the menu groups.
+    ///
+    /// Unlike a `clear_menu()` followed by adding items one by one, no intermediate menu
+    /// is ever visible to the tray thread, as long as the state stays locked. Callbacks
+    /// of the previous menu are ignored from now on.
+    pub fn rebuild_from_items(&mut self, items: Vec<MenuItemData>) {
+        self.menu_groups.clear();
+        self.set_menu(items);
+    }
+
+    /// Resets the state to the defaults of a fresh `TrayState`, optionally keeping the
+    /// menu and the icon.
+    ///
+    /// The tray ID, event channel, runtime counters and text direction always stay, the
+    /// latter following the node's `text_direction` setting. With `preserve_menu`
+    /// the menu items keep their current checkmark and radio states, along with pins,
+    /// groups and activation cooldowns, and callbacks of the menu shown by the host stay
+    /// valid. Otherwise the menu is cleared like with `clear_menu()`. With
+    /// `preserve_icon` the icon name, theme path, pixmaps, overlay and DPI scale stay.
+    pub fn migrate_state(&mut self, preserve_menu: bool, preserve_icon: bool) {
+        let mut fresh = Self::new(std::mem::take(&mut self.tray_id));
+        fresh.event_sender = self.event_sender.take();
+        fresh.stats = Arc::clone(&self.stats);
+        fresh.text_direction = self.text_direction;
+
+        if preserve_menu {
+            fresh.menu = std::mem::take(&mut self.menu);
+            fresh.pinned_item_ids = std::mem::take(&mut self.pinned_item_ids);
+            fresh.use_path_ids = self.use_path_ids;
+            fresh.menu_groups = std::mem::take(&mut self.menu_groups);
+            fresh.menu_generation = self.menu_generation;
+            fresh.activation_cooldowns = std::mem::take(&mut self.activation_cooldowns);
+            fresh.last_activation_times = std::mem::take(&mut self.last_activation_times);
+        } else {
+            fresh.menu_generation = self.menu_generation + 1;
+        }
+
+        if preserve_icon {
+            fresh.icon_name = std::mem::take(&mut self.icon_name);
+            fresh.icon_theme_path = std::mem::take(&mut self.icon_theme_path);
+            fresh.icon_pixmap = std::mem::take(&mut self.icon_pixmap);
+            fresh.icon_set = self.icon_set;
+            fresh.overlay_icon_pixmap = std::mem::take(&mut self.overlay_icon_pixmap);
+            fresh.icon_dpi_scale = self.icon_dpi_scale;
+        }
+
+        *self = fresh;
+    }
+
+    /// Returns `true` if menu callbacks built at `generation` belong to the current menu.
+    ///
+    /// A callback from an outdated menu is counted as a dropped event.
+    pub fn is_current_menu(&self, generation: u64) -> bool {
+        let current = generation == self.menu_generation;
+        if !current {
+            TrayStats::increment(&self.stats.events_dropped);
+        }
+        current
+    }
+
+    /// Resets the icon to the default icon name, clearing any pixmaps.
+    pub fn reset_icon(&mut self) {
+        self.icon_pixmap.clear();
+        self.icon_name = DEFAULT_ICON_NAME.to_string();
+        self.icon_set = false;
+    }
+
+    /// Sets the name of the icon from the freedesktop icon theme.
+    ///
+    /// Pixmaps set before are kept.
+    pub fn set_icon_name(&mut self, icon_name: impl Into<String>) {
+        self.icon_name = icon_name.into();
+        self.icon_set = true;
+    }
+
+    /// Sets the tray icon from ARGB32 pixmaps.
+    ///
+    /// Clears the icon name so the pixmaps take precedence.
+    pub fn set_icon(&mut self, icon_pixmap: Vec<ksni::Icon>) {
+        self.icon_pixmap = icon_pixmap;
+        self.icon_name = String::new();
+        self.icon_set = true;
+    }
+
+    /// Sets the icon from a single RGBA8 image, clearing the icon name.
+    ///
+    /// Returns `Error::InvalidIconSize` if the dimensions are not positive or too large,
+    /// and `Error::InvalidIconData` if `data` is not `width * height * 4` bytes long.
+    pub fn set_icon_rgba(&mut self, width: i32, height: i32, data: &[u8]) -> Result<()> {
+        self.set_icon_pixels(width, height, data, icon::PixelFormat::Rgba8, 0)
+    }
+
+    /// Sets the icon from a single image in any 8-bit `format`, clearing the icon name.
+    ///
+    /// Rows start every `stride` bytes, 0 meaning tightly packed rows. See
+    /// [`icon::pack_argb`] for the errors returned; the icon is left unchanged on error.
+    pub fn set_icon_pixels(
+        &mut self,
+        width: i32,
+        height: i32,
+        data: &[u8],
+        format: icon::PixelFormat,
+        stride: usize,
+    ) -> Result<()> {
+        let argb_data = icon::pack_argb(width, height, data, format, stride)?;
+        self.set_icon(vec![ksni::Icon {
+            width,
+            height,
+            data: argb_data,
+        }]);
+        Ok(())
+    }
+
+    /// Sets the icon from ARGB32 pixmaps in network byte order, as sent to the host,
+    /// clearing the icon name.
+    ///
+    /// The pixel data is stored as is. Returns `Error::InvalidIconSize` if any pixmap
+    /// has dimensions rejected by [`icon::validate_size`], and `Error::InvalidIconData`
+    /// if its data is not `width * height * 4` bytes long; the icon is left unchanged.
+    pub fn set_icon_argb(&mut self, icon_pixmap: Vec<ksni::Icon>) -> Result<()> {
+        for pixmap in &icon_pixmap {
+            let expected = icon::rgba_len(pixmap.width, pixmap.height)?;
+            if pixmap.data.len() != expected {
+                return Err(Error::InvalidIconData {
+                    expected,
+                    got: pixmap.data.len(),
+                });
+            }
+        }
+
+        self.set_icon(icon_pixmap);
+        Ok(())
+    }
+
+    /// Sets the static base of the title.
+    pub fn set_title_base(&mut self, title_base: impl Into<String>) {
+        self.title_base = title_base.into();
+    }
+
+    /// Sets the dynamic text appended to the title base.
+    pub fn set_title_suffix(&mut self, title_suffix: impl Into<String>) {
+        self.title_suffix = title_suffix.into();
+    }
+
+    /// Returns the full title, which is the title base followed by the suffix.
+    pub fn title(&self) -> String {
+        format!("{}{}", self.title_base, self.title_suffix)
+    }
+
+    /// Sets the tooltip title, subtitle, and icon name, removing the tooltip icon pixmap.
+    pub fn set_tooltip(
+        &mut self,
+        title: impl Into<String>,
+        subtitle: impl Into<String>,
+        icon_name: impl Into<String>,
+    ) {
+        self.tooltip_title = title.into();
+        self.tooltip_subtitle = subtitle.into();
+        self.tooltip_icon_name = icon_name.into();
+        self.tooltip_icon_pixmap.clear();
+        self.tooltip_markup = false;
+    }
+
+    /// Sets the tooltip title, keeping the rest of the tooltip.
+    ///
+    /// The title is sanitized as markup if the tooltip was set with `set_tooltip_rich()`.
+    pub fn set_tooltip_title(&mut self, title: &str) {
+        self.tooltip_title = if self.tooltip_markup {
+            markup::sanitize(title)
+        } else {
+            title.to_string()
+        };
+    }
+
+    /// Sets the tooltip subtitle, keeping the rest of the tooltip.
+    ///
+    /// The subtitle is sanitized as markup if the tooltip was set with `set_tooltip_rich()`.
+    pub fn set_tooltip_subtitle(&mut self, subtitle: &str) {
+        self.tooltip_subtitle = if self.tooltip_markup {
+            markup::sanitize(subtitle)
+        } else {
+            subtitle.to_string()
+        };
+    }
+
+    /// Sets the tooltip title and subtitle from markup, keeping the tooltip icon name but
+    /// removing the tooltip icon pixmap.
+    ///
+    /// Both are sanitized down to the tags supported by `tray::markup`.
+    pub fn set_tooltip_rich(&mut self, title_markup: &str, body_markup: &str) {
+        self.tooltip_title = markup::sanitize(title_markup);
+        self.tooltip_subtitle = markup::sanitize(body_markup);
+        self.tooltip_icon_pixmap.clear();
+        self.tooltip_markup = true;
+    }
+
+    /// Builds the tooltip sent to the host.
+    ///
+    /// The specification only allows markup in the description, so a markup title is
+    /// sent as plain text. A plain-text subtitle is escaped so it is never rendered as
+    /// markup.
+    pub fn tooltip(&self) -> ksni::ToolTip {
+        let (title, description) = if self.tooltip_markup {
+            (
+                markup::to_plain_text(&self.tooltip_title),
+                self.tooltip_subtitle.clone(),
+            )
+        } else {
+            (
+                self.tooltip_title.clone(),
+                markup::escape(&self.tooltip_subtitle),
+            )
+        };
+
+        ksni::ToolTip {
+            icon_name: self.tooltip_icon_name.clone(),
+            icon_pixmap: self.tooltip_icon_pixmap.clone(),
+            title,
+            description,
+        }
+    }
+
+    /// Parses a tray state from a TOML document.
+    ///
+    /// Missing fields fall back to their default values. Icon pixmaps and the event
+    /// channel are never read from TOML.
+    ///
+    /// # Example
+    ///
+    /// ```toml
+    /// tray_id = "my_app"
+    /// title = "My Application"
+    ///
+    /// [[menu]]
+    /// type = "standard"
+    /// id = "quit"
+    /// label = "Quit"
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn import_from_toml(s: &str) -> Result<Self> {
+        Ok(toml::from_str(s)?)
+    }
+
+    /// Computes a hash of all state that is visible to the system tray host.
+    ///
+    /// Used to detect whether anything changed since the last refresh.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.icon_name.hash(&mut hasher);
+        self.icon_theme_path.hash(&mut hasher);
+        for icon in &self.icon_pixmap {
+            icon.width.hash(&mut hasher);
+            icon.height.hash(&mut hasher);
+            icon.data.hash(&mut hasher);
+        }
+        for icon in &self.overlay_icon_pixmap {
+            icon.width.hash(&mut hasher);
+            icon.height.hash(&mut hasher);
+            icon.data.hash(&mut hasher);
+        }
+        self.icon_dpi_scale.to_bits().hash(&mut hasher);
+        self.title().hash(&mut hasher);
+        self.tooltip_title.hash(&mut hasher);
+        self.tooltip_subtitle.hash(&mut hasher);
+        self.tooltip_icon_name.hash(&mut hasher);
+        for icon in &self.tooltip_icon_pixmap {
+            icon.width.hash(&mut hasher);
+            icon.height.hash(&mut hasher);
+            icon.data.hash(&mut hasher);
+        }
+        self.tooltip_markup.hash(&mut hasher);
+        self.tray_id.hash(&mut hasher);
+        self.status.hash(&mut hasher);
+        self.tray_enabled.hash(&mut hasher);
+        self.window_id.hash(&mut hasher);
+        self.text_direction.hash(&mut hasher);
+        self.menu.hash(&mut hasher);
+        self.menu_generation.hash(&mut hasher);
+        self.pinned_item_ids.hash(&mut hasher);
+        self.use_path_ids.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Returns whether the state changed since `last_hash` was recorded, and records
+    /// the current [`content_hash`](Self::content_hash) in it.
+    ///
+    /// A `last_hash` of `None` counts as changed, e.g. before the first refresh.
+    pub fn update_content_hash(&self, last_hash: &mut Option<u64>) -> bool {
+        let hash = self.content_hash();
+        last_hash.replace(hash) != Some(hash)
+    }
+
+    /// Returns the `WindowId` reported to the host, 0 if no window is set or its ID does
+    /// not fit the 32-bit property.
+    pub fn sni_window_id(&self) -> i32 {
+        self.window_id
+            .and_then(|window_id| i32::try_from(window_id).ok())
+            .filter(|window_id| *window_id > 0)
+            .unwrap_or(0)
+    }
+
+    /// Returns the icon pixmaps ordered by preference.
+    ///
+    /// ksni does not pass the icon size requested by the host, so all pixmaps are
+    /// always exposed and the host picks one. The pixmap nearest to the default icon
+    /// size multiplied by `icon_dpi_scale` comes first, for hosts that take the first.
+    pub fn preferred_icon_pixmaps(&self) -> Vec<ksni::Icon> {
+        let size = (icon::DEFAULT_ICON_SIZE as f32 * self.icon_dpi_scale).round() as i32;
+        let mut pixmaps = self.icon_pixmap.clone();
+        icon::order_by_preferred_size(&mut pixmaps, size);
+        pixmaps
+    }
+
+    /// Enables or disables the whole tray.
+    ///
+    /// Disabling sets the status to passive, which hides the icon on hosts that respect
+    /// it, and disables all menu items. Re-enabling restores the previous status.
+    pub fn set_tray_enabled(&mut self, enabled: bool) {
+        if enabled == self.tray_enabled {
+            return;
+        }
+
+        if enabled {
+            self.status = self.status_before_disable;
+        } else {
+            self.status_before_disable = self.status;
+            self.status = ksni::Status::Passive;
+        }
+        self.tray_enabled = enabled;
+    }
+
+    /// Sets the activation cooldown for a menu item.
+    ///
+    /// A zero duration removes the cooldown.
+    pub fn set_activation_cooldown(&mut self, id: &str, cooldown: Duration) {
+        if cooldown.is_zero() {
+            self.activation_cooldowns.remove(id);
+            self.last_activation_times.remove(id);
+        } else {
+            self.activation_cooldowns.insert(id.to_string(), cooldown);
+        }
+    }
+
+    /// Records an activation of a menu item, respecting its cooldown.
+    ///
+    /// Returns `false` if the item was already activated less than its cooldown ago,
+    /// in which case the activation should be swallowed.
+    pub fn try_activate(&mut self, id: &str) -> bool {
+        let Some(cooldown) = self.activation_cooldowns.get(id) else {
+            return true;
+        };
+
+        let now = Instant::now();
+        if let Some(last) = self.last_activation_times.get(id)
+            && now.duration_since(*last) < *cooldown
+        {
+            return false;
+        }
+        self.last_activation_times.insert(id.to_string(), now);
+        true
+    }
+
+    /// Finds a menu item by the ID emitted in `TrayEvent::MenuActivated`.
+    ///
+    /// With `use_path_ids` enabled the emitted ID is prefixed by the labels of the
+    /// enclosing submenus, each followed by a slash, so the whole path is matched against
+    /// the menu tree. IDs and labels may contain slashes themselves.
+    pub fn find_item_by_emitted_id(&self, emitted_id: &str) -> Option<&MenuItemData> {
+        if self.use_path_ids {
+            Self::find_item_by_path(&self.menu, emitted_id)
+        } else {
+            self.find_item(emitted_id)
+        }
+    }
+
+    /// Finds the item whose emitted path ID, relative to `items`, is `path`.
+    fn find_item_by_path<'a>(items: &'a [MenuItemData], path: &str) -> Option<&'a MenuItemData> {
+        items.iter().find_map(|item| match item {
+            MenuItemData::SubMenu { label, submenu, .. } => {
+                let rest = path.strip_prefix(label.as_str())?.strip_prefix('/')?;
+                Self::find_item_by_path(submenu, rest)
+            }
+            _ if item.id() == Some(path) => Some(item),
+            _ => None,
+        })
+    }
+
+    /// Finds a menu item by ID, searching submenus recursively.
+    pub fn find_item(&self, id: &str) -> Option<&MenuItemData> {
+        Self::find_item_recursive(&self.menu, id)
+    }
+
+    /// Returns `true` if a menu item with the given ID exists, searching submenus recursively.
+    pub fn contains_id(&self, id: &str) -> bool {
+        self.find_item(id).is_some()
+    }
+
+    /// Recursively searches through menu items to find an item by ID.
+    pub fn find_item_recursive<'a>(
+        items: &'a [MenuItemData],
         id: &str,
-    ) -> Option<bool> {
+    ) -> Option<&'a MenuItemData> {
         for menu_item in items {
-            match menu_item {
-                MenuItemData::Checkmark {
-                    id: item_id,
-                    checked,
-                    ..
-                } => {
-                    if item_id == id {
-                        *checked = !*checked;
-                        return Some(*checked);
+            if menu_item.id() == Some(id) {
+                return Some(menu_item);
+            }
+            if let MenuItemData::SubMenu { submenu, .. } = menu_item
+                && let Some(result) = Self::find_item_recursive(submenu, id)
+            {
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    /// Finds a menu item by ID for mutation, searching submenus recursively.
+    pub fn find_item_mut(&mut self, id: &str) -> Option<&mut MenuItemData> {
+        self.iter_items_mut().find(|item| item.id() == Some(id))
+    }
+
+    /// Returns a depth-first iterator over the menu items for mutation.
+    ///
+    /// The items of submenus are visited in place of the submenus themselves, which are
+    /// not yielded: a mutable reference to a submenu would alias the items inside it.
+    /// Use `find_submenu_mut()` to modify a submenu.
+    pub fn iter_items_mut(&mut self) -> impl Iterator<Item = &mut MenuItemData> + '_ {
+        Self::iter_slice_mut(&mut self.menu)
+    }
+
+    /// Returns a depth-first iterator over `items` for mutation, as `iter_items_mut()`
+    /// does over the whole menu.
+    fn iter_slice_mut(items: &mut [MenuItemData]) -> impl Iterator<Item = &mut MenuItemData> {
+        let mut stack = vec![items.iter_mut()];
+        std::iter::from_fn(move || {
+            while let Some(items) = stack.last_mut() {
+                match items.next() {
+                    Some(MenuItemData::SubMenu { submenu, .. }) => stack.push(submenu.iter_mut()),
+                    Some(item) => return Some(item),
+                    None => {
+                        stack.pop();
                     }
                 }
-                MenuItemData::SubMenu { submenu, .. } => {
-                    if let Some(result) = Self::find_and_toggle_checkmark_recursive(submenu, id) {
-                        return Some(result);
+            }
+            None
+        })
+    }
+
+    /// Finds a submenu by label, searching nested submenus recursively.
+    pub fn find_submenu(&self, label: &str) -> Option<&MenuItemData> {
+        Self::find_submenu_recursive(&self.menu, label)
+    }
+
+    /// Recursively searches through menu items to find a submenu by label.
+    pub fn find_submenu_recursive<'a>(
+        items: &'a [MenuItemData],
+        label: &str,
+    ) -> Option<&'a MenuItemData> {
+        for menu_item in items {
+            if let MenuItemData::SubMenu {
+                label: sub_label,
+                submenu,
+                ..
+            } = menu_item
+            {
+                if sub_label == label {
+                    return Some(menu_item);
+                }
+                if let Some(result) = Self::find_submenu_recursive(submenu, label) {
+                    return Some(result);
+                }
+            }
+        }
+        None
+    }
+
+    /// Finds the items of a submenu by label among `items`, without searching nested submenus.
+    pub fn find_submenu_mut<'a>(
+        items: &'a mut [MenuItemData],
+        label: &str,
+    ) -> Option<&'a mut Vec<MenuItemData>> {
+        items.iter_mut().find_map(|item| match item {
+            MenuItemData::SubMenu {
+                label: sub_label,
+                submenu,
+                ..
+            } if sub_label == label => Some(submenu),
+            _ => None,
+        })
+    }
+
+    /// Finds the items of a submenu by label, searching nested submenus recursively.
+    ///
+    /// Walks the menu depth-first like `iter_items_mut()`, which cannot be used here as
+    /// it yields the items of submenus instead of the submenus themselves.
+    pub fn find_submenu_items_mut(&mut self, label: &str) -> Option<&mut Vec<MenuItemData>> {
+        let mut stack = vec![self.menu.iter_mut()];
+        while let Some(items) = stack.last_mut() {
+            match items.next() {
+                Some(MenuItemData::SubMenu {
+                    label: sub_label,
+                    submenu,
+                    ..
+                }) => {
+                    if sub_label == label {
+                        return Some(submenu);
                     }
+                    stack.push(submenu.iter_mut());
+                }
+                Some(_) => {}
+                None => {
+                    stack.pop();
                 }
-                _ => {}
             }
         }
         None
     }
 
-    /// Finds a radio group by ID and selects the option at the given index.
+    /// Returns `true` if selecting `index` in the given radio group should not emit an event.
     ///
-    /// Returns the ID of the selected option if found, or None if not found.
-    pub fn find_and_select_radio(&mut self, group_id: &str, index: usize) -> Option<String> {
-        Self::find_and_select_radio_recursive(&mut self.menu, group_id, index)
+    /// This is the case when the group has re-select events disabled and the option at
+    /// `index` is already selected.
+    pub fn is_radio_reselect_suppressed(&self, group_id: &str, index: usize) -> bool {
+        matches!(
+            self.find_item(group_id),
+            Some(MenuItemData::RadioGroup {
+                selected,
+                emit_on_reselect: false,
+                ..
+            }) if *selected == index
+        )
+    }
+
+    /// Recursively searches through menu items to find a mutable item by ID.
+    #[deprecated(note = "use `find_item_mut()` or `iter_items_mut()` instead")]
+    pub fn find_item_mut_recursive<'a>(
+        items: &'a mut [MenuItemData],
+        id: &str,
+    ) -> Option<&'a mut MenuItemData> {
+        Self::iter_slice_mut(items).find(|item| item.id() == Some(id))
+    }
+
+    /// Recursively searches through menu items to find and toggle a checkmark.
+    #[deprecated(note = "use `find_and_toggle_checkmark()` or `iter_items_mut()` instead")]
+    pub fn find_and_toggle_checkmark_recursive(
+        items: &mut [MenuItemData],
+        id: &str,
+    ) -> Option<bool> {
+        Self::iter_slice_mut(items).find_map(|item| Self::toggle_checkmark(item, id))
     }
 
     /// Recursively searches through menu items to find and select a radio option.
+    #[deprecated(note = "use `find_and_select_radio()` or `iter_items_mut()` instead")]
     pub fn find_and_select_radio_recursive(
-        items: &mut Vec<MenuItemData>,
+        items: &mut [MenuItemData],
         group_id: &str,
         index: usize,
     ) -> Option<String> {
+        Self::iter_slice_mut(items).find_map(|item| Self::select_radio_in(item, group_id, index))
+    }
+
+    /// Finds a checkmark item by ID and toggles its state.
+    ///
+    /// Returns the new checked state if found, or None if not found.
+    pub fn find_and_toggle_checkmark(&mut self, id: &str) -> Option<bool> {
+        self.iter_items_mut()
+            .find_map(|item| Self::toggle_checkmark(item, id))
+    }
+
+    /// Toggles `item` if it is the checkmark with the given ID, returning its new state.
+    fn toggle_checkmark(item: &mut MenuItemData, id: &str) -> Option<bool> {
+        match item {
+            MenuItemData::Checkmark {
+                id: item_id,
+                checked,
+                ..
+            } if item_id == id => {
+                *checked = !*checked;
+                Some(*checked)
+            }
+            _ => None,
+        }
+    }
+
+    /// Finds a radio group by ID and selects the option at the given index.
+    ///
+    /// Returns the ID of the selected option if found, or None if not found.
+    pub fn find_and_select_radio(&mut self, group_id: &str, index: usize) -> Option<String> {
+        self.iter_items_mut()
+            .find_map(|item| Self::select_radio_in(item, group_id, index))
+    }
+
+    /// Selects the option at `index` if `item` is the radio group with the given ID,
+    /// returning the ID of the option.
+    fn select_radio_in(item: &mut MenuItemData, group_id: &str, index: usize) -> Option<String> {
+        match item {
+            MenuItemData::RadioGroup {
+                id,
+                selected,
+                options,
+                ..
+            } if id == group_id && index < options.len() => {
+                *selected = index;
+                Some(options[index].id.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Sets the state of a checkmark item by ID, searching submenus recursively.
+    ///
+    /// Returns whether the state changed, or `Error::ItemNotFound` if no checkmark has
+    /// this ID.
+    pub fn set_checkmark(&mut self, id: &str, checked: bool) -> Result<bool> {
+        match self.find_item_mut(id) {
+            Some(MenuItemData::Checkmark {
+                checked: item_checked,
+                ..
+            }) => {
+                let changed = *item_checked != checked;
+                *item_checked = checked;
+                Ok(changed)
+            }
+            _ => Err(Error::ItemNotFound { id: id.to_string() }),
+        }
+    }
+
+    /// Appends an option to a radio group by ID, searching submenus recursively.
+    ///
+    /// Returns `Error::ItemNotFound` if no radio group has this ID.
+    pub fn add_radio_option(&mut self, group_id: &str, option: RadioItemData) -> Result<()> {
+        match self.find_item_mut(group_id) {
+            Some(MenuItemData::RadioGroup { options, .. }) => {
+                options.push(option);
+                Ok(())
+            }
+            _ => Err(Error::ItemNotFound {
+                id: group_id.to_string(),
+            }),
+        }
+    }
+
+    /// Returns events describing the current state of every checkmark and radio group.
+    ///
+    /// Produces a `CheckmarkToggled` for each checkmark and a `RadioSelected` for each
+    /// radio group with a valid selection, in menu order, including submenu contents.
+    pub fn current_toggle_states(&self) -> Vec<TrayEvent> {
+        let mut events = Vec::new();
+        Self::collect_toggle_states_recursive(&self.menu, &mut events);
+        events
+    }
+
+    /// Recursively collects the current checkmark and radio states as events.
+    fn collect_toggle_states_recursive(items: &[MenuItemData], events: &mut Vec<TrayEvent>) {
         for menu_item in items {
             match menu_item {
+                MenuItemData::Checkmark { id, checked, .. } => {
+                    events.push(TrayEvent::CheckmarkToggled(id.clone(), *checked));
+                }
                 MenuItemData::RadioGroup {
                     id,
                     selected,
                     options,
+                    ..
                 } => {
-                    if id == group_id && index < options.len() {
-                        *selected = index;
-                        return Some(options[index].id.clone());
+                    if let Some(option) = options.get(*selected) {
+                        events.push(TrayEvent::RadioSelected(
+                            id.clone(),
+                            *selected,
+                            option.id.clone(),
+                        ));
                     }
                 }
                 MenuItemData::SubMenu { submenu, .. } => {
-                    if let Some(result) =
-                        Self::find_and_select_radio_recursive(submenu, group_id, index)
-                    {
-                        return Some(result);
-                    }
+                    Self::collect_toggle_states_recursive(submenu, events);
                 }
                 _ => {}
             }
         }
-        None
+    }
+
+    /// Finds all menu items whose label matches the given text.
+    ///
+    /// Returns the IDs of matching items in menu order. Items nested in submenus are
+    /// returned as path IDs prefixed by their parent submenu labels (e.g. `"File/open"`),
+    /// radio options as `"group_id/option_id"`, and submenus as their label path.
+    ///
+    /// When `exact` is false, the match is a case-insensitive substring search.
+    pub fn find_items_by_label(&self, label: &str, exact: bool) -> Vec<String> {
+        let needle = if exact {
+            label.to_string()
+        } else {
+            label.to_lowercase()
+        };
+        let mut results = Vec::new();
+        Self::find_items_by_label_recursive(&self.menu, &needle, exact, "", &mut results);
+        results
+    }
+
+    /// Recursively collects the path IDs of items whose label matches `needle`.
+    pub fn find_items_by_label_recursive(
+        items: &[MenuItemData],
+        needle: &str,
+        exact: bool,
+        prefix: &str,
+        results: &mut Vec<String>,
+    ) {
+        let matches = |candidate: &str| {
+            if exact {
+                candidate == needle
+            } else {
+                candidate.to_lowercase().contains(needle)
+            }
+        };
+
+        for menu_item in items {
+            match menu_item {
+                MenuItemData::Standard { id, label, .. }
+                | MenuItemData::Checkmark { id, label, .. } => {
+                    if matches(label) {
+                        results.push(format!("{}{}", prefix, id));
+                    }
+                }
+                MenuItemData::RadioGroup { id, options, .. } => {
+                    for option in options {
+                        if matches(&option.label) {
+                            results.push(format!("{}{}/{}", prefix, id, option.id));
+                        }
+                    }
+                }
+                MenuItemData::SubMenu { label, submenu, .. } => {
+                    let path = format!("{}{}", prefix, label);
+                    if matches(label) {
+                        results.push(path.clone());
+                    }
+                    Self::find_items_by_label_recursive(
+                        submenu,
+                        needle,
+                        exact,
+                        &format!("{}/", path),
+                        results,
+                    );
+                }
+                MenuItemData::Header { .. } | MenuItemData::Separator => {}
+            }
+        }
     }
 
     /// Builds the ksni menu structure from the internal menu data.
+    ///
+    /// Pinned items are placed first in pin order, followed by the remaining items
+    /// in insertion order.
     pub fn build_menu_items(&self) -> Vec<MenuItem<KsniTray>> {
-        self.menu
-            .iter()
+        self.ordered_menu_items()
             .map(|item| self.build_menu_item(item))
             .collect()
     }
 
+    /// Returns the top-level menu items in display order.
+    ///
+    /// Pinned items come first in pin order, followed by the remaining items in
+    /// insertion order.
+    pub fn ordered_menu_items(&self) -> impl Iterator<Item = &MenuItemData> {
+        let pinned = self.pinned_item_ids.iter().filter_map(|pinned_id| {
+            self.menu
+                .iter()
+                .find(|item| item.id() == Some(pinned_id.as_str()))
+        });
+        let rest = self.menu.iter().filter(|item| {
+            !item
+                .id()
+                .is_some_and(|id| self.pinned_item_ids.iter().any(|pinned_id| pinned_id == id))
+        });
+        pinned.chain(rest)
+    }
+
+    /// Describes the menu that `build_menu_items()` produces.
+    ///
+    /// Unlike the built ksni items, the description holds no closures and can be
+    /// inspected and compared directly.
+    pub fn describe_menu(&self) -> Vec<MenuItemDescription> {
+        self.ordered_menu_items()
+            .map(|item| self.describe_menu_item(item))
+            .collect()
+    }
+
+    /// Describes a single menu item, including its submenu items or radio options.
+    pub fn describe_menu_item(&self, item: &MenuItemData) -> MenuItemDescription {
+        let mut description = MenuItemDescription {
+            item_type: item.type_name(),
+            id: item.id().map(str::to_string),
+            label: item.label().unwrap_or_default().to_string(),
+            enabled: true,
+            visible: true,
+            checked: None,
+            selected: None,
+            children: Vec::new(),
+        };
+
+        match item {
+            MenuItemData::Standard {
+                enabled, visible, ..
+            } => {
+                description.enabled = *enabled && self.tray_enabled;
+                description.visible = *visible;
+            }
+            MenuItemData::Checkmark {
+                enabled,
+                visible,
+                checked,
+                ..
+            } => {
+                description.enabled = *enabled && self.tray_enabled;
+                description.visible = *visible;
+                description.checked = Some(*checked);
+            }
+            MenuItemData::RadioGroup {
+                selected, options, ..
+            } => {
+                description.selected = Some(*selected);
+                description.children = options
+                    .iter()
+                    .map(|option| MenuItemDescription {
+                        item_type: "radio_option",
+                        id: Some(option.id.clone()),
+                        label: option.label.clone(),
+                        enabled: option.enabled && self.tray_enabled,
+                        visible: option.visible,
+                        checked: None,
+                        selected: None,
+                        children: Vec::new(),
+                    })
+                    .collect();
+            }
+            MenuItemData::SubMenu {
+                enabled,
+                visible,
+                submenu,
+                ..
+            } => {
+                description.enabled = *enabled && self.tray_enabled;
+                description.visible = *visible;
+                description.children = submenu
+                    .iter()
+                    .map(|item| self.describe_menu_item(item))
+                    .collect();
+            }
+            MenuItemData::Header { .. } => description.enabled = false,
+            MenuItemData::Separator => {}
+        }
+
+        description
+    }
+
+    /// Appends an item to the top level of the menu.
+    ///
+    /// Returns `Error::DuplicateId`, leaving the menu unchanged, if an item with the same
+    /// ID is already in the menu.
+    pub fn push_item(&mut self, item: MenuItemData) -> Result<()> {
+        if let Some(id) = item.id()
+            && self.contains_id(id)
+        {
+            return Err(Error::DuplicateId { id: id.to_string() });
+        }
+        self.menu.push(item);
+        Ok(())
+    }
+
+    /// Pins a top-level menu item so it is always shown first.
+    ///
+    /// Returns `Error::ItemNotFound` if the item is not at the top level of the menu.
+    /// Pinning an already pinned item keeps its existing pin position.
+    pub fn pin_item(&mut self, id: &str) -> Result<()> {
+        if !self.menu.iter().any(|item| item.id() == Some(id)) {
+            return Err(Error::ItemNotFound { id: id.to_string() });
+        }
+        if !self.pinned_item_ids.iter().any(|pinned_id| pinned_id == id) {
+            self.pinned_item_ids.push(id.to_string());
+        }
+        Ok(())
+    }
+
+    /// Removes a menu item from the pin list.
+    ///
+    /// Returns `Error::ItemNotFound` if the item was not pinned.
+    pub fn unpin_item(&mut self, id: &str) -> Result<()> {
+        let len = self.pinned_item_ids.len();
+        self.pinned_item_ids.retain(|pinned_id| pinned_id != id);
+        if self.pinned_item_ids.len() == len {
+            return Err(Error::ItemNotFound { id: id.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Groups top-level menu items between two separators, without a submenu.
+    ///
+    /// All top-level items whose ID is in `item_ids` are removed from their positions and
+    /// reinserted, in menu order, at the position of the first matched item, with a
+    /// `Separator` before and after them. The grouped IDs are recorded in `menu_groups`
+    /// under `group_name`.
+    ///
+    /// Returns `Error::ItemNotFound`, leaving the menu unchanged, if none of the items
+    /// are at the top level of the menu.
+    pub fn group_menu_items(&mut self, group_name: &str, item_ids: &[&str]) -> Result<()> {
+        let in_group = |item: &MenuItemData| item.id().is_some_and(|id| item_ids.contains(&id));
+
+        let Some(start) = self.menu.iter().position(in_group) else {
+            return Err(Error::ItemNotFound {
+                id: item_ids.join(", "),
+            });
+        };
+
+        let (group, mut menu): (Vec<_>, Vec<_>) = std::mem::take(&mut self.menu)
+            .into_iter()
+            .partition(in_group);
+
+        let grouped_ids = group
+            .iter()
+            .filter_map(|item| item.id().map(str::to_string))
+            .collect();
+
+        let mut block = Vec::with_capacity(group.len() + 2);
+        block.push(MenuItemData::Separator);
+        block.extend(group);
+        block.push(MenuItemData::Separator);
+
+        // Every item before `start` stays in place, so the index is still valid.
+        menu.splice(start..start, block);
+        self.menu = menu;
+        self.menu_groups.insert(group_name.to_string(), grouped_ids);
+        Ok(())
+    }
+
+    /// Sets the display priority of a menu item by ID.
+    ///
+    /// Returns `Error::ItemNotFound` if no item with a priority has this ID.
+    pub fn set_item_priority(&mut self, id: &str, new_priority: i32) -> Result<()> {
+        match self.find_item_mut(id) {
+            Some(
+                MenuItemData::Standard { priority, .. }
+                | MenuItemData::Checkmark { priority, .. }
+                | MenuItemData::RadioGroup { priority, .. },
+            ) => {
+                *priority = new_priority;
+                Ok(())
+            }
+            _ => Err(Error::ItemNotFound { id: id.to_string() }),
+        }
+    }
+
+    /// Sorts the menu by descending priority, including submenu contents.
+    ///
+    /// The sort is stable, so items with equal priority keep their relative order.
+    pub fn sort_menu_by_priority(&mut self) {
+        Self::sort_by_priority_recursive(&mut self.menu);
+    }
+
+    /// Recursively sorts menu items by descending priority.
+    fn sort_by_priority_recursive(items: &mut [MenuItemData]) {
+        items.sort_by_key(|item| std::cmp::Reverse(item.priority()));
+        for item in items {
+            if let MenuItemData::SubMenu { submenu, .. } = item {
+                Self::sort_by_priority_recursive(submenu);
+            }
+        }
+    }
+
+    /// Returns the ID emitted for a standard item at the given submenu path.
+    pub fn emitted_id(&self, id: &str, path_prefix: &str) -> String {
+        if self.use_path_ids {
+            format!("{}{}", path_prefix, id)
+        } else {
+            id.to_string()
+        }
+    }
+
+    /// Sends an event to the node, counting it as dropped if no node listens anymore.
+    pub fn send_event(&self, event: TrayEvent) {
+        match self.event_sender {
+            Some(ref tx) => self.stats.send_event(tx, event),
+            None => TrayStats::increment(&self.stats.events_dropped),
+        }
+    }
+
+    /// Handles a click on a standard item, sending `MenuActivated` with `emitted_id`
+    /// unless the item is in its activation cooldown.
+    pub fn click_standard(&mut self, id: &str, emitted_id: &str) {
+        if self.try_activate(id) {
+            self.send_event(TrayEvent::MenuActivated(emitted_id.to_string()));
+        }
+    }
+
+    /// Handles a click on a checkmark, toggling it and sending `CheckmarkToggled`.
+    pub fn click_checkmark(&mut self, id: &str) {
+        if let Some(checked) = self.find_and_toggle_checkmark(id) {
+            self.send_event(TrayEvent::CheckmarkToggled(id.to_string(), checked));
+        }
+    }
+
+    /// Handles a click on a radio option, selecting it and sending `RadioSelected`
+    /// unless re-selection events are suppressed for the group.
+    pub fn click_radio(&mut self, group_id: &str, index: usize) {
+        if self.is_radio_reselect_suppressed(group_id, index) {
+            return;
+        }
+        if let Some(option_id) = self.find_and_select_radio(group_id, index) {
+            self.send_event(TrayEvent::RadioSelected(
+                group_id.to_string(),
+                index,
+                option_id,
+            ));
+        }
+    }
+
     /// Converts a single MenuItemData into a ksni MenuItem.
     pub fn build_menu_item(&self, item: &MenuItemData) -> MenuItem<KsniTray> {
+        self.build_menu_item_at(item, "")
+    }
+
+    /// Converts a MenuItemData located under the given submenu path into a ksni MenuItem.
+    ///
+    /// `path_prefix` holds the labels of the enclosing submenus, each followed by a slash.
+    /// It prefixes the emitted IDs of standard items when `use_path_ids` is enabled.
+    fn build_menu_item_at(&self, item: &MenuItemData, path_prefix: &str) -> MenuItem<KsniTray> {
         match item {
             MenuItemData::Standard {
                 id,
@@ -147,17 +1293,20 @@ impl TrayState {
                 icon_name,
                 enabled,
                 visible,
+                ..
             } => {
                 let id_clone = id.clone();
-                let sender = self.event_sender.clone();
+                let emitted_id = self.emitted_id(id, path_prefix);
+                let generation = self.menu_generation;
                 StandardItem {
                     label: label.clone(),
                     icon_name: icon_name.clone(),
-                    enabled: *enabled,
+                    enabled: *enabled && self.tray_enabled,
                     visible: *visible,
-                    activate: Box::new(move |_this: &mut KsniTray| {
-                        if let Some(ref tx) = sender {
-                            let _ = tx.send(TrayEvent::MenuActivated(id_clone.clone()));
+                    activate: Box::new(move |this: &mut KsniTray| {
+                        let mut state = this.state.lock().unwrap();
+                        if state.is_current_menu(generation) {
+                            state.click_standard(&id_clone, &emitted_id);
                         }
                     }),
                     ..Default::default()
@@ -171,23 +1320,20 @@ impl TrayState {
                 enabled,
                 visible,
                 checked,
+                ..
             } => {
                 let id_clone = id.clone();
-                let sender = self.event_sender.clone();
+                let generation = self.menu_generation;
                 CheckmarkItem {
                     label: label.clone(),
                     icon_name: icon_name.clone(),
-                    enabled: *enabled,
+                    enabled: *enabled && self.tray_enabled,
                     visible: *visible,
                     checked: *checked,
                     activate: Box::new(move |this: &mut KsniTray| {
-                        let new_checked = {
-                            let mut state = this.state.lock().unwrap();
-                            state.find_and_toggle_checkmark(&id_clone)
-                        };
-
-                        if let (Some(tx), Some(checked)) = (&sender, new_checked) {
-                            let _ = tx.send(TrayEvent::CheckmarkToggled(id_clone.clone(), checked));
+                        let mut state = this.state.lock().unwrap();
+                        if state.is_current_menu(generation) {
+                            state.click_checkmark(&id_clone);
                         }
                     }),
                     ..Default::default()
@@ -198,20 +1344,16 @@ impl TrayState {
                 id,
                 selected,
                 options,
+                ..
             } => {
                 let id_clone = id.clone();
-                let sender = self.event_sender.clone();
+                let generation = self.menu_generation;
                 RadioGroup {
                     selected: *selected,
                     select: Box::new(move |this: &mut KsniTray, index| {
-                        let option_id = {
-                            let mut state = this.state.lock().unwrap();
-                            state.find_and_select_radio(&id_clone, index)
-                        };
-
-                        if let (Some(tx), Some(opt_id)) = (&sender, option_id) {
-                            let _ =
-                                tx.send(TrayEvent::RadioSelected(id_clone.clone(), index, opt_id));
+                        let mut state = this.state.lock().unwrap();
+                        if state.is_current_menu(generation) {
+                            state.click_radio(&id_clone, index);
                         }
                     }),
                     options: options
@@ -219,7 +1361,7 @@ impl TrayState {
                         .map(|opt| RadioItem {
                             label: opt.label.clone(),
                             icon_name: opt.icon_name.clone(),
-                            enabled: opt.enabled,
+                            enabled: opt.enabled && self.tray_enabled,
                             visible: opt.visible,
                             ..Default::default()
                         })
@@ -234,15 +1376,25 @@ impl TrayState {
                 enabled,
                 visible,
                 submenu,
+                ..
             } => SubMenu {
                 label: label.clone(),
                 icon_name: icon_name.clone(),
-                enabled: *enabled,
+                enabled: *enabled && self.tray_enabled,
                 visible: *visible,
-                submenu: submenu
-                    .iter()
-                    .map(|item| self.build_menu_item(item))
-                    .collect(),
+                submenu: {
+                    let child_prefix = format!("{}{}/", path_prefix, label);
+                    submenu
+                        .iter()
+                        .map(|item| self.build_menu_item_at(item, &child_prefix))
+                        .collect()
+                },
+                ..Default::default()
+            }
+            .into(),
+            MenuItemData::Header { label } => StandardItem {
+                label: label.clone(),
+                enabled: false,
                 ..Default::default()
             }
             .into(),
@@ -0,0 +1,70 @@
+//! Runtime statistics.
+//!
+//! This module contains the counters used to diagnose tray performance. All counters
+//! are updated with relaxed atomic operations, so keeping them costs next to nothing
+//! when nobody reads them.
+
+use crate::tray::event::TrayEvent;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+
+/// Runtime counters of a tray, shared between the Godot node and the ksni service.
+#[derive(Debug, Default)]
+pub struct TrayStats {
+    /// Number of changes pushed to the host over D-Bus.
+    pub update_pushes: AtomicU64,
+    /// Number of times the ksni menu was rebuilt from the tray state.
+    pub menu_rebuilds: AtomicU64,
+    /// Number of events sent into the event channel.
+    pub events_sent: AtomicU64,
+    /// Number of events taken from the event channel by the node.
+    pub events_received: AtomicU64,
+    /// Number of events emitted as signals.
+    pub events_emitted: AtomicU64,
+    /// Number of events discarded, by debouncing or because the node was gone.
+    pub events_dropped: AtomicU64,
+    /// Duration of the last update push, in microseconds.
+    pub last_update_usec: AtomicU64,
+    /// Number of images converted into icon pixmaps.
+    pub icon_conversions: AtomicU64,
+}
+
+impl TrayStats {
+    /// Increments a counter by one.
+    pub fn increment(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Sends an event into the event channel, counting it as sent or dropped.
+    pub fn send_event(&self, sender: &Sender<TrayEvent>, event: TrayEvent) {
+        if sender.send(event).is_ok() {
+            Self::increment(&self.events_sent);
+        } else {
+            Self::increment(&self.events_dropped);
+            log::debug!("Dropped tray event: the receiving node is gone");
+        }
+    }
+
+    /// Returns the number of events sent but not yet taken by the node.
+    pub fn channel_backlog(&self) -> u64 {
+        let sent = self.events_sent.load(Ordering::Relaxed);
+        let received = self.events_received.load(Ordering::Relaxed);
+        sent.saturating_sub(received)
+    }
+
+    /// Resets all counters to zero.
+    pub fn reset(&self) {
+        for counter in [
+            &self.update_pushes,
+            &self.menu_rebuilds,
+            &self.events_sent,
+            &self.events_received,
+            &self.events_emitted,
+            &self.events_dropped,
+            &self.last_update_usec,
+            &self.icon_conversions,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! Stand-ins for the tray service on platforms without StatusNotifierItem trays.
+//!
+//! Spawning always fails with `Error::Unsupported` there, so no tray service ever runs
+//! and these types cannot be constructed. They only mirror the parts of
+//! `ksni::blocking` used by the crate, so the code holding a handle compiles unchanged.
+
+use std::convert::Infallible;
+use std::marker::PhantomData;
+
+/// Handle to a running tray service, which never exists on this platform.
+pub struct Handle<T>(Infallible, PhantomData<T>);
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        match self.0 {}
+    }
+}
+
+impl<T> Handle<T> {
+    /// Updates the tray state and pushes it to the host.
+    pub fn update<R, F: FnOnce(&mut T) -> R>(&self, _f: F) -> Option<R> {
+        match self.0 {}
+    }
+
+    /// Shuts the tray service down.
+    pub fn shutdown(&self) -> ShutdownAwaiter {
+        match self.0 {}
+    }
+
+    /// Returns whether the tray service was shut down.
+    pub fn is_closed(&self) -> bool {
+        match self.0 {}
+    }
+}
+
+/// Waits for a tray service to finish shutting down.
+pub struct ShutdownAwaiter(Infallible);
+
+impl ShutdownAwaiter {
+    /// Blocks until the tray service is shut down.
+    pub fn wait(self) {
+        match self.0 {}
+    }
+}
@@ -0,0 +1,120 @@
+//! Detection of the StatusNotifierWatcher.
+//!
+//! Trays can only be shown when a StatusNotifierWatcher runs on the session bus. Its
+//! absence is the most common reason for a tray not showing up, e.g. on GNOME without
+//! the AppIndicator extension, so it is checked before spawning to report it clearly.
+
+use crate::error::{Error, Result};
+use std::sync::mpsc::{RecvTimeoutError, channel};
+use std::time::Duration;
+
+/// Well-known D-Bus name of the StatusNotifierWatcher.
+pub const WATCHER_NAME: &str = "org.kde.StatusNotifierWatcher";
+
+/// Object path of the StatusNotifierWatcher.
+pub const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+
+/// Watcher property read by [`preferred_icon_size`].
+pub const ICON_SIZE_PROPERTY: &str = "IconSize";
+
+/// Returns whether a StatusNotifierWatcher owns its name on the session bus.
+///
+/// Returns `None` if the session bus cannot be reached, or on platforms without D-Bus,
+/// leaving the error to the spawn itself.
+pub fn is_present() -> Option<bool> {
+    #[cfg(target_os = "linux")]
+    {
+        let connection = zbus::blocking::Connection::session().ok()?;
+        let dbus = zbus::blocking::fdo::DBusProxy::new(&connection).ok()?;
+        let name = zbus::names::BusName::try_from(WATCHER_NAME).ok()?;
+        dbus.name_has_owner(name).ok()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// Like [`is_present`], but gives up after `timeout`, e.g. when the bus daemon hangs.
+///
+/// The check runs on its own thread, which is left to finish in the background when it
+/// times out. Returns `Error::Timeout` in that case.
+pub fn is_present_within(timeout: Duration) -> Result<Option<bool>> {
+    within(timeout, is_present)
+}
+
+/// Asks the StatusNotifierWatcher for the icon size it recommends, in pixels.
+///
+/// The size is read from the integer `IconSize` property of the watcher interface. The
+/// StatusNotifierItem specification does not define it, and the watchers of Plasma and
+/// the GNOME AppIndicator extension do not publish it, so this mostly returns `None`,
+/// like when there is no watcher or no session bus. A zero or negative size also
+/// counts as unavailable.
+pub fn preferred_icon_size() -> Option<i32> {
+    #[cfg(target_os = "linux")]
+    {
+        use zbus::zvariant::Value;
+
+        let connection = zbus::blocking::Connection::session().ok()?;
+        let properties = zbus::blocking::fdo::PropertiesProxy::builder(&connection)
+            .destination(WATCHER_NAME)
+            .ok()?
+            .path(WATCHER_PATH)
+            .ok()?
+            .build()
+            .ok()?;
+        let interface = zbus::names::InterfaceName::try_from(WATCHER_NAME).ok()?;
+        let size = match *properties.get(interface, ICON_SIZE_PROPERTY).ok()? {
+            Value::U8(size) => i64::from(size),
+            Value::I16(size) => i64::from(size),
+            Value::U16(size) => i64::from(size),
+            Value::I32(size) => i64::from(size),
+            Value::U32(size) => i64::from(size),
+            Value::I64(size) => size,
+            _ => return None,
+        };
+        i32::try_from(size).ok().filter(|size| *size > 0)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// Like [`preferred_icon_size`], but gives up after `timeout`, as [`is_present_within`]
+/// does.
+pub fn preferred_icon_size_within(timeout: Duration) -> Result<Option<i32>> {
+    within(timeout, preferred_icon_size)
+}
+
+/// Runs a blocking D-Bus query on its own thread, returning `Error::Timeout` if it does
+/// not finish within `timeout`.
+fn within<T: Send + 'static>(timeout: Duration, query: fn() -> Option<T>) -> Result<Option<T>> {
+    let (result_tx, result_rx) = channel();
+    std::thread::spawn(move || {
+        let _ = result_tx.send(query());
+    });
+    match result_rx.recv_timeout(timeout) {
+        Ok(result) => Ok(result),
+        Err(RecvTimeoutError::Timeout) => Err(Error::Timeout),
+        Err(RecvTimeoutError::Disconnected) => Ok(None),
+    }
+}
+
+/// Returns advice on getting a StatusNotifierWatcher for the desktop environment, as
+/// named in `XDG_CURRENT_DESKTOP` (e.g. `"ubuntu:GNOME"`).
+pub fn missing_watcher_hint(current_desktop: &str) -> &'static str {
+    let is_desktop = |name: &str| {
+        current_desktop
+            .split(':')
+            .any(|desktop| desktop.eq_ignore_ascii_case(name))
+    };
+
+    if is_desktop("GNOME") {
+        "on GNOME install the AppIndicator and KStatusNotifierItem Support extension"
+    } else if is_desktop("KDE") {
+        "make sure the System Tray widget is on a Plasma panel"
+    } else {
+        "start a panel or system tray that supports StatusNotifierItem"
+    }
+}
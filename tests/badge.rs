@@ -0,0 +1,29 @@
+//! Tests for rendering notification badges.
+
+use godot_ksni::tray::icon::{self, MAX_BADGE_COUNT};
+
+/// Returns the ARGB32 alpha and red values of a pixel.
+fn alpha_and_red(badge: &ksni::Icon, x: i32, y: i32) -> (u8, u8) {
+    let offset = ((y * badge.width + x) * 4) as usize;
+    (badge.data[offset], badge.data[offset + 1])
+}
+
+#[test]
+fn badge_is_a_circle_with_a_white_count() {
+    let badge = icon::render_badge(1, 22);
+    assert_eq!((badge.width, badge.height), (22, 22));
+    assert_eq!(badge.data.len(), 22 * 22 * 4);
+
+    // Transparent corners, opaque red at the edge of the circle, white in the glyph
+    assert_eq!(alpha_and_red(&badge, 0, 0).0, 0);
+    assert_eq!(alpha_and_red(&badge, 11, 2), (255, 220));
+    let center = (0..22).any(|x| alpha_and_red(&badge, x, 11) == (255, 255));
+    assert!(center, "the count is not drawn");
+}
+
+#[test]
+fn large_counts_are_capped() {
+    let capped = icon::render_badge(MAX_BADGE_COUNT + 1, 22);
+    assert_eq!(icon::render_badge(12_345, 22).data, capped.data);
+    assert_ne!(icon::render_badge(MAX_BADGE_COUNT, 22).data, capped.data);
+}
@@ -0,0 +1,39 @@
+//! Helpers shared by the D-Bus integration tests.
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+
+/// A `dbus-daemon` session bus owned by the test, killed when dropped.
+pub struct PrivateBus {
+    daemon: Child,
+    /// Address to connect to, as used in `DBUS_SESSION_BUS_ADDRESS`.
+    pub address: String,
+}
+
+impl PrivateBus {
+    /// Launches a private session bus, or returns `None` if `dbus-daemon` is unavailable.
+    pub fn launch() -> Option<Self> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+
+        let mut address = String::new();
+        let stdout = daemon.stdout.take()?;
+        BufReader::new(stdout).read_line(&mut address).ok()?;
+
+        Some(Self {
+            daemon,
+            address: address.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
@@ -0,0 +1,377 @@
+//! Integration tests for the ksni wiring against a private D-Bus session.
+//!
+//! The test launches its own `dbus-daemon`, serves a mock StatusNotifierWatcher on it,
+//! and spawns a `KsniTray` backed by a populated `TrayState`, all without Godot. It then
+//! acts as the tray host and dbusmenu client: it checks the exported properties and menu
+//! layout, sends activation events, and checks the `TrayEvent`s coming out of the channel.
+//! Finally it spawns the tray again from the same state, as `spawn_tray()` does after
+//! `shutdown_tray()`, and checks that the menu is kept and clicks reach the new channel.
+//!
+//! The test is skipped when `dbus-daemon` is not installed.
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::PrivateBus;
+use godot_ksni::{KsniTray, MenuItemData, RadioItemData, TrayEvent, TrayState};
+use ksni::blocking::TrayMethods;
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{Receiver, channel};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use zbus::blocking::{Connection, Proxy, connection};
+use zbus::zvariant::OwnedValue;
+
+const WATCHER_NAME: &str = "org.kde.StatusNotifierWatcher";
+const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+const SNI_PATH: &str = "/StatusNotifierItem";
+const SNI_INTERFACE: &str = "org.kde.StatusNotifierItem";
+const MENU_PATH: &str = "/MenuBar";
+const MENU_INTERFACE: &str = "com.canonical.dbusmenu";
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Minimal StatusNotifierWatcher that records registered items.
+struct MockWatcher {
+    registered_items: Arc<Mutex<Vec<String>>>,
+}
+
+#[zbus::interface(name = "org.kde.StatusNotifierWatcher")]
+impl MockWatcher {
+    fn register_status_notifier_item(&self, service: &str) {
+        self.registered_items
+            .lock()
+            .unwrap()
+            .push(service.to_string());
+    }
+
+    fn register_status_notifier_host(&self, _service: &str) {}
+
+    #[zbus(property)]
+    fn registered_status_notifier_items(&self) -> Vec<String> {
+        self.registered_items.lock().unwrap().clone()
+    }
+
+    #[zbus(property)]
+    fn is_status_notifier_host_registered(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn protocol_version(&self) -> i32 {
+        0
+    }
+}
+
+/// A decoded dbusmenu layout node.
+struct LayoutNode {
+    id: i32,
+    label: String,
+    children: Vec<LayoutNode>,
+}
+
+type LayoutTuple = (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>);
+
+fn decode_layout((id, properties, children): LayoutTuple) -> LayoutNode {
+    LayoutNode {
+        id,
+        label: properties
+            .get("label")
+            .and_then(|value| String::try_from(value.clone()).ok())
+            .unwrap_or_default(),
+        children: children
+            .into_iter()
+            .map(|child| decode_layout(child.try_into().expect("child should be a layout")))
+            .collect(),
+    }
+}
+
+fn find_by_label<'a>(node: &'a LayoutNode, label: &str) -> Option<&'a LayoutNode> {
+    if node.label == label {
+        return Some(node);
+    }
+    node.children
+        .iter()
+        .find_map(|child| find_by_label(child, label))
+}
+
+fn test_menu() -> Vec<MenuItemData> {
+    vec![
+        MenuItemData::Standard {
+            id: "open".to_string(),
+            label: "Open".to_string(),
+            icon_name: String::new(),
+            enabled: true,
+            visible: true,
+            priority: 0,
+            suppress_global: false,
+        },
+        MenuItemData::Checkmark {
+            id: "autostart".to_string(),
+            label: "Start on Boot".to_string(),
+            icon_name: String::new(),
+            enabled: true,
+            visible: true,
+            checked: false,
+            priority: 0,
+        },
+        MenuItemData::RadioGroup {
+            id: "theme".to_string(),
+            selected: 0,
+            options: vec![
+                RadioItemData {
+                    id: "light".to_string(),
+                    label: "Light".to_string(),
+                    icon_name: String::new(),
+                    enabled: true,
+                    visible: true,
+                },
+                RadioItemData {
+                    id: "dark".to_string(),
+                    label: "Dark".to_string(),
+                    icon_name: String::new(),
+                    enabled: true,
+                    visible: true,
+                },
+            ],
+            emit_on_reselect: true,
+            priority: 0,
+        },
+        MenuItemData::Separator,
+        MenuItemData::SubMenu {
+            label: "Settings".to_string(),
+            icon_name: String::new(),
+            enabled: true,
+            visible: true,
+            submenu: vec![MenuItemData::Standard {
+                id: "prefs".to_string(),
+                label: "Preferences".to_string(),
+                icon_name: String::new(),
+                enabled: true,
+                visible: true,
+                priority: 0,
+                suppress_global: false,
+            }],
+            priority: 0,
+        },
+    ]
+}
+
+/// Waits for the next event, skipping `MenuAboutToShow` sent while the host reads the menu.
+fn next_event(events: &Receiver<TrayEvent>) -> TrayEvent {
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match events.recv_timeout(remaining) {
+            Ok(TrayEvent::MenuAboutToShow) => continue,
+            Ok(event) => return event,
+            Err(e) => panic!("no tray event received: {}", e),
+        }
+    }
+}
+
+/// Waits until the watcher has seen `count` registrations and returns the last service.
+fn wait_for_registration(registered_items: &Mutex<Vec<String>>, count: usize) -> String {
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        let items = registered_items.lock().unwrap();
+        if items.len() >= count {
+            return items[count - 1].clone();
+        }
+        drop(items);
+        assert!(Instant::now() < deadline, "tray did not register");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn get_layout(menu: &Proxy) -> LayoutNode {
+    let (_revision, layout): (u32, LayoutTuple) = menu
+        .call("GetLayout", &(0_i32, -1_i32, Vec::<String>::new()))
+        .expect("layout should be readable");
+    decode_layout(layout)
+}
+
+fn click(menu: &Proxy, id: i32) {
+    menu.call::<_, _, ()>(
+        "Event",
+        &(id, "clicked".to_string(), OwnedValue::from(0_u8), 0_u32),
+    )
+    .expect("menu click should succeed");
+}
+
+#[test]
+fn tray_round_trip_over_private_session_bus() {
+    let Some(bus) = PrivateBus::launch() else {
+        eprintln!("dbus-daemon not available, skipping D-Bus integration test");
+        return;
+    };
+
+    // SAFETY: this is the only test in this binary, and no other thread reads the
+    // environment before the bus address is set.
+    unsafe { std::env::set_var("DBUS_SESSION_BUS_ADDRESS", &bus.address) };
+
+    let registered_items = Arc::new(Mutex::new(Vec::new()));
+    let _watcher = connection::Builder::session()
+        .unwrap()
+        .serve_at(
+            WATCHER_PATH,
+            MockWatcher {
+                registered_items: registered_items.clone(),
+            },
+        )
+        .unwrap()
+        .name(WATCHER_NAME)
+        .unwrap()
+        .build()
+        .expect("mock watcher should connect");
+
+    let (tx, events) = channel();
+    let mut state = TrayState::new("integration_tray".to_string());
+    state.set_title_base("Integration Tray");
+    state.set_tooltip("Tooltip title", "Tooltip subtitle", "");
+    state.set_menu(test_menu());
+    state.category = ksni::Category::Communications;
+    state.event_sender = Some(tx);
+    let state = Arc::new(Mutex::new(state));
+
+    let handle = KsniTray {
+        state: state.clone(),
+    }
+    .spawn()
+    .expect("tray should spawn");
+
+    // Registration
+    let service = wait_for_registration(&registered_items, 1);
+
+    let client = Connection::session().expect("client should connect");
+
+    // Exported properties
+    let sni = Proxy::new(&client, service.as_str(), SNI_PATH, SNI_INTERFACE).unwrap();
+    assert_eq!(
+        sni.get_property::<String>("Id").unwrap(),
+        "integration_tray"
+    );
+    assert_eq!(
+        sni.get_property::<String>("Title").unwrap(),
+        "Integration Tray"
+    );
+    assert_eq!(sni.get_property::<String>("Status").unwrap(), "Active");
+    assert_eq!(
+        sni.get_property::<String>("Category").unwrap(),
+        "Communications"
+    );
+    assert_eq!(
+        sni.get_property::<String>("IconName").unwrap(),
+        "application-x-executable"
+    );
+    let tool_tip: (String, Vec<(i32, i32, Vec<u8>)>, String, String) =
+        sni.get_property("ToolTip").unwrap();
+    assert_eq!(tool_tip.2, "Tooltip title");
+    assert_eq!(tool_tip.3, "Tooltip subtitle");
+
+    // Menu layout
+    let menu = Proxy::new(&client, service.as_str(), MENU_PATH, MENU_INTERFACE).unwrap();
+    let layout = get_layout(&menu);
+
+    let labels: Vec<&str> = layout
+        .children
+        .iter()
+        .map(|child| child.label.as_str())
+        .collect();
+    assert_eq!(
+        labels,
+        ["Open", "Start on Boot", "Light", "Dark", "", "Settings"]
+    );
+    let settings = find_by_label(&layout, "Settings").unwrap();
+    assert_eq!(settings.children.len(), 1);
+    assert_eq!(settings.children[0].label, "Preferences");
+
+    // Event round-trips
+    click(&menu, find_by_label(&layout, "Open").unwrap().id);
+    assert_eq!(
+        next_event(&events),
+        TrayEvent::MenuActivated("open".to_string())
+    );
+
+    click(&menu, find_by_label(&layout, "Start on Boot").unwrap().id);
+    assert_eq!(
+        next_event(&events),
+        TrayEvent::CheckmarkToggled("autostart".to_string(), true)
+    );
+    assert!(matches!(
+        state.lock().unwrap().find_item("autostart"),
+        Some(MenuItemData::Checkmark { checked: true, .. })
+    ));
+
+    click(&menu, find_by_label(&layout, "Dark").unwrap().id);
+    assert_eq!(
+        next_event(&events),
+        TrayEvent::RadioSelected("theme".to_string(), 1, "dark".to_string())
+    );
+
+    click(&menu, find_by_label(&layout, "Preferences").unwrap().id);
+    assert_eq!(
+        next_event(&events),
+        TrayEvent::MenuActivated("prefs".to_string())
+    );
+
+    sni.call::<_, _, ()>("Activate", &(10_i32, 20_i32))
+        .expect("activate should succeed");
+    assert_eq!(next_event(&events), TrayEvent::Activated(10, 20));
+
+    // Clicks after the node dropped its receiver are counted as dropped
+    drop(events);
+    let stats = state.lock().unwrap().stats.clone();
+    let dropped = stats.events_dropped.load(Ordering::Relaxed);
+    click(&menu, find_by_label(&layout, "Open").unwrap().id);
+    let deadline = Instant::now() + TIMEOUT;
+    while stats.events_dropped.load(Ordering::Relaxed) == dropped {
+        assert!(Instant::now() < deadline, "click was not handled");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    // Spawning again from the same state keeps the menu, and the menu closures send
+    // through the event sender of the new spawn, not the one they were built with.
+    handle.shutdown().wait();
+    let (tx, events) = channel();
+    state.lock().unwrap().event_sender = Some(tx);
+    let handle = KsniTray {
+        state: state.clone(),
+    }
+    .spawn()
+    .expect("tray should spawn again");
+
+    let service = wait_for_registration(&registered_items, 2);
+    let menu = Proxy::new(&client, service.as_str(), MENU_PATH, MENU_INTERFACE).unwrap();
+    let layout = get_layout(&menu);
+    assert_eq!(layout.children.len(), 6);
+    click(&menu, find_by_label(&layout, "Open").unwrap().id);
+    assert_eq!(
+        next_event(&events),
+        TrayEvent::MenuActivated("open".to_string())
+    );
+
+    // Menu closures hold no references to the state, so shutting down leaves none
+    // behind. The blocking ksni backend frees the stopped service, which holds one,
+    // only when its runtime next runs, so spawn and stop another tray to drive it.
+    handle.shutdown().wait();
+    drop(handle);
+    let weak_state = Arc::downgrade(&state);
+    drop(state);
+    KsniTray {
+        state: Arc::new(Mutex::new(TrayState::new("driver_tray".to_string()))),
+    }
+    .spawn()
+    .expect("second tray should spawn")
+    .shutdown()
+    .wait();
+    let deadline = Instant::now() + TIMEOUT;
+    while weak_state.upgrade().is_some() {
+        assert!(
+            Instant::now() < deadline,
+            "tray state leaked after shutdown"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
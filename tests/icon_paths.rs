@@ -0,0 +1,48 @@
+//! Tests for resolving icon resource paths before loading them.
+
+use godot_ksni::tray::icon::resolve_import_path;
+
+#[test]
+fn import_and_remap_files_resolve_to_their_source() {
+    assert_eq!(
+        resolve_import_path("res://icon.png.import"),
+        "res://icon.png"
+    );
+    assert_eq!(
+        resolve_import_path("res://icons/tray.svg.import"),
+        "res://icons/tray.svg"
+    );
+    assert_eq!(
+        resolve_import_path("res://icon.tres.remap"),
+        "res://icon.tres"
+    );
+    assert_eq!(
+        resolve_import_path("user://cache.png.import"),
+        "user://cache.png"
+    );
+}
+
+#[test]
+fn source_and_imported_paths_are_kept() {
+    for path in [
+        "res://icon.png",
+        "res://icon.svg",
+        "res://.godot/imported/icon.png-218a8f2b3041327d8a5756f3a245f83b.ctex",
+        "user://screenshot.webp",
+        "",
+    ] {
+        assert_eq!(resolve_import_path(path), path);
+    }
+}
+
+#[test]
+fn only_one_suffix_is_stripped() {
+    assert_eq!(
+        resolve_import_path("res://icon.png.import.import"),
+        "res://icon.png.import"
+    );
+    assert_eq!(
+        resolve_import_path("res://icon.png.remap.import"),
+        "res://icon.png.remap"
+    );
+}
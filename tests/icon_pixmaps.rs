@@ -0,0 +1,248 @@
+//! Tests for setting the icon from raw pixel data, and for converting float pixels.
+
+use godot_ksni::tray::icon::{self, PixelFormat};
+use godot_ksni::{Error, TrayState};
+
+fn pixmap(size: i32, data: Vec<u8>) -> ksni::Icon {
+    ksni::Icon {
+        width: size,
+        height: size,
+        data,
+    }
+}
+
+#[test]
+fn raw_pixmaps_are_stored_unchanged() {
+    let mut state = TrayState::new("pixmap_tray".to_string());
+    state.icon_name = "folder".to_string();
+
+    let small = pixmap(1, vec![0xff, 0x10, 0x20, 0x30]);
+    let large = pixmap(2, (0..16).collect());
+    state
+        .set_icon_argb(vec![small.clone(), large.clone()])
+        .unwrap();
+
+    assert!(state.icon_name.is_empty());
+    assert_eq!(state.icon_pixmap.len(), 2);
+    assert_eq!(state.icon_pixmap[0].data, small.data);
+    assert_eq!(state.icon_pixmap[1].data, large.data);
+}
+
+#[test]
+fn malformed_raw_pixmaps_leave_the_icon_unchanged() {
+    let mut state = TrayState::new("pixmap_tray".to_string());
+    state.icon_name = "folder".to_string();
+
+    let result = state.set_icon_argb(vec![pixmap(1, vec![0; 4]), pixmap(2, vec![0; 15])]);
+
+    assert!(matches!(
+        result,
+        Err(Error::InvalidIconData {
+            expected: 16,
+            got: 15
+        })
+    ));
+    assert_eq!(state.icon_name, "folder");
+    assert!(state.icon_pixmap.is_empty());
+}
+
+#[test]
+fn explicitly_set_icons_are_tracked() {
+    let mut state = TrayState::new("icon_tray".to_string());
+    assert!(!state.icon_set);
+
+    // Setting the default name on purpose still counts as choosing an icon
+    state.set_icon_name(godot_ksni::tray::state::DEFAULT_ICON_NAME);
+    assert!(state.icon_set);
+
+    state.reset_icon();
+    assert!(!state.icon_set);
+    state.set_icon(vec![pixmap(1, vec![0; 4])]);
+    assert!(state.icon_set);
+}
+
+#[test]
+fn padded_rgb_rows_are_packed_as_opaque_argb() {
+    // 2x2 RGB pixels with 2 bytes of padding after each row
+    let data = [
+        1, 2, 3, 4, 5, 6, 0, 0, //
+        7, 8, 9, 10, 11, 12, 0, 0,
+    ];
+    let argb = icon::pack_argb(2, 2, &data, PixelFormat::Rgb8, 8).unwrap();
+    assert_eq!(
+        argb,
+        [255, 1, 2, 3, 255, 4, 5, 6, 255, 7, 8, 9, 255, 10, 11, 12]
+    );
+
+    // The padding after the last row may be left out
+    let argb = icon::pack_argb(2, 2, &data[..14], PixelFormat::Rgb8, 8).unwrap();
+    assert_eq!(argb.len(), 16);
+}
+
+#[test]
+fn bgra_pixels_are_swizzled_to_argb() {
+    let argb = icon::pack_argb(1, 1, &[10, 20, 30, 40], PixelFormat::Bgra8, 0).unwrap();
+    assert_eq!(argb, [40, 30, 20, 10]);
+}
+
+#[test]
+fn inconsistent_stride_and_length_are_rejected() {
+    assert!(matches!(
+        icon::pack_argb(2, 2, &[0; 12], PixelFormat::Rgb8, 5),
+        Err(Error::InvalidIconStride {
+            stride: 5,
+            row_bytes: 6
+        })
+    ));
+    assert!(matches!(
+        icon::pack_argb(2, 2, &[0; 13], PixelFormat::Rgb8, 8),
+        Err(Error::InvalidIconData {
+            expected: 14,
+            got: 13
+        })
+    ));
+    assert!(matches!(
+        icon::pack_argb(2, 2, &[0; 17], PixelFormat::Rgb8, 8),
+        Err(Error::InvalidIconData {
+            expected: 16,
+            got: 17
+        })
+    ));
+}
+
+#[test]
+fn extreme_dimensions_are_rejected_without_overflow() {
+    let extremes = [
+        (0, 1),
+        (1, 0),
+        (-1, 1),
+        (1, -1),
+        (i32::MIN, i32::MIN),
+        (-46341, -46341),
+        (46341, 46341),
+        (i32::MAX, 1),
+        (1, i32::MAX),
+        (i32::MAX, i32::MAX),
+        (4097, 4096),
+    ];
+    for (width, height) in extremes {
+        for format in [PixelFormat::Rgba8, PixelFormat::Rgb8, PixelFormat::Bgra8] {
+            assert!(
+                matches!(
+                    icon::pack_argb(width, height, &[0; 16], format, 0),
+                    Err(Error::InvalidIconSize { .. })
+                ),
+                "{width}x{height} was accepted"
+            );
+        }
+
+        let mut state = TrayState::new("pixmap_tray".to_string());
+        assert!(matches!(
+            state.set_icon_rgba(width, height, &[0; 16]),
+            Err(Error::InvalidIconSize { .. })
+        ));
+        assert!(matches!(
+            state.set_icon_argb(vec![ksni::Icon {
+                width,
+                height,
+                data: vec![0; 16],
+            }]),
+            Err(Error::InvalidIconSize { .. })
+        ));
+        assert!(state.icon_pixmap.is_empty());
+    }
+}
+
+#[test]
+fn largest_allowed_icon_size_is_accepted() {
+    assert_eq!(icon::validate_size(4096, 4096).unwrap(), (4096, 4096));
+    assert_eq!(
+        icon::rgba_len(1, 4096 * 4096).unwrap(),
+        icon::MAX_ICON_PIXELS * 4
+    );
+    assert!(icon::validate_size(4096 * 4096 + 1, 1).is_err());
+}
+
+#[test]
+fn huge_strides_report_short_data() {
+    for stride in [usize::MAX, usize::MAX / 2, usize::MAX / 3] {
+        assert!(matches!(
+            icon::pack_argb(2, 3, &[0; 24], PixelFormat::Rgba8, stride),
+            Err(Error::InvalidIconData { got: 24, .. })
+        ));
+    }
+}
+
+#[test]
+fn grayscale_keeps_alpha_and_weights_channels() {
+    let mut argb = [128, 255, 0, 0, 255, 0, 255, 0, 0, 0, 0, 255];
+    icon::grayscale_argb(&mut argb);
+    assert_eq!(argb, [128, 76, 76, 76, 255, 150, 150, 150, 0, 29, 29, 29]);
+}
+
+#[test]
+fn opaque_conversion_forces_alpha() {
+    let mut rgba = [10, 20, 30, 0, 40, 50, 60, 128];
+    icon::rgba_to_argb_opaque(&mut rgba);
+    assert_eq!(rgba, [255, 10, 20, 30, 255, 40, 50, 60]);
+}
+
+#[test]
+fn gauges_of_invalid_sizes_are_rejected() {
+    let render = |size| icon::render_gauge(0.5, [0; 4], [255; 4], size, false);
+
+    for size in [0, -1, i32::MIN, 4097, i32::MAX] {
+        assert!(
+            matches!(render(size), Err(Error::InvalidIconSize { .. })),
+            "size {size}"
+        );
+    }
+    let gauge = render(4).unwrap();
+    assert_eq!(gauge.data.len(), icon::rgba_len(4, 4).unwrap());
+}
+
+#[test]
+fn padded_sizes_are_bounded_without_overflow() {
+    assert_eq!(icon::padded_size(16, 8, 4).unwrap(), (24, 16));
+    assert_eq!(icon::padded_size(16, 8, 0).unwrap(), (16, 8));
+
+    for (width, height, padding) in [
+        (16, 16, -1),
+        (16, 16, icon::MAX_ICON_PADDING + 1),
+        (16, 16, i32::MAX),
+        (i32::MAX, 16, 1),
+        (4096, 4096, 1),
+    ] {
+        assert!(
+            matches!(
+                icon::padded_size(width, height, padding),
+                Err(Error::InvalidIconSize { .. })
+            ),
+            "{width}x{height} padded by {padding}"
+        );
+    }
+}
+
+#[test]
+fn linear_values_are_encoded_as_srgb() {
+    let encode = |value: f32| icon::quantize(icon::linear_to_srgb(value));
+
+    assert_eq!(encode(0.0), 0);
+    assert_eq!(encode(0.5), 188);
+    assert_eq!(encode(1.0), 255);
+    assert_eq!(encode(4.0), 255);
+    assert_eq!(encode(-1.0), 0);
+    assert_eq!(encode(f32::INFINITY), 255);
+    assert_eq!(encode(f32::NAN), 0);
+}
+
+#[test]
+fn float_pixels_are_converted_with_linear_alpha() {
+    let pixel: Vec<u8> = [0.0f32, 0.5, 8.0, 0.5]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+
+    assert_eq!(icon::rgbaf_to_rgba8(&pixel, true), [0, 188, 255, 128]);
+    assert_eq!(icon::rgbaf_to_rgba8(&pixel, false), [0, 128, 255, 128]);
+}
@@ -0,0 +1,26 @@
+//! Tests for queueing log records for the main thread.
+
+use godot_ksni::tray::logging::{self, MAX_PENDING};
+use log::LevelFilter;
+
+#[test]
+fn the_queue_keeps_only_the_newest_records() {
+    logging::init();
+    logging::set_level(LevelFilter::Info);
+    assert_eq!(log::max_level(), LevelFilter::Info);
+
+    log::debug!("filtered out");
+    for index in 0..MAX_PENDING + 10 {
+        log::warn!("record {index}");
+    }
+
+    let records = logging::drain();
+    assert_eq!(records.len(), MAX_PENDING);
+    assert!(records[0].1.ends_with("record 10"), "{}", records[0].1);
+    assert!(
+        records[MAX_PENDING - 1]
+            .1
+            .ends_with(&format!("record {}", MAX_PENDING + 9))
+    );
+    assert!(logging::drain().is_empty());
+}
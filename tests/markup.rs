@@ -0,0 +1,110 @@
+//! Tests for tooltip markup escaping and sanitization.
+
+use godot_ksni::tray::markup::{escape, sanitize, to_plain_text};
+
+#[test]
+fn escape_neutralizes_markup() {
+    assert_eq!(escape("a < b && c > d"), "a &lt; b &amp;&amp; c &gt; d");
+    assert_eq!(escape("<b>bold</b>"), "&lt;b&gt;bold&lt;/b&gt;");
+}
+
+#[test]
+fn sanitize_keeps_whitelisted_tags() {
+    assert_eq!(
+        sanitize("<b>3</b> active<br><i>12 MB/s</i>"),
+        "<b>3</b> active<br/><i>12 MB/s</i>"
+    );
+    assert_eq!(sanitize("<B>x</B><BR />"), "<b>x</b><br/>");
+    assert_eq!(
+        sanitize("<img src=\"network-wired\">"),
+        "<img src=\"network-wired\"/>"
+    );
+    assert_eq!(
+        sanitize("<img width='9' src='dialog-ok' />"),
+        "<img src=\"dialog-ok\"/>"
+    );
+}
+
+#[test]
+fn sanitize_escapes_unknown_tags() {
+    assert_eq!(
+        sanitize("<script>alert(1)</script>"),
+        "&lt;script&gt;alert(1)&lt;/script&gt;"
+    );
+    assert_eq!(
+        sanitize("<a href=\"https://example.com\">x</a>"),
+        "&lt;a href=\"https://example.com\"&gt;x&lt;/a&gt;"
+    );
+    assert_eq!(sanitize("<b onclick=\"x\">y"), "&lt;b onclick=\"x\"&gt;y");
+    assert_eq!(
+        sanitize("<style>*{}</style>"),
+        "&lt;style&gt;*{}&lt;/style&gt;"
+    );
+}
+
+#[test]
+fn sanitize_rejects_image_paths_and_urls() {
+    assert_eq!(
+        sanitize("<img src=\"/etc/passwd\">"),
+        "&lt;img src=\"/etc/passwd\"&gt;"
+    );
+    assert_eq!(
+        sanitize("<img src=\"file:///tmp/x.png\">"),
+        "&lt;img src=\"file:///tmp/x.png\"&gt;"
+    );
+    assert_eq!(
+        sanitize("<img src=\"../icon\">"),
+        "&lt;img src=\"../icon\"&gt;"
+    );
+    assert_eq!(sanitize("<img>"), "&lt;img&gt;");
+    assert_eq!(sanitize("<img src=unquoted>"), "&lt;img src=unquoted&gt;");
+}
+
+#[test]
+fn sanitize_balances_tags() {
+    assert_eq!(sanitize("<b>open"), "<b>open</b>");
+    assert_eq!(sanitize("stray</b>"), "stray");
+    assert_eq!(sanitize("<b><i>x</b>y</i>"), "<b><i>x</i></b><i>y</i>");
+    assert_eq!(sanitize("<i><b><b>x"), "<i><b><b>x</b></b></i>");
+}
+
+#[test]
+fn sanitize_handles_broken_syntax() {
+    assert_eq!(sanitize("<<b>x</b>"), "&lt;<b>x</b>");
+    assert_eq!(sanitize("<b"), "&lt;b");
+    assert_eq!(sanitize("a > b"), "a &gt; b");
+    assert_eq!(sanitize("<"), "&lt;");
+    assert_eq!(sanitize("<>"), "&lt;&gt;");
+    assert_eq!(sanitize("</>"), "&lt;/&gt;");
+    assert_eq!(sanitize("<b\n>x"), "<b>x</b>");
+}
+
+#[test]
+fn sanitize_keeps_only_valid_entities() {
+    assert_eq!(
+        sanitize("&amp; &lt; &#60; &#x3C;"),
+        "&amp; &lt; &#60; &#x3C;"
+    );
+    assert_eq!(sanitize("R&D"), "R&amp;D");
+    assert_eq!(
+        sanitize("&bogus; &#0; &#xZZ;"),
+        "&amp;bogus; &amp;#0; &amp;#xZZ;"
+    );
+    assert_eq!(sanitize("&"), "&amp;");
+}
+
+#[test]
+fn sanitize_keeps_unicode_text() {
+    assert_eq!(sanitize("<b>日本語</b> 🎉"), "<b>日本語</b> 🎉");
+}
+
+#[test]
+fn plain_text_strips_markup() {
+    assert_eq!(
+        to_plain_text("<b>3</b> active<br><i>fast</i>"),
+        "3 active\nfast"
+    );
+    assert_eq!(to_plain_text("a &lt; b &amp; c"), "a < b & c");
+    assert_eq!(to_plain_text("<script>x</script>"), "<script>x</script>");
+    assert_eq!(to_plain_text("<img src=\"dialog-ok\">done"), "done");
+}
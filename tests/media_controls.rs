@@ -0,0 +1,77 @@
+//! Tests for the media player controls preset.
+
+use godot_ksni::MenuItemData;
+use godot_ksni::menu::media;
+
+fn ids(items: &[MenuItemData]) -> Vec<&str> {
+    items.iter().filter_map(MenuItemData::id).collect()
+}
+
+#[test]
+fn flags_select_controls_in_menu_order() {
+    assert_eq!(
+        ids(&media::items(media::ALL, str::to_string)),
+        [
+            "media_play_pause",
+            "media_previous",
+            "media_next",
+            "media_stop"
+        ]
+    );
+    assert_eq!(
+        ids(&media::items(media::NEXT | media::PREVIOUS, str::to_string)),
+        ["media_previous", "media_next"]
+    );
+    assert!(media::items(0, str::to_string).is_empty());
+}
+
+#[test]
+fn controls_are_told_apart_from_custom_items() {
+    let custom = MenuItemData::Standard {
+        id: "quit".to_string(),
+        label: "Quit".to_string(),
+        icon_name: String::new(),
+        enabled: true,
+        visible: true,
+        priority: 0,
+        suppress_global: false,
+    };
+    assert!(!media::is_control(&custom));
+    assert!(
+        media::items(media::ALL, str::to_string)
+            .iter()
+            .all(media::is_control)
+    );
+    assert_eq!(media::signal("media_stop"), Some("stop_pressed"));
+    assert_eq!(media::signal("quit"), None);
+}
+
+#[test]
+fn playback_state_flips_play_pause() {
+    let mut item = media::items(media::PLAY_PAUSE, str::to_string).remove(0);
+
+    media::set_playing(&mut item, true, str::to_string);
+    assert!(matches!(
+        &item,
+        MenuItemData::Standard { label, icon_name, .. }
+            if label == "Pause" && icon_name == "media-playback-pause"
+    ));
+
+    media::set_playing(&mut item, false, str::to_string);
+    assert!(matches!(
+        &item,
+        MenuItemData::Standard { label, icon_name, .. }
+            if label == "Play" && icon_name == "media-playback-start"
+    ));
+}
+
+#[test]
+fn labels_are_translated() {
+    let tr = |label: &str| format!("[{label}]");
+    let mut items = media::items(media::PLAY_PAUSE | media::STOP, tr);
+    let labels: Vec<_> = items.iter().filter_map(MenuItemData::label).collect();
+    assert_eq!(labels, ["[Play]", "[Stop]"]);
+
+    media::set_playing(&mut items[0], true, tr);
+    assert_eq!(items[0].label(), Some("[Pause]"));
+}
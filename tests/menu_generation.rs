@@ -0,0 +1,562 @@
+//! Tests for building the ksni menu, and for ignoring menu callbacks built from an
+//! outdated menu or arriving after the node was freed.
+
+use godot_ksni::{
+    Error, KsniTray, MenuItemData, MenuItemDescription, RadioItemData, TrayEvent, TrayState,
+};
+use ksni::MenuItem;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Barrier, Mutex};
+use std::thread;
+
+fn test_menu() -> Vec<MenuItemData> {
+    vec![
+        MenuItemData::Standard {
+            id: "open".to_string(),
+            label: "Open".to_string(),
+            icon_name: String::new(),
+            enabled: true,
+            visible: true,
+            priority: 0,
+            suppress_global: false,
+        },
+        MenuItemData::Checkmark {
+            id: "autostart".to_string(),
+            label: "Start on Boot".to_string(),
+            icon_name: String::new(),
+            enabled: true,
+            visible: true,
+            checked: false,
+            priority: 0,
+        },
+    ]
+}
+
+/// Calls the activation callback of a built standard or checkmark item, as ksni does
+/// when the host reports a click.
+fn click(item: &MenuItem<KsniTray>, tray: &mut KsniTray) {
+    match item {
+        MenuItem::Standard(item) => (item.activate)(tray),
+        MenuItem::Checkmark(item) => (item.activate)(tray),
+        _ => panic!("item is not clickable"),
+    }
+}
+
+fn is_checked(tray: &KsniTray, id: &str) -> bool {
+    matches!(
+        tray.state.lock().unwrap().find_item(id),
+        Some(MenuItemData::Checkmark { checked: true, .. })
+    )
+}
+
+fn radio_option(id: &str, visible: bool) -> RadioItemData {
+    RadioItemData {
+        id: id.to_string(),
+        label: id.to_uppercase(),
+        icon_name: String::new(),
+        enabled: true,
+        visible,
+    }
+}
+
+#[test]
+fn built_items_keep_their_kind_and_state() {
+    let mut state = TrayState::new("build_tray".to_string());
+    let mut menu = test_menu();
+    if let MenuItemData::Checkmark { checked, .. } = &mut menu[1] {
+        *checked = true;
+    }
+    menu.push(MenuItemData::RadioGroup {
+        id: "quality".to_string(),
+        selected: 1,
+        options: vec![radio_option("low", true), radio_option("high", false)],
+        emit_on_reselect: false,
+        priority: 0,
+    });
+    menu.push(MenuItemData::Separator);
+    menu.push(MenuItemData::Header {
+        label: "Recent".to_string(),
+    });
+    menu.push(MenuItemData::SubMenu {
+        label: "More".to_string(),
+        icon_name: String::new(),
+        enabled: true,
+        visible: false,
+        submenu: vec![MenuItemData::Standard {
+            id: "hidden".to_string(),
+            label: "Hidden".to_string(),
+            icon_name: String::new(),
+            enabled: true,
+            visible: false,
+            priority: 0,
+            suppress_global: false,
+        }],
+        priority: 0,
+    });
+    state.set_menu(menu);
+
+    let items = state.build_menu_items();
+    assert_eq!(items.len(), 6);
+
+    let MenuItem::Standard(open) = &items[0] else {
+        panic!("expected a standard item");
+    };
+    assert_eq!(open.label, "Open");
+    assert!(open.enabled && open.visible);
+
+    let MenuItem::Checkmark(autostart) = &items[1] else {
+        panic!("expected a checkmark item");
+    };
+    assert!(autostart.checked);
+
+    let MenuItem::RadioGroup(quality) = &items[2] else {
+        panic!("expected a radio group");
+    };
+    assert_eq!(quality.selected, 1);
+    let labels: Vec<_> = quality.options.iter().map(|o| o.label.as_str()).collect();
+    assert_eq!(labels, ["LOW", "HIGH"]);
+    assert!(quality.options[0].visible && !quality.options[1].visible);
+
+    assert!(matches!(items[3], MenuItem::Separator));
+
+    let MenuItem::Standard(header) = &items[4] else {
+        panic!("expected a header item");
+    };
+    assert_eq!(header.label, "Recent");
+    assert!(!header.enabled);
+
+    let MenuItem::SubMenu(more) = &items[5] else {
+        panic!("expected a submenu");
+    };
+    assert!(!more.visible);
+    let [MenuItem::Standard(hidden)] = more.submenu.as_slice() else {
+        panic!("expected one standard item in the submenu");
+    };
+    assert_eq!(hidden.label, "Hidden");
+    assert!(!hidden.visible);
+}
+
+fn description(item_type: &'static str, id: Option<&str>, label: &str) -> MenuItemDescription {
+    MenuItemDescription {
+        item_type,
+        id: id.map(str::to_string),
+        label: label.to_string(),
+        enabled: true,
+        visible: true,
+        checked: None,
+        selected: None,
+        children: Vec::new(),
+    }
+}
+
+#[test]
+fn descriptions_follow_the_built_menu() {
+    let mut state = TrayState::new("describe_tray".to_string());
+    let mut menu = test_menu();
+    menu.push(MenuItemData::SubMenu {
+        label: "Quality".to_string(),
+        icon_name: String::new(),
+        enabled: true,
+        visible: true,
+        submenu: vec![MenuItemData::RadioGroup {
+            id: "quality".to_string(),
+            selected: 1,
+            options: vec![radio_option("low", true), radio_option("high", false)],
+            emit_on_reselect: true,
+            priority: 0,
+        }],
+        priority: 0,
+    });
+    menu.push(MenuItemData::Separator);
+    state.set_menu(menu);
+    state.pin_item("autostart").unwrap();
+
+    let quality = MenuItemDescription {
+        selected: Some(1),
+        children: vec![
+            description("radio_option", Some("low"), "LOW"),
+            MenuItemDescription {
+                visible: false,
+                ..description("radio_option", Some("high"), "HIGH")
+            },
+        ],
+        ..description("radio_group", Some("quality"), "")
+    };
+    let submenu = MenuItemDescription {
+        children: vec![quality],
+        ..description("submenu", None, "Quality")
+    };
+    assert_eq!(
+        state.describe_menu(),
+        [
+            MenuItemDescription {
+                checked: Some(false),
+                ..description("checkmark", Some("autostart"), "Start on Boot")
+            },
+            description("standard", Some("open"), "Open"),
+            submenu.clone(),
+            description("separator", None, ""),
+        ]
+    );
+    assert_eq!(state.describe_menu().len(), state.build_menu_items().len());
+    assert_eq!(state.describe_menu_item(&state.menu[2]), submenu);
+}
+
+#[test]
+fn descriptions_of_a_disabled_tray_are_disabled() {
+    let mut state = TrayState::new("describe_tray".to_string());
+    state.set_menu(test_menu());
+    state.menu.push(MenuItemData::Header {
+        label: "Status".to_string(),
+    });
+    state.tray_enabled = false;
+
+    let enabled: Vec<_> = state.describe_menu().iter().map(|d| d.enabled).collect();
+    assert_eq!(enabled, [false, false, false]);
+}
+
+#[test]
+fn callbacks_of_a_cleared_menu_are_ignored() {
+    let (mut state, events) = TrayState::with_event_channel("generation_tray".to_string());
+    state.set_menu(test_menu());
+    let old_items = state.build_menu_items();
+    let mut tray = KsniTray {
+        state: Arc::new(Mutex::new(state)),
+    };
+
+    click(&old_items[0], &mut tray);
+    assert_eq!(
+        events.try_recv(),
+        Ok(TrayEvent::MenuActivated("open".to_string()))
+    );
+
+    // Rebuild the menu with the same IDs while the host still shows the old one
+    {
+        let mut state = tray.state.lock().unwrap();
+        state.clear_menu();
+        for item in test_menu() {
+            state.menu.push(item);
+        }
+    }
+    let stats = tray.state.lock().unwrap().stats.clone();
+    let dropped = stats.events_dropped.load(Ordering::Relaxed);
+
+    click(&old_items[0], &mut tray);
+    click(&old_items[1], &mut tray);
+    assert!(events.try_recv().is_err());
+    assert!(!is_checked(&tray, "autostart"));
+    assert_eq!(stats.events_dropped.load(Ordering::Relaxed), dropped + 2);
+
+    let new_items = tray.state.lock().unwrap().build_menu_items();
+    click(&new_items[1], &mut tray);
+    assert_eq!(
+        events.try_recv(),
+        Ok(TrayEvent::CheckmarkToggled("autostart".to_string(), true))
+    );
+    assert!(is_checked(&tray, "autostart"));
+}
+
+#[test]
+fn callbacks_of_a_replaced_menu_are_ignored() {
+    let (mut state, events) = TrayState::with_event_channel("generation_tray".to_string());
+    state.set_menu(test_menu());
+    let old_items = state.build_menu_items();
+    let mut tray = KsniTray {
+        state: Arc::new(Mutex::new(state)),
+    };
+
+    tray.state.lock().unwrap().set_menu(test_menu());
+    click(&old_items[0], &mut tray);
+    assert!(events.try_recv().is_err());
+}
+
+#[test]
+fn rebuilding_replaces_the_menu_and_its_groups() {
+    let (mut state, events) = TrayState::with_event_channel("generation_tray".to_string());
+    state.set_menu(test_menu());
+    state
+        .menu_groups
+        .insert("section".to_string(), vec!["open".to_string()]);
+    let old_items = state.build_menu_items();
+    let mut tray = KsniTray {
+        state: Arc::new(Mutex::new(state)),
+    };
+
+    tray.state
+        .lock()
+        .unwrap()
+        .rebuild_from_items(test_menu()[1..].to_vec());
+    click(&old_items[0], &mut tray);
+    assert!(events.try_recv().is_err());
+
+    let state = tray.state.lock().unwrap();
+    assert_eq!(state.menu.len(), 1);
+    assert!(state.find_item("open").is_none());
+    assert!(state.menu_groups.is_empty());
+}
+
+#[test]
+fn migrating_keeps_the_menu_and_its_toggles() {
+    let (mut state, events) = TrayState::with_event_channel("generation_tray".to_string());
+    state.set_menu(test_menu());
+    state.title_base = "Old Title".to_string();
+    state.icon_name = "folder".to_string();
+    state.text_direction = ksni::TextDirection::RightToLeft;
+    let old_items = state.build_menu_items();
+    let mut tray = KsniTray {
+        state: Arc::new(Mutex::new(state)),
+    };
+    click(&old_items[1], &mut tray);
+    assert!(events.try_recv().is_ok());
+
+    tray.state.lock().unwrap().migrate_state(true, false);
+    {
+        let state = tray.state.lock().unwrap();
+        assert_eq!(state.tray_id, "generation_tray");
+        assert_eq!(state.title_base, "Tray Icon");
+        assert_eq!(state.icon_name, "application-x-executable");
+        assert_eq!(state.text_direction, ksni::TextDirection::RightToLeft);
+    }
+    assert!(is_checked(&tray, "autostart"));
+
+    // The host still shows the same menu, so its callbacks keep working
+    click(&old_items[0], &mut tray);
+    assert_eq!(
+        events.try_recv(),
+        Ok(TrayEvent::MenuActivated("open".to_string()))
+    );
+}
+
+#[test]
+fn migrating_without_the_menu_invalidates_its_callbacks() {
+    let (mut state, events) = TrayState::with_event_channel("generation_tray".to_string());
+    state.set_menu(test_menu());
+    state.icon_name = "folder".to_string();
+    let old_items = state.build_menu_items();
+    let mut tray = KsniTray {
+        state: Arc::new(Mutex::new(state)),
+    };
+
+    tray.state.lock().unwrap().migrate_state(false, true);
+    assert!(tray.state.lock().unwrap().menu.is_empty());
+    assert_eq!(tray.state.lock().unwrap().icon_name, "folder");
+
+    click(&old_items[0], &mut tray);
+    assert!(events.try_recv().is_err());
+}
+
+#[test]
+fn freeing_the_node_during_activations_drops_events_without_panicking() {
+    const CLICKS: usize = 2000;
+
+    let (mut state, events) = TrayState::with_event_channel("generation_tray".to_string());
+    state.set_menu(test_menu());
+    let stats = state.stats.clone();
+    let state = Arc::new(Mutex::new(state));
+    let started = Arc::new(Barrier::new(2));
+    let freed = Arc::new(Barrier::new(2));
+
+    // The host keeps clicking the menu on the ksni thread
+    let host = {
+        let mut tray = KsniTray {
+            state: Arc::clone(&state),
+        };
+        let (started, freed) = (Arc::clone(&started), Arc::clone(&freed));
+        thread::spawn(move || {
+            let items = tray.state.lock().unwrap().build_menu_items();
+            started.wait();
+            for i in 0..CLICKS {
+                click(&items[i % 2], &mut tray);
+            }
+            // The host still shows the menu after the node is gone
+            freed.wait();
+            for i in 0..CLICKS {
+                click(&items[i % 2], &mut tray);
+            }
+        })
+    };
+
+    // The node is freed in the order of `TrayIcon::shutdown_tray()`, minus the ksni
+    // handle: the receiver goes away, then the sender, then the state.
+    started.wait();
+    drop(events);
+    state.lock().unwrap().event_sender = None;
+    drop(state);
+    freed.wait();
+
+    host.join()
+        .expect("activations must not panic after the node is freed");
+    let sent = stats.events_sent.load(Ordering::Relaxed);
+    let dropped = stats.events_dropped.load(Ordering::Relaxed);
+    assert_eq!(sent + dropped, 2 * CLICKS as u64);
+    assert!(dropped >= CLICKS as u64);
+}
+
+#[test]
+#[allow(deprecated)]
+fn deprecated_recursive_helpers_search_submenus() {
+    let mut submenu = test_menu();
+    submenu.push(MenuItemData::RadioGroup {
+        id: "quality".to_string(),
+        selected: 0,
+        options: vec![radio_option("low", true), radio_option("high", true)],
+        emit_on_reselect: true,
+        priority: 0,
+    });
+    let mut menu = vec![MenuItemData::SubMenu {
+        label: "Settings".to_string(),
+        icon_name: String::new(),
+        enabled: true,
+        visible: true,
+        submenu,
+        priority: 0,
+    }];
+
+    assert!(TrayState::find_item_mut_recursive(&mut menu, "open").is_some());
+    assert!(TrayState::find_item_mut_recursive(&mut menu, "missing").is_none());
+    assert_eq!(
+        TrayState::find_and_toggle_checkmark_recursive(&mut menu, "autostart"),
+        Some(true)
+    );
+    assert_eq!(
+        TrayState::find_and_select_radio_recursive(&mut menu, "quality", 1),
+        Some("high".to_string())
+    );
+    assert_eq!(
+        TrayState::find_and_select_radio_recursive(&mut menu, "quality", 2),
+        None
+    );
+}
+
+#[test]
+fn setters_reach_toggles_inside_submenus() {
+    let mut state = TrayState::new("submenu_tray".to_string());
+    let mut submenu = test_menu();
+    submenu.push(MenuItemData::RadioGroup {
+        id: "quality".to_string(),
+        selected: 0,
+        options: vec![radio_option("low", true)],
+        emit_on_reselect: true,
+        priority: 0,
+    });
+    state.set_menu(vec![MenuItemData::SubMenu {
+        label: "Settings".to_string(),
+        icon_name: String::new(),
+        enabled: true,
+        visible: true,
+        submenu,
+        priority: 0,
+    }]);
+
+    assert!(matches!(state.set_checkmark("autostart", true), Ok(true)));
+    assert!(matches!(state.set_checkmark("autostart", true), Ok(false)));
+    assert!(matches!(
+        state.set_checkmark("open", true),
+        Err(Error::ItemNotFound { .. })
+    ));
+    assert!(
+        state
+            .add_radio_option("quality", radio_option("high", true))
+            .is_ok()
+    );
+    assert!(matches!(
+        state.add_radio_option("autostart", radio_option("high", true)),
+        Err(Error::ItemNotFound { .. })
+    ));
+    assert_eq!(
+        state.find_and_select_radio("quality", 1),
+        Some("high".to_string())
+    );
+    assert_eq!(
+        state.current_toggle_states(),
+        [
+            TrayEvent::CheckmarkToggled("autostart".to_string(), true),
+            TrayEvent::RadioSelected("quality".to_string(), 1, "high".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn nested_submenus_are_found_by_label() {
+    let submenu = |label: &str, submenu| MenuItemData::SubMenu {
+        label: label.to_string(),
+        icon_name: String::new(),
+        enabled: true,
+        visible: true,
+        submenu,
+        priority: 0,
+    };
+    let mut state = TrayState::new("nested_tray".to_string());
+    state.set_menu(vec![
+        submenu("File", test_menu()),
+        submenu("Settings", vec![submenu("Saves", Vec::new())]),
+    ]);
+
+    state
+        .find_submenu_items_mut("Saves")
+        .unwrap()
+        .push(MenuItemData::Separator);
+    assert!(state.find_submenu_items_mut("File").is_some());
+    assert!(state.find_submenu_items_mut("Open").is_none());
+    assert!(matches!(
+        state.find_submenu("Saves"),
+        Some(MenuItemData::SubMenu { submenu, .. }) if submenu.len() == 1
+    ));
+}
+
+#[test]
+fn pushing_a_duplicate_id_is_rejected() {
+    let mut state = TrayState::new("duplicate_tray".to_string());
+    state.set_menu(test_menu());
+
+    assert!(matches!(
+        state.push_item(test_menu().remove(1)),
+        Err(Error::DuplicateId { id }) if id == "autostart"
+    ));
+    assert!(state.push_item(MenuItemData::Separator).is_ok());
+    assert_eq!(state.menu.len(), 3);
+}
+
+#[test]
+fn emitted_path_ids_resolve_through_the_whole_path() {
+    let standard = |id: &str, label: &str| MenuItemData::Standard {
+        id: id.to_string(),
+        label: label.to_string(),
+        icon_name: String::new(),
+        enabled: true,
+        visible: true,
+        priority: 0,
+        suppress_global: false,
+    };
+    let mut state = TrayState::new("path_tray".to_string());
+    state.set_menu(vec![
+        standard("open", "Open Game"),
+        MenuItemData::SubMenu {
+            label: "File".to_string(),
+            icon_name: String::new(),
+            enabled: true,
+            visible: true,
+            submenu: vec![
+                standard("open", "Open File"),
+                standard("recent/1", "Recent"),
+            ],
+            priority: 0,
+        },
+    ]);
+    state.use_path_ids = true;
+
+    let label = |emitted_id| state.find_item_by_emitted_id(emitted_id)?.label();
+    assert_eq!(label("open"), Some("Open Game"));
+    assert_eq!(label("File/open"), Some("Open File"));
+    assert_eq!(label("File/recent/1"), Some("Recent"));
+    assert_eq!(label("recent/1"), None);
+    assert_eq!(label("Edit/open"), None);
+
+    state.use_path_ids = false;
+    assert_eq!(
+        state
+            .find_item_by_emitted_id("recent/1")
+            .and_then(|item| item.label()),
+        Some("Recent")
+    );
+}
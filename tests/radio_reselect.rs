@@ -0,0 +1,82 @@
+//! Tests for the two radio re-select modes, and how they interact with programmatic
+//! selection.
+
+use godot_ksni::{MenuItemData, RadioItemData, TrayEvent, TrayState};
+use std::sync::mpsc::Receiver;
+
+fn quality_tray(emit_on_reselect: bool) -> (TrayState, Receiver<TrayEvent>) {
+    let (mut state, events) = TrayState::with_event_channel("radio_tray".to_string());
+    let option = |id: &str| RadioItemData {
+        id: id.to_string(),
+        label: id.to_string(),
+        icon_name: String::new(),
+        enabled: true,
+        visible: true,
+    };
+    state.set_menu(vec![MenuItemData::RadioGroup {
+        id: "quality".to_string(),
+        selected: 0,
+        options: vec![option("low"), option("high")],
+        emit_on_reselect,
+        priority: 0,
+    }]);
+    (state, events)
+}
+
+fn selected(index: usize, option_id: &str) -> TrayEvent {
+    TrayEvent::RadioSelected("quality".to_string(), index, option_id.to_string())
+}
+
+#[test]
+fn reselecting_emits_again_by_default() {
+    let (mut state, events) = quality_tray(true);
+
+    assert!(!state.is_radio_reselect_suppressed("quality", 0));
+    state.click_radio("quality", 0);
+    state.click_radio("quality", 0);
+
+    assert_eq!(
+        events.try_iter().collect::<Vec<_>>(),
+        [selected(0, "low"), selected(0, "low"),]
+    );
+}
+
+#[test]
+fn reselecting_is_ignored_when_suppressed() {
+    let (mut state, events) = quality_tray(false);
+
+    assert!(state.is_radio_reselect_suppressed("quality", 0));
+    assert!(!state.is_radio_reselect_suppressed("quality", 1));
+    state.click_radio("quality", 0);
+    state.click_radio("quality", 1);
+    state.click_radio("quality", 1);
+
+    assert_eq!(events.try_iter().collect::<Vec<_>>(), [selected(1, "high")]);
+}
+
+#[test]
+fn programmatic_selection_moves_the_suppressed_option() {
+    let (mut state, events) = quality_tray(false);
+
+    assert_eq!(
+        state.find_and_select_radio("quality", 1),
+        Some("high".to_string())
+    );
+    assert_eq!(state.find_and_select_radio("quality", 2), None);
+    assert!(events.try_recv().is_err());
+
+    state.click_radio("quality", 1);
+    state.click_radio("quality", 0);
+
+    assert_eq!(events.try_iter().collect::<Vec<_>>(), [selected(0, "low")]);
+}
+
+#[test]
+fn programmatic_selection_does_not_suppress_by_default() {
+    let (mut state, events) = quality_tray(true);
+
+    state.find_and_select_radio("quality", 1);
+    state.click_radio("quality", 1);
+
+    assert_eq!(events.try_iter().collect::<Vec<_>>(), [selected(1, "high")]);
+}
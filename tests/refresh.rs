@@ -0,0 +1,17 @@
+//! Tests for detecting whether the tray state changed since the last refresh.
+
+use godot_ksni::TrayState;
+
+#[test]
+fn unchanged_state_needs_no_refresh() {
+    let mut state = TrayState::new("refresh_tray".to_string());
+    let mut last_hash = None;
+
+    assert!(state.update_content_hash(&mut last_hash));
+    assert_eq!(last_hash, Some(state.content_hash()));
+    assert!(!state.update_content_hash(&mut last_hash));
+
+    state.set_title_base("Changed");
+    assert!(state.update_content_hash(&mut last_hash));
+    assert!(!state.update_content_hash(&mut last_hash));
+}
@@ -0,0 +1,48 @@
+//! Tests for mapping activation positions onto screens.
+
+use godot_ksni::tray::screen::{self, ScreenPosition, ScreenRect};
+
+/// A 1920x1080 screen with a 2560x1440 screen to its right, aligned at the bottom.
+const SCREENS: [ScreenRect; 2] = [
+    ScreenRect {
+        x: 0,
+        y: 360,
+        width: 1920,
+        height: 1080,
+    },
+    ScreenRect {
+        x: 1920,
+        y: 0,
+        width: 2560,
+        height: 1440,
+    },
+];
+
+fn position(screen: usize, x: i32, y: i32) -> Option<ScreenPosition> {
+    Some(ScreenPosition { screen, x, y })
+}
+
+#[test]
+fn positions_on_a_screen_are_made_relative_to_it() {
+    assert_eq!(screen::locate(100, 1420, &SCREENS), position(0, 100, 1060));
+    assert_eq!(screen::locate(4470, 10, &SCREENS), position(1, 2550, 10));
+    // Screen edges belong to the screen starting there.
+    assert_eq!(screen::locate(1920, 0, &SCREENS), position(1, 0, 0));
+    assert_eq!(screen::locate(0, 360, &SCREENS), position(0, 0, 0));
+}
+
+#[test]
+fn positions_off_screen_are_clamped_onto_the_nearest_screen() {
+    // Above the shorter screen, and past the right edge of the desktop.
+    assert_eq!(screen::locate(500, 0, &SCREENS), position(0, 500, 0));
+    assert_eq!(
+        screen::locate(5000, 1500, &SCREENS),
+        position(1, 2559, 1439)
+    );
+    assert_eq!(screen::locate(-30, 900, &SCREENS), position(0, 0, 540));
+}
+
+#[test]
+fn no_screens_give_no_position() {
+    assert_eq!(screen::locate(10, 10, &[]), None);
+}
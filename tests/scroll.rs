@@ -0,0 +1,69 @@
+//! Tests for accumulating scroll deltas into steps.
+
+use godot_ksni::tray::scroll::{ScrollAccumulator, ScrollOrientation};
+
+use ScrollOrientation::{Horizontal, Vertical};
+
+fn push_all(accumulator: &mut ScrollAccumulator, deltas: &[(i32, ScrollOrientation)]) -> Vec<i32> {
+    deltas
+        .iter()
+        .map(|&(delta, orientation)| accumulator.push(delta, orientation))
+        .collect()
+}
+
+#[test]
+fn raw_mode_passes_deltas_through() {
+    let mut accumulator = ScrollAccumulator::new(0);
+    assert_eq!(
+        push_all(
+            &mut accumulator,
+            &[(1, Vertical), (-120, Vertical), (7, Horizontal)]
+        ),
+        [1, -120, 7]
+    );
+}
+
+#[test]
+fn small_deltas_add_up_to_whole_steps() {
+    // A touchpad delivering a wheel notch of 120 as small deltas
+    let mut accumulator = ScrollAccumulator::new(120);
+    let steps = push_all(&mut accumulator, &[(30, Vertical); 9]);
+    assert_eq!(steps, [0, 0, 0, 1, 0, 0, 0, 1, 0]);
+
+    // A large delta emits several steps and keeps the remainder
+    assert_eq!(accumulator.push(270, Vertical), 2);
+    assert_eq!(accumulator.push(60, Vertical), 1);
+}
+
+#[test]
+fn orientations_accumulate_separately() {
+    let mut accumulator = ScrollAccumulator::new(2);
+    assert_eq!(
+        push_all(
+            &mut accumulator,
+            &[
+                (1, Vertical),
+                (1, Horizontal),
+                (1, Vertical),
+                (1, Horizontal)
+            ]
+        ),
+        [0, 0, 1, 1]
+    );
+}
+
+#[test]
+fn reversing_discards_the_remainder() {
+    let mut accumulator = ScrollAccumulator::new(3);
+    assert_eq!(
+        push_all(
+            &mut accumulator,
+            &[(2, Vertical), (-1, Vertical), (-2, Vertical)]
+        ),
+        [0, 0, -1]
+    );
+
+    accumulator.push(2, Vertical);
+    accumulator.reset();
+    assert_eq!(accumulator.push(1, Vertical), 0);
+}
@@ -0,0 +1,75 @@
+//! Tests for abandoning a tray registration when the StatusNotifierWatcher hangs, or
+//! when the spawn thread dies.
+//!
+//! The hung watcher test launches its own `dbus-daemon` and serves a watcher that never
+//! answers `RegisterStatusNotifierItem`, like a watcher process that is stuck. It is
+//! skipped when `dbus-daemon` is not installed.
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::PrivateBus;
+use godot_ksni::{Error, KsniTray, TrayState};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use zbus::blocking::connection;
+
+/// StatusNotifierWatcher whose registration method never returns.
+struct HungWatcher;
+
+#[zbus::interface(name = "org.kde.StatusNotifierWatcher")]
+impl HungWatcher {
+    async fn register_status_notifier_item(&self, _service: &str) {
+        std::future::pending::<()>().await;
+    }
+}
+
+#[test]
+fn spawn_gives_up_on_a_hung_watcher() {
+    let Some(bus) = PrivateBus::launch() else {
+        eprintln!("dbus-daemon not available, skipping spawn timeout test");
+        return;
+    };
+
+    // SAFETY: the other test in this binary does not use the environment, and no other
+    // thread reads it before the bus address is set.
+    unsafe { std::env::set_var("DBUS_SESSION_BUS_ADDRESS", &bus.address) };
+
+    let _watcher = connection::Builder::session()
+        .unwrap()
+        .serve_at("/StatusNotifierWatcher", HungWatcher)
+        .unwrap()
+        .name("org.kde.StatusNotifierWatcher")
+        .unwrap()
+        .build()
+        .expect("hung watcher should connect");
+
+    let tray = KsniTray {
+        state: Arc::new(Mutex::new(TrayState::new("hung_tray".to_string()))),
+    };
+    let started = Instant::now();
+    let result = tray.spawn_with_timeout(Duration::from_millis(200));
+
+    assert!(matches!(result, Err(Error::Timeout)));
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn spawn_reports_a_panicking_spawn_thread() {
+    let state = Arc::new(Mutex::new(TrayState::new("poisoned_tray".to_string())));
+
+    // A callback that panicked while holding the lock poisons the state, so building
+    // the menu panics on the spawn thread before any D-Bus call.
+    let poisoner = Arc::clone(&state);
+    let _ = std::thread::spawn(move || {
+        let _state = poisoner.lock().unwrap();
+        panic!("callback panicked");
+    })
+    .join();
+
+    let tray = KsniTray { state };
+    let result = tray.spawn_with_timeout(Duration::from_secs(5));
+
+    assert!(matches!(result, Err(Error::SpawnPanicked)));
+}
@@ -0,0 +1,34 @@
+//! Tests for replacing a tooltip that has an icon pixmap.
+
+use godot_ksni::TrayState;
+
+fn tray_with_tooltip_texture() -> TrayState {
+    let mut state = TrayState::new("tooltip_tray".to_string());
+    // What `set_tooltip_from_dict()` stores for a Dictionary with an `icon_texture`
+    state.set_tooltip("My Game", "Connected", "");
+    state.tooltip_icon_pixmap = vec![ksni::Icon {
+        width: 1,
+        height: 1,
+        data: vec![255, 0, 128, 255],
+    }];
+    state
+}
+
+#[test]
+fn setting_the_tooltip_removes_the_texture() {
+    let mut state = tray_with_tooltip_texture();
+    assert_eq!(state.tooltip().icon_pixmap.len(), 1);
+
+    state.set_tooltip("My Game", "Offline", "network-offline");
+    let tooltip = state.tooltip();
+    assert!(tooltip.icon_pixmap.is_empty());
+    assert_eq!(tooltip.icon_name, "network-offline");
+}
+
+#[test]
+fn setting_a_rich_tooltip_removes_the_texture() {
+    let mut state = tray_with_tooltip_texture();
+
+    state.set_tooltip_rich("My Game", "<b>Offline</b>");
+    assert!(state.tooltip().icon_pixmap.is_empty());
+}
@@ -0,0 +1,100 @@
+//! Tests for validating and sanitizing tray IDs.
+
+use godot_ksni::tray::state::MAX_TRAY_ID_LEN;
+use godot_ksni::{Error, TrayState};
+
+fn validate(tray_id: &str) -> String {
+    TrayState::validate_tray_id(tray_id).unwrap()
+}
+
+#[test]
+fn valid_ids_are_unchanged() {
+    assert_eq!(validate("my_app"), "my_app");
+    assert_eq!(validate("com.example.MyApp"), "com.example.MyApp");
+    assert_eq!(validate("my-app_2"), "my-app_2");
+}
+
+#[test]
+fn ids_without_letters_or_digits_are_rejected() {
+    for tray_id in ["", "   ", "...", "!?", "日本語"] {
+        assert!(matches!(
+            TrayState::validate_tray_id(tray_id),
+            Err(Error::InvalidTrayId { .. })
+        ));
+    }
+}
+
+#[test]
+fn leading_digits_are_prefixed() {
+    assert_eq!(validate("2048"), "_2048");
+    assert_eq!(validate("com.2048.game"), "com._2048.game");
+}
+
+#[test]
+fn invalid_characters_are_replaced() {
+    assert_eq!(validate("My App!"), "My_App_");
+    assert_eq!(validate("café ☕ bar"), "caf_bar");
+    assert_eq!(validate("org..example."), "org.example");
+}
+
+#[test]
+fn long_ids_are_truncated() {
+    let tray_id = validate(&"a".repeat(1000));
+    assert_eq!(tray_id.len(), MAX_TRAY_ID_LEN);
+
+    // Truncating must not leave a trailing dot or an empty element.
+    let tray_id = validate(&format!("{}.b", "a".repeat(MAX_TRAY_ID_LEN - 1)));
+    assert_eq!(tray_id, "a".repeat(MAX_TRAY_ID_LEN - 1));
+}
+
+#[test]
+fn sanitized_app_names_are_valid() {
+    let names = [
+        "My Cool Game!".to_string(),
+        "2048".to_string(),
+        "Ünïcode Game".to_string(),
+        "com.example.my-game".to_string(),
+        "x".repeat(300),
+        format!("{} b", "a".repeat(MAX_TRAY_ID_LEN - 1)),
+        format!("9{}", "z".repeat(MAX_TRAY_ID_LEN)),
+    ];
+    for name in &names {
+        let tray_id = TrayState::sanitize_tray_id(name);
+        assert!(!tray_id.is_empty(), "{name:?}");
+        assert_eq!(validate(&tray_id), tray_id, "{name:?}");
+    }
+
+    assert_eq!(TrayState::sanitize_tray_id("My Cool Game!"), "my_cool_game");
+    assert_eq!(TrayState::sanitize_tray_id("2048"), "_2048");
+    assert_eq!(
+        TrayState::sanitize_tray_id("com.example.my-game"),
+        "com_example_my_game"
+    );
+    assert_eq!(TrayState::sanitize_tray_id("!?"), "");
+}
+
+#[test]
+fn empty_app_names_fall_back_to_the_defaults() {
+    let defaults = TrayState::default();
+    for name in ["", "   \t\n", "!?.,;:-"] {
+        let state = TrayState::from_app_name(name);
+        assert_eq!(state.tray_id, defaults.tray_id, "{name:?}");
+        if name.trim().is_empty() {
+            assert_eq!(state.title_base, defaults.title_base);
+        }
+    }
+
+    // Punctuation is still a usable title
+    assert_eq!(TrayState::from_app_name("!?.,;:-").title_base, "!?.,;:-");
+}
+
+#[test]
+fn long_app_names_are_truncated_to_a_valid_id() {
+    let name = format!("  {}  ", "Long Game Name ".repeat(50));
+    let state = TrayState::from_app_name(&name);
+
+    assert_eq!(state.title_base, name.trim());
+    assert!(state.tray_id.len() <= MAX_TRAY_ID_LEN);
+    assert!(state.tray_id.starts_with("long_game_name_long"));
+    assert_eq!(validate(&state.tray_id), state.tray_id);
+}
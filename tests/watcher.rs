@@ -0,0 +1,85 @@
+//! Tests for detecting the StatusNotifierWatcher before spawning.
+//!
+//! The presence test launches its own `dbus-daemon` and is skipped when it is not
+//! installed. Before that, it checks the timeout against a bus that never answers.
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::PrivateBus;
+use godot_ksni::Error;
+use godot_ksni::tray::watcher::{self, WATCHER_NAME, WATCHER_PATH};
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixListener};
+use std::time::{Duration, Instant};
+use zbus::blocking::connection;
+
+/// StatusNotifierWatcher without any behavior, only owning the name and recommending
+/// an icon size.
+struct IdleWatcher;
+
+#[zbus::interface(name = "org.kde.StatusNotifierWatcher")]
+impl IdleWatcher {
+    #[zbus(property)]
+    fn icon_size(&self) -> u32 {
+        48
+    }
+}
+
+#[test]
+fn watcher_presence_follows_the_bus_name() {
+    // A socket nobody accepts on stalls the connection, like a hung bus daemon.
+    let silent_name = format!("godot_ksni_silent_bus_{}", std::process::id());
+    let _silent = UnixListener::bind_addr(&SocketAddr::from_abstract_name(&silent_name).unwrap())
+        .expect("silent socket should bind");
+    // SAFETY: the other test in this binary does not use the environment, and no other
+    // thread reads it while the bus address is set.
+    unsafe {
+        std::env::set_var(
+            "DBUS_SESSION_BUS_ADDRESS",
+            format!("unix:abstract={silent_name}"),
+        )
+    };
+
+    let started = Instant::now();
+    let result = watcher::is_present_within(Duration::from_millis(200));
+    assert!(matches!(result, Err(Error::Timeout)), "{result:?}");
+    let result = watcher::preferred_icon_size_within(Duration::from_millis(200));
+    assert!(matches!(result, Err(Error::Timeout)), "{result:?}");
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    let Some(bus) = PrivateBus::launch() else {
+        eprintln!("dbus-daemon not available, skipping watcher test");
+        return;
+    };
+
+    // SAFETY: as above; the abandoned check only read the environment before connecting.
+    unsafe { std::env::set_var("DBUS_SESSION_BUS_ADDRESS", &bus.address) };
+
+    assert_eq!(watcher::is_present(), Some(false));
+    assert_eq!(watcher::preferred_icon_size(), None);
+
+    let _watcher = connection::Builder::session()
+        .unwrap()
+        .serve_at(WATCHER_PATH, IdleWatcher)
+        .unwrap()
+        .name(WATCHER_NAME)
+        .unwrap()
+        .build()
+        .expect("watcher should connect");
+
+    assert_eq!(watcher::is_present(), Some(true));
+    assert_eq!(watcher::preferred_icon_size(), Some(48));
+    assert_eq!(
+        watcher::preferred_icon_size_within(Duration::from_secs(5)).ok(),
+        Some(Some(48))
+    );
+}
+
+#[test]
+fn missing_watcher_hint_depends_on_the_desktop() {
+    assert!(watcher::missing_watcher_hint("ubuntu:GNOME").contains("AppIndicator"));
+    assert!(watcher::missing_watcher_hint("KDE").contains("Plasma"));
+    assert!(watcher::missing_watcher_hint("").contains("StatusNotifierItem"));
+}
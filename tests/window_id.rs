@@ -0,0 +1,20 @@
+//! Tests for reporting the window ID to the host.
+
+use godot_ksni::TrayState;
+
+#[test]
+fn window_ids_outside_the_property_range_are_not_reported() {
+    let mut state = TrayState::new("window_tray".to_string());
+    assert_eq!(state.sni_window_id(), 0);
+
+    state.window_id = Some(0x0460_0007);
+    assert_eq!(state.sni_window_id(), 0x0460_0007);
+
+    state.window_id = Some(i64::from(i32::MAX));
+    assert_eq!(state.sni_window_id(), i32::MAX);
+
+    for window_id in [i64::from(i32::MAX) + 1, 1 << 40, -1, i64::MIN] {
+        state.window_id = Some(window_id);
+        assert_eq!(state.sni_window_id(), 0, "{window_id}");
+    }
+}